@@ -1,25 +1,36 @@
 use crate::{
-    cube::Cube,
-    history,
+    config::Config,
+    error::ParseError,
+    history::{self, Imported},
     inspection::Inspection,
-    scramble::Scramble,
-    session::Session,
-    stats::{get_avg, StatEntry, Stats},
+    puzzle::{Preview, Puzzle},
+    scramble::{PendingScramble, Scramble},
+    session::{Session, SessionInfo, SessionSettings},
+    stats::{self, Calendar, GlobalStats, SessionSummary, StatEntry, StatKind, Stats},
     timer::Timer,
+    transfer::{self, ExportFormat},
 };
 use anyhow::Result;
+use chrono::{Local, NaiveDate, TimeDelta};
 use crossterm::terminal;
 use std::{
-    cmp::Ordering,
-    ffi::OsStr,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    path::Path,
+    time::{Duration, Instant},
 };
 
-pub const SCRAMBLE_LENGTH: u8 = 25;
+// numbers of last solves the chart can be zoomed to, from the widest
+const CHART_ZOOMS: [usize; 4] = [100, 50, 25, 12];
+// widths the buckets of the histogram can be set to, in milliseconds
+const BUCKET_WIDTHS: [u128; 9] = [100, 250, 500, 1000, 2000, 5000, 10000, 30000, 60000];
 
 pub enum AppState {
     Idle,
     SolveInfo,
+    Histogram,
+    PbHistory,
+    AverageDetail,
+    GlobalStats,
+    Calendar,
     Set,
     Ready,
     Solving,
@@ -30,124 +41,228 @@ pub enum Confirmation {
     Session,
 }
 
+/// Setting of the session being typed in.
+pub enum Prompt {
+    Rename,
+    Stats,
+    Thresholds,
+    /// Path of a csTimer or Twisty Timer export to add the sessions of.
+    Import,
+    /// Path to export the session to, in the format told by its extension.
+    Export,
+}
+
+pub struct Input {
+    pub prompt: Prompt,
+    pub text: String,
+}
+
 pub struct App {
     pub timer: Timer,
     pub inspection: Inspection,
-    pub scramble: Scramble,
+    /// Scramble to solve, unset while it is being generated.
+    pub scramble: Option<Scramble>,
     pub last_scramble: Option<Scramble>,
+    /// Scramble generated ahead of time, so that the next one is ready once
+    /// the solve is over.
+    next_scramble: Option<PendingScramble>,
     pub session: Session,
-    pub available_sessions: Vec<bool>,
+    /// Sessions in the order of the session manifest.
+    pub sessions: Vec<SessionInfo>,
     pub selected_session_idx: usize,
-    pub cube_preview: Cube,
+    pub preview: Preview,
     pub state: AppState,
-    pub inspection_enabled: bool,
-    pub inspection_warning_enabled: bool,
+    pub config: Config,
+    /// PBs set by the last solve, shown for a while after it.
+    pub pb_flash: Option<(String, Instant)>,
+    /// Line of the stats whose PBs are listed in the PB history.
+    pub pb_history_kind: StatKind,
+    /// Tracked stat whose solves are listed in the average detail.
+    pub detail_stat: usize,
+    /// Whether the average detail lists the solves of the best value of the
+    /// stat rather than of the current one.
+    pub detail_best: bool,
+    /// Stats of all the sessions of the puzzle, read when they are shown.
+    pub global_stats: Option<GlobalStats>,
+    /// Solves per day of all the sessions of the puzzle, read when they are
+    /// shown.
+    pub calendar: Option<Calendar>,
+    /// Day selected in the calendar.
+    pub calendar_day: NaiveDate,
+    pub chart_enabled: bool,
+    /// Number of last solves the chart shows, all of them if unset.
+    pub chart_zoom: Option<usize>,
     pub confirmation: Option<Confirmation>,
+    pub input: Option<Input>,
+    pub error: Option<String>,
+    /// Outcome of an action that takes no time to notice otherwise, shown
+    /// until a key is pressed.
+    pub notice: Option<String>,
     pub supports_keyboard_enhancement: bool,
 }
 
 impl App {
     pub fn new() -> Result<Self> {
+        let config = Config::load()?;
+        let manifest = history::read_manifest()?;
+        let preview = Preview::new(Puzzle::default(), None);
+
         let mut app = Self {
             timer: Timer::new(),
-            inspection: Inspection::new(),
-            scramble: Scramble::new(SCRAMBLE_LENGTH),
+            inspection: Inspection::new(&config),
+            scramble: None,
             last_scramble: None,
+            next_scramble: None,
             session: Session::default(),
-            available_sessions: Vec::default(),
+            sessions: manifest.sessions,
             selected_session_idx: 0,
-            cube_preview: Cube::new(),
+            preview,
             state: AppState::Idle,
-            inspection_enabled: true,
-            inspection_warning_enabled: true,
+            config,
+            pb_flash: None,
+            pb_history_kind: StatKind::Single,
+            detail_stat: 0,
+            detail_best: false,
+            global_stats: None,
+            calendar: None,
+            calendar_day: Local::now().date_naive(),
+            chart_enabled: true,
+            chart_zoom: None,
             confirmation: None,
+            input: None,
+            error: None,
+            notice: None,
             supports_keyboard_enhancement: terminal::supports_keyboard_enhancement()?,
         };
 
-        app.generate_scramble_preview();
+        if app.sessions.is_empty() {
+            let info = app.new_session_info()?;
+            app.sessions.push(info);
+            app.save_manifest()?;
+        }
 
-        for session_file in history::get_sessions_list()? {
-            let Some(idx) = session_file
-                .file_stem()
-                .and_then(OsStr::to_str)
-                .and_then(|s| s.split_once('_'))
-                .and_then(|s| s.1.parse::<usize>().ok())
-                .and_then(|num| num.checked_sub(1))
-            else {
-                continue;
-            };
+        app.load_session()?;
+        app.poll_scramble();
 
-            match idx.cmp(&app.available_sessions.len()) {
-                Ordering::Less => app.available_sessions[idx] = true,
-                Ordering::Equal => app.available_sessions.push(true),
-                Ordering::Greater => {
-                    for _ in 0..(idx - app.available_sessions.len()) {
-                        app.available_sessions.push(false);
-                    }
-                    app.available_sessions.push(true);
-                }
-            }
-        }
+        if !manifest.errors.is_empty() {
+            let errors = manifest.errors.join("\n");
 
-        if app.available_sessions.is_empty() {
-            app.available_sessions.push(false);
-        } else if app.available_sessions[0] {
-            app.load_session()?;
+            app.error = Some(match app.error.take() {
+                Some(error) => format!("{errors}\n{error}"),
+                None => errors,
+            });
         }
 
         Ok(app)
     }
 
     pub fn load_session(&mut self) -> Result<()> {
-        let path = history::get_session_history_file(&format!(
-            "session_{}.csv",
-            self.selected_session_idx + 1
-        ))?;
-
-        let mut session = history::read_history(path)?;
-
-        let mut start = 0;
-        let mut end = 4;
-
-        while session.solves.len() > end {
-            let slice = &session.solves[start..=end];
-            let avg_of_5 = get_avg(slice, 5);
-            session.solves[end].avg_of_5 = avg_of_5;
-            start += 1;
-            end += 1;
-        }
-
-        start = 0;
-        end = 11;
+        let info = &self.sessions[self.selected_session_idx];
+        let path = history::get_session_history_file(&info.file)?;
+
+        // the history file is only written once a solve is added
+        let mut session = if !path.exists() {
+            Session {
+                puzzle: info.puzzle,
+                ..Session::default()
+            }
+        } else {
+            match history::read_history(path) {
+                Ok(session) => session,
+                Err(e) => {
+                    self.error = Some(format!("{e:#}"));
+
+                    Session {
+                        is_corrupted: true,
+                        puzzle: info.puzzle,
+                        ..Session::default()
+                    }
+                }
+            }
+        };
 
-        while session.solves.len() > end {
-            let slice = &session.solves[start..=end];
-            let avg_of_12 = get_avg(slice, 12);
-            session.solves[end].avg_of_12 = avg_of_12;
-            start += 1;
-            end += 1;
+        // the solves in the file tell the puzzle of the session best
+        if session.solves.is_empty() {
+            session.puzzle = info.puzzle;
+        } else {
+            self.sessions[self.selected_session_idx].puzzle = session.puzzle;
         }
 
-        session.update_stats();
+        session.tracked = self.session_info().settings.stats.clone();
+        session.recompute();
         session.select_first();
+        self.set_session(session);
+
+        Ok(())
+    }
+
+    fn set_session(&mut self, session: Session) {
+        let is_same_puzzle = session.puzzle == self.session.puzzle;
         self.session = session;
 
+        if !is_same_puzzle {
+            self.reset_scrambles();
+        }
+    }
+
+    fn select_session(&mut self, idx: usize) -> Result<()> {
+        let (puzzle, length) = (self.session.puzzle, self.settings().scramble_length);
+
+        self.selected_session_idx = idx;
+        self.load_session()?;
+
+        if self.session.puzzle == puzzle && self.settings().scramble_length != length {
+            self.reset_scrambles();
+        }
+
         Ok(())
     }
 
+    /// Entry for a new session, with a history file of its own and the
+    /// puzzle currently being timed.
+    fn new_session_info(&self) -> Result<SessionInfo> {
+        Ok(SessionInfo {
+            file: history::new_session_file(&self.sessions)?,
+            name: format!("Session {}", self.sessions.len() + 1),
+            puzzle: self.session.puzzle,
+            created: history::now(),
+            settings: self.config.session_settings(),
+        })
+    }
+
+    fn save_manifest(&self) -> Result<()> {
+        history::write_manifest(&self.sessions)
+    }
+
+    pub fn session_info(&self) -> &SessionInfo {
+        &self.sessions[self.selected_session_idx]
+    }
+
+    pub fn settings(&self) -> &SessionSettings {
+        &self.session_info().settings
+    }
+
     pub fn delete_session(&mut self) -> Result<()> {
         if matches!(self.confirmation, Some(Confirmation::Session)) {
-            let path = history::get_session_history_file(&format!(
-                "session_{}.csv",
-                self.selected_session_idx + 1
-            ))?;
+            let path = history::get_session_history_file(&self.session_info().file)?;
 
-            std::fs::remove_file(path)?;
-            self.session = Session::default();
-            self.available_sessions[self.selected_session_idx] = false;
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+
+            self.sessions.remove(self.selected_session_idx);
+
+            // there is always a session to time solves in
+            if self.sessions.is_empty() {
+                let info = self.new_session_info()?;
+                self.sessions.push(info);
+            }
+
+            self.save_manifest()?;
+            self.select_session(self.selected_session_idx.min(self.sessions.len() - 1))?;
 
             self.confirmation = None;
-        } else if self.available_sessions[self.selected_session_idx] {
+        } else {
             self.confirmation = Some(Confirmation::Session);
         }
 
@@ -155,38 +270,384 @@ impl App {
     }
 
     pub fn next_session(&mut self) -> Result<()> {
-        self.selected_session_idx += 1;
+        if self.selected_session_idx + 1 == self.sessions.len() {
+            // a session without solves at the end is already there to be used
+            if self.get_solves().is_empty() && !self.session.is_corrupted {
+                return Ok(());
+            }
 
-        if self.selected_session_idx == self.available_sessions.len() {
-            self.available_sessions.push(false);
-            self.session = Session::default();
-        } else if self.available_sessions[self.selected_session_idx] {
-            self.load_session()?;
-        } else {
-            self.session = Session::default();
+            let info = self.new_session_info()?;
+            self.sessions.push(info);
+            self.save_manifest()?;
         }
 
-        Ok(())
+        self.select_session(self.selected_session_idx + 1)
     }
 
     pub fn previous_session(&mut self) -> Result<()> {
-        self.selected_session_idx = self.selected_session_idx.saturating_sub(1);
+        if self.selected_session_idx == 0 {
+            return Ok(());
+        }
 
-        if self.available_sessions[self.selected_session_idx] {
-            self.load_session()?;
+        self.select_session(self.selected_session_idx - 1)
+    }
+
+    /// Moves the current session the given number of places later in the
+    /// order of the sessions, or earlier if negative.
+    pub fn move_session(&mut self, by: isize) -> Result<()> {
+        let Some(idx) = self
+            .selected_session_idx
+            .checked_add_signed(by)
+            .filter(|idx| *idx < self.sessions.len())
+        else {
+            return Ok(());
+        };
+
+        let info = self.sessions.remove(self.selected_session_idx);
+        self.sessions.insert(idx, info);
+        self.selected_session_idx = idx;
+
+        self.save_manifest()
+    }
+
+    /// Starts typing in the setting, starting from its current value.
+    pub fn start_input(&mut self, prompt: Prompt) {
+        let text = match prompt {
+            Prompt::Rename => self.session_info().name.clone(),
+            Prompt::Stats => stats::join_tracked_stats(&self.settings().stats),
+            Prompt::Thresholds => stats::join_thresholds(&self.settings().thresholds),
+            Prompt::Import | Prompt::Export => String::new(),
+        };
+
+        self.input = Some(Input { prompt, text });
+    }
+
+    pub fn submit_input(&mut self) -> Result<()> {
+        let Some(input) = self.input.take() else {
+            return Ok(());
+        };
+
+        match input.prompt {
+            Prompt::Rename => self.rename_session(input.text.trim()),
+            Prompt::Stats => self.set_tracked_stats(&input.text),
+            Prompt::Thresholds => self.set_thresholds(&input.text),
+            Prompt::Import => self.import_sessions(input.text.trim()),
+            Prompt::Export => self.export_session(input.text.trim()),
+        }
+    }
+
+    fn export_session(&mut self, path: &str) -> Result<()> {
+        let name = self.session_info().name.as_str();
+        let format = ExportFormat::from_path(Path::new(path));
+        let text = transfer::export(&[(name, &self.session)], format)?;
+
+        match std::fs::write(path, text) {
+            Ok(()) => {
+                self.notice = Some(format!(
+                    "Exported {} solves to {path}",
+                    self.session.solves.len()
+                ));
+            }
+            Err(e) => self.error = Some(format!("Couldn't write {path}: {e}")),
+        }
+
+        Ok(())
+    }
+
+    /// Adds the sessions of an export of another timer after the other
+    /// sessions and switches to the first of them.
+    fn import_sessions(&mut self, path: &str) -> Result<()> {
+        let Imported { sessions, skipped } =
+            match transfer::import(Path::new(path), self.session.puzzle) {
+                Ok(imported) => imported,
+                Err(e) => {
+                    self.error = Some(format!("{e:#}"));
+                    return Ok(());
+                }
+            };
+        let skipped = if skipped.is_empty() {
+            String::new()
         } else {
-            self.session = Session::default();
+            format!(
+                "\nSkipped the sessions of puzzles that aren't supported: {}",
+                skipped.join(", ")
+            )
+        };
+
+        if sessions.is_empty() {
+            self.error = Some(format!("{path} has no solves to import{skipped}"));
+            return Ok(());
+        }
+
+        let first = self.sessions.len();
+        history::import_sessions(
+            &mut self.sessions,
+            &sessions,
+            &self.config.session_settings(),
+        )?;
+        self.select_session(first)?;
+
+        let solve_count = sessions
+            .iter()
+            .map(|session| session.solves.len())
+            .sum::<usize>();
+        let unparsed = sessions
+            .iter()
+            .map(|session| session.unparsed_scrambles)
+            .sum::<usize>();
+
+        let mut notice = format!(
+            "Imported {solve_count} solves in {} session{}",
+            sessions.len(),
+            if sessions.len() == 1 { "" } else { "s" }
+        );
+        if unparsed > 0 {
+            notice +=
+                &format!(", {unparsed} of them without their scramble, which couldn't be read");
         }
+        self.notice = Some(notice + &skipped);
 
         Ok(())
     }
 
-    pub fn get_solves(&self) -> &Vec<Solve> {
-        &self.session.solves
+    fn rename_session(&mut self, name: &str) -> Result<()> {
+        if name.is_empty() {
+            self.error = Some(String::from("Session names can't be empty"));
+            return Ok(());
+        }
+
+        self.sessions[self.selected_session_idx].name = name.to_string();
+        self.save_manifest()
     }
 
-    pub fn get_mut_solves(&mut self) -> &mut Vec<Solve> {
-        &mut self.session.solves
+    fn set_tracked_stats(&mut self, text: &str) -> Result<()> {
+        let tracked = match stats::parse_tracked_stats(text) {
+            Ok(tracked) => tracked,
+            Err(e) => {
+                self.error = Some(e.to_string());
+                return Ok(());
+            }
+        };
+
+        self.sessions[self.selected_session_idx].settings.stats = tracked.clone();
+        self.session.tracked = tracked;
+        self.session.recompute();
+
+        self.save_manifest()
+    }
+
+    fn set_thresholds(&mut self, text: &str) -> Result<()> {
+        let thresholds = match stats::parse_thresholds(text) {
+            Ok(thresholds) => thresholds,
+            Err(e) => {
+                self.error = Some(e.to_string());
+                return Ok(());
+            }
+        };
+
+        self.sessions[self.selected_session_idx].settings.thresholds = thresholds;
+        self.save_manifest()
+    }
+
+    /// Changes the width of the buckets of the histogram by a number of steps
+    /// through the available widths.
+    pub fn change_bucket_width(&mut self, by: isize) -> Result<()> {
+        let settings = &mut self.sessions[self.selected_session_idx].settings;
+        let current = BUCKET_WIDTHS
+            .iter()
+            .position(|&width| width >= settings.bucket_width)
+            .unwrap_or(BUCKET_WIDTHS.len() - 1);
+        let idx = current
+            .saturating_add_signed(by)
+            .min(BUCKET_WIDTHS.len() - 1);
+
+        settings.bucket_width = BUCKET_WIDTHS[idx];
+        self.save_manifest()
+    }
+
+    /// Short name of the line of the stats, like `single` or `ao5`.
+    pub fn stat_name(&self, kind: StatKind) -> String {
+        match kind {
+            StatKind::Single => String::from("single"),
+            StatKind::Tracked(idx) => self.session.tracked[idx].stat.to_string(),
+        }
+    }
+
+    /// Switches the PB history to the next or previous line of the stats.
+    pub fn cycle_pb_history(&mut self, by: isize) {
+        let kinds = StatKind::all(self.session.tracked.len()).collect::<Vec<StatKind>>();
+        let idx = kinds
+            .iter()
+            .position(|&kind| kind == self.pb_history_kind)
+            .unwrap_or(0);
+
+        self.pb_history_kind = kinds[(idx as isize + by).rem_euclid(kinds.len() as isize) as usize];
+    }
+
+    /// Reads the history of every session of the puzzle to show the stats of
+    /// all of them, compared by the stats tracked in the current session.
+    /// Calls `f` with the name of every session of the puzzle and the
+    /// session, with the stats of the loaded one computed. Returns the names
+    /// of the sessions whose history couldn't be read.
+    fn for_each_puzzle_session(&self, mut f: impl FnMut(&str, &Session)) -> Vec<String> {
+        let mut unreadable = Vec::new();
+
+        for (idx, info) in self.sessions.iter().enumerate() {
+            if info.puzzle != self.session.puzzle {
+                continue;
+            }
+
+            // the loaded session may have solves that couldn't be saved
+            if idx == self.selected_session_idx {
+                f(&info.name, &self.session);
+                continue;
+            }
+
+            let Ok(mut session) = history::read_session(info) else {
+                unreadable.push(info.name.clone());
+                continue;
+            };
+
+            session.tracked = self.session.tracked.clone();
+            session.recompute();
+            f(&info.name, &session);
+        }
+
+        unreadable
+    }
+
+    pub fn show_global_stats(&mut self) -> Result<()> {
+        let mut sessions = Vec::new();
+        let unreadable = self.for_each_puzzle_session(|name, session| {
+            sessions.push(SessionSummary::new(name, session));
+        });
+
+        self.global_stats = Some(GlobalStats {
+            stats: self
+                .session
+                .tracked
+                .iter()
+                .map(|tracked| tracked.stat)
+                .collect(),
+            sessions,
+            unreadable,
+        });
+        self.state = AppState::GlobalStats;
+
+        Ok(())
+    }
+
+    pub fn show_calendar(&mut self) {
+        let mut calendar = Calendar::default();
+        let unreadable = self.for_each_puzzle_session(|_, session| {
+            calendar.add(&session.solves);
+        });
+        calendar.unreadable = unreadable;
+
+        self.calendar = Some(calendar);
+        self.calendar_day = Local::now().date_naive();
+        self.state = AppState::Calendar;
+    }
+
+    /// Moves the day selected in the calendar, which can't go past today.
+    pub fn move_calendar_day(&mut self, days: i64) {
+        let today = Local::now().date_naive();
+
+        if let Some(day) = self.calendar_day.checked_add_signed(TimeDelta::days(days)) {
+            self.calendar_day = day.min(today);
+        }
+    }
+
+    /// Opens the average detail, if the session tracks any stat.
+    pub fn show_average_detail(&mut self) {
+        if !self.session.tracked.is_empty() {
+            self.detail_stat = self.detail_stat.min(self.session.tracked.len() - 1);
+            self.state = AppState::AverageDetail;
+        }
+    }
+
+    /// Switches the average detail to the next or previous tracked stat.
+    pub fn cycle_average_detail(&mut self, by: isize) {
+        let count = self.session.tracked.len() as isize;
+        self.detail_stat = (self.detail_stat as isize + by).rem_euclid(count) as usize;
+    }
+
+    /// Shows fewer of the last solves in the chart.
+    pub fn zoom_chart_in(&mut self) {
+        self.chart_zoom = match self.chart_zoom {
+            None => Some(CHART_ZOOMS[0]),
+            Some(zoom) => CHART_ZOOMS
+                .iter()
+                .find(|&&next| next < zoom)
+                .copied()
+                .or(Some(zoom)),
+        };
+    }
+
+    /// Shows more of the last solves in the chart, up to all of them.
+    pub fn zoom_chart_out(&mut self) {
+        self.chart_zoom = self
+            .chart_zoom
+            .and_then(|zoom| CHART_ZOOMS.iter().rev().find(|&&next| next > zoom).copied());
+    }
+
+    pub fn toggle_inspection(&mut self) -> Result<()> {
+        let settings = &mut self.sessions[self.selected_session_idx].settings;
+        settings.inspection = !settings.inspection;
+
+        // new sessions follow the last choice
+        self.config.inspection_enabled = settings.inspection;
+
+        self.save_manifest()?;
+        self.save_config();
+
+        Ok(())
+    }
+
+    pub fn toggle_inspection_warning(&mut self) {
+        self.config.inspection_warning_enabled = !self.config.inspection_warning_enabled;
+        self.save_config();
+    }
+
+    /// Writes the toggled settings to the config file, which only shows the
+    /// error if it fails, as the file may have been edited since it was read.
+    fn save_config(&mut self) {
+        if let Err(e) = self.config.save() {
+            self.error = Some(format!("{e:#}"));
+        }
+    }
+
+    /// Changes the length of the scrambles of the session by the given
+    /// number of moves, if the puzzle uses random move scrambles.
+    pub fn change_scramble_length(&mut self, by: isize) -> Result<()> {
+        let Some(default) = self.config.scramble_length(self.session.puzzle) else {
+            return Ok(());
+        };
+
+        let settings = &mut self.sessions[self.selected_session_idx].settings;
+        let length = settings
+            .scramble_length
+            .unwrap_or(default)
+            .saturating_add_signed(by)
+            .max(1);
+        settings.scramble_length = (length != default).then_some(length);
+
+        self.save_manifest()?;
+        self.generate_scramble();
+
+        Ok(())
+    }
+
+    /// Number of moves in the scrambles of the session, if the puzzle uses
+    /// random move scrambles.
+    pub fn scramble_length(&self) -> Option<usize> {
+        self.settings()
+            .scramble_length
+            .or(self.config.scramble_length(self.session.puzzle))
+    }
+
+    pub fn get_solves(&self) -> &Vec<Solve> {
+        &self.session.solves
     }
 
     pub fn get_stats(&self) -> &Stats {
@@ -194,21 +655,82 @@ impl App {
     }
 
     pub fn generate_scramble(&mut self) {
-        self.last_scramble = Some(std::mem::replace(
-            &mut self.scramble,
-            Scramble::new(SCRAMBLE_LENGTH),
-        ));
+        if let Some(scramble) = self.scramble.take() {
+            self.last_scramble = Some(scramble);
+        }
+
+        self.poll_scramble();
         self.generate_scramble_preview();
     }
 
-    pub fn generate_scramble_preview(&mut self) {
-        self.cube_preview = Cube::new();
+    /// Shows the scramble generated ahead of time once it is ready, and
+    /// starts generating the one after it. Does nothing while there is a
+    /// scramble to solve.
+    pub fn poll_scramble(&mut self) {
+        if self.scramble.is_some() {
+            return;
+        }
+
+        let (puzzle, length) = (self.session.puzzle, self.scramble_length());
+
+        let pending = match self.next_scramble.take() {
+            Some(pending) if pending.is_for(puzzle, length) => pending,
+            _ => PendingScramble::spawn(puzzle, length),
+        };
 
-        for r#move in &self.scramble.moves {
-            self.cube_preview.apply_move(r#move);
+        match pending.try_take() {
+            Some(scramble) => {
+                self.scramble = Some(scramble);
+                self.next_scramble = Some(PendingScramble::spawn(puzzle, length));
+                self.generate_scramble_preview();
+            }
+            None => self.next_scramble = Some(pending),
         }
     }
 
+    pub fn next_puzzle(&mut self) -> Result<()> {
+        self.switch_puzzle(self.session.puzzle.next())
+    }
+
+    pub fn previous_puzzle(&mut self) -> Result<()> {
+        self.switch_puzzle(self.session.puzzle.previous())
+    }
+
+    fn switch_puzzle(&mut self, puzzle: Puzzle) -> Result<()> {
+        if puzzle == self.session.puzzle {
+            return Ok(());
+        }
+
+        // a session only holds solves of a single puzzle
+        if !self.get_solves().is_empty() || self.session.is_corrupted {
+            self.error = Some(format!(
+                "'{}' is for {} solves, switch to an empty session to time another puzzle",
+                self.session_info().name,
+                self.session.puzzle
+            ));
+            return Ok(());
+        }
+
+        self.session.puzzle = puzzle;
+        self.sessions[self.selected_session_idx].puzzle = puzzle;
+        self.reset_scrambles();
+
+        self.save_manifest()
+    }
+
+    /// Drops the scrambles made for another puzzle or length than the ones of
+    /// the session, generating a new one.
+    fn reset_scrambles(&mut self) {
+        self.scramble = None;
+        self.last_scramble = None;
+        self.poll_scramble();
+        self.generate_scramble_preview();
+    }
+
+    pub fn generate_scramble_preview(&mut self) {
+        self.preview = Preview::new(self.session.puzzle, self.scramble.as_ref());
+    }
+
     pub fn start_timer(&mut self) {
         self.timer.start();
         self.state = AppState::Solving;
@@ -218,7 +740,6 @@ impl App {
         self.timer.stop();
         self.state = AppState::Idle;
         self.add_solve()?;
-        self.session.update_stats_on_new();
         self.generate_scramble_preview();
 
         Ok(())
@@ -235,38 +756,36 @@ impl App {
     }
 
     pub fn add_solve(&mut self) -> Result<()> {
-        let penalty = if self.inspection_enabled {
+        let penalty = if self.settings().inspection {
             self.inspection.penalty
         } else {
             Penalty::Ok
         };
 
-        let solve = Solve::new(
-            self.timer.result,
-            penalty,
-            None,
-            None,
-            std::mem::replace(&mut self.scramble, Scramble::new(SCRAMBLE_LENGTH)),
-        );
+        let scramble = self
+            .scramble
+            .take()
+            .unwrap_or_else(|| Scramble::empty(self.session.puzzle));
+        let solve = Solve::new(self.timer.result, penalty, scramble);
+        self.poll_scramble();
 
         history::add_to_history(
-            history::get_session_history_file(&format!(
-                "session_{}.csv",
-                self.selected_session_idx + 1
-            ))?,
+            history::get_session_history_file(&self.session_info().file)?,
             &solve,
+            self.session.puzzle,
         )?;
-        self.available_sessions[self.selected_session_idx] = true;
-
-        self.get_mut_solves().push(solve);
 
-        let avg_of_5 = get_avg(self.get_solves(), 5);
-        let avg_of_12 = get_avg(self.get_solves(), 12);
+        let pbs = self.session.push(solve);
 
-        let solve = self.get_mut_solves().last_mut().unwrap();
+        if !pbs.is_empty() {
+            let last = self.session.solves.last().unwrap();
+            let pbs = pbs
+                .iter()
+                .map(|&kind| format!("{} {}", self.stat_name(kind), last.stat(kind).unwrap()))
+                .collect::<Vec<String>>();
 
-        solve.avg_of_5 = avg_of_5;
-        solve.avg_of_12 = avg_of_12;
+            self.pb_flash = Some((format!("New PB: {}", pbs.join(", ")), Instant::now()));
+        }
 
         Ok(())
     }
@@ -274,8 +793,7 @@ impl App {
     pub fn delete_selected_solve(&mut self) -> Result<()> {
         if matches!(self.confirmation, Some(Confirmation::Solve)) {
             if let Some(idx) = self.session.selected_idx() {
-                self.session.solves.remove(idx);
-                self.session.update_around(idx + 1);
+                self.session.remove(idx);
                 self.state = AppState::Idle;
             }
 
@@ -293,17 +811,16 @@ impl App {
             return Ok(());
         };
 
-        let solve = &mut self.session.solves[idx];
-
-        if matches!(solve.time.penalty, Penalty::PlusTwo) {
-            solve.time.penalty = Penalty::Ok;
-            solve.time.time -= 2000;
-        } else {
-            solve.time.penalty = Penalty::PlusTwo;
-            solve.time.time += 2000;
-        }
+        self.session.edit(idx, |solve| {
+            if matches!(solve.time.penalty, Penalty::PlusTwo) {
+                solve.time.penalty = Penalty::Ok;
+                solve.time.time -= 2000;
+            } else {
+                solve.time.penalty = Penalty::PlusTwo;
+                solve.time.time += 2000;
+            }
+        });
 
-        self.session.update_around(idx);
         self.rewrite_history_file()
     }
 
@@ -312,28 +829,33 @@ impl App {
             return Ok(());
         };
 
-        let solve = &mut self.session.solves[idx];
-
-        solve.time.penalty = match solve.time.penalty {
-            Penalty::Ok => Penalty::Dnf,
-            Penalty::PlusTwo => {
-                solve.time.time -= 2000;
-                Penalty::Dnf
-            }
-            Penalty::Dnf => Penalty::Ok,
-        };
+        self.session.edit(idx, |solve| {
+            solve.time.penalty = match solve.time.penalty {
+                Penalty::Ok => Penalty::Dnf,
+                Penalty::PlusTwo => {
+                    solve.time.time -= 2000;
+                    Penalty::Dnf
+                }
+                Penalty::Dnf => Penalty::Ok,
+            };
+        });
 
-        self.session.update_around(idx);
         self.rewrite_history_file()
     }
 
-    fn rewrite_history_file(&self) -> Result<()> {
+    fn rewrite_history_file(&mut self) -> Result<()> {
+        // the file still holds the solves that couldn't be loaded
+        if self.session.is_corrupted {
+            self.error = Some(String::from(
+                "Changes to this session can't be saved until its history file is fixed",
+            ));
+            return Ok(());
+        }
+
         history::update_history(
-            history::get_session_history_file(&format!(
-                "session_{}.csv",
-                self.selected_session_idx + 1
-            ))?,
+            history::get_session_history_file(&self.session_info().file)?,
             self.get_solves(),
+            self.session.puzzle,
         )
     }
 }
@@ -345,56 +867,66 @@ pub enum Penalty {
     Dnf,
 }
 
-impl From<u8> for Penalty {
-    fn from(value: u8) -> Self {
-        match value {
+impl TryFrom<u8> for Penalty {
+    type Error = ParseError;
+
+    fn try_from(value: u8) -> Result<Self, ParseError> {
+        Ok(match value {
             0 => Self::Ok,
             1 => Self::PlusTwo,
             2 => Self::Dnf,
-            _ => panic!(),
-        }
+            _ => return Err(ParseError::Penalty(value)),
+        })
     }
 }
 
 #[derive(Clone)]
 pub struct Solve {
     pub time: StatEntry,
-    pub avg_of_5: Option<StatEntry>,
-    pub avg_of_12: Option<StatEntry>,
+    /// Tracked stats of the session ending at the solve, in the order they
+    /// are set.
+    pub averages: Vec<Option<StatEntry>>,
     pub scramble: Scramble,
     pub date: u64,
+    pub comment: String,
 }
 
 impl Solve {
-    fn new(
-        time: Duration,
-        penalty: Penalty,
-        avg_of_5: Option<StatEntry>,
-        avg_of_12: Option<StatEntry>,
-        scramble: Scramble,
-    ) -> Self {
+    fn new(time: Duration, penalty: Penalty, scramble: Scramble) -> Self {
         Self {
             time: StatEntry::new(time.as_millis(), penalty),
-            avg_of_5,
-            avg_of_12,
+            averages: Vec::new(),
             scramble,
-            date: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            date: history::now(),
+            comment: String::new(),
         }
     }
 
-    pub fn from_history_file(time: u128, penalty: u8, scramble: &str, date: u64) -> Self {
-        let time = StatEntry::new(time, penalty.into());
-        let scramble: Scramble = scramble.into();
-
-        Self {
+    pub fn from_history_file(
+        time: u128,
+        penalty: u8,
+        scramble: &str,
+        date: u64,
+        comment: &str,
+        puzzle: Puzzle,
+    ) -> Result<Self, ParseError> {
+        let time = StatEntry::new(time, penalty.try_into()?);
+        let scramble = Scramble::parse(puzzle, scramble)?;
+
+        Ok(Self {
             time,
-            avg_of_5: None,
-            avg_of_12: None,
+            averages: Vec::new(),
             scramble,
             date,
+            comment: comment.to_string(),
+        })
+    }
+
+    /// Value of the line of the stats at the solve.
+    pub fn stat(&self, kind: StatKind) -> Option<StatEntry> {
+        match kind {
+            StatKind::Single => Some(self.time),
+            StatKind::Tracked(idx) => self.averages[idx],
         }
     }
 }
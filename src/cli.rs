@@ -0,0 +1,347 @@
+use anyhow::{bail, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+use crate::{
+    app::{Penalty, Solve},
+    config::Config,
+    history,
+    puzzle::Puzzle,
+    scramble::Scramble,
+    session::{Session, SessionInfo},
+    stats::{Distribution, StatEntry, StatKind},
+    timer::{self, millis_to_string_not_running},
+    transfer::{self, ExportFormat},
+};
+
+/// Speedcubing timer for the terminal, which opens the timer unless a
+/// command is given.
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Print random scrambles, one per line
+    Scramble {
+        /// WCA id of the puzzle, like 333, 444, pyram, minx, skewb, sq1 or
+        /// clock
+        #[arg(long, default_value = "333")]
+        puzzle: Puzzle,
+        #[arg(long, default_value_t = 1)]
+        count: usize,
+        /// Number of moves of random move scrambles
+        #[arg(long)]
+        length: Option<usize>,
+    },
+    /// Print the stats of sessions
+    Stats {
+        /// Name of the session, all of them if left out
+        #[arg(long)]
+        session: Option<String>,
+        #[arg(long, value_enum, default_value_t = StatsFormat::Text)]
+        format: StatsFormat,
+    },
+    /// Add a solve to a session
+    Add {
+        /// Time in seconds or in minutes and seconds, like 12.345 or 1:05.2
+        time: String,
+        /// Penalty of the solve: ok, +2 or dnf
+        #[arg(long, default_value = "ok", value_parser = parse_penalty)]
+        penalty: Penalty,
+        /// Scramble the solve was done with
+        #[arg(long, default_value = "")]
+        scramble: String,
+        /// Name of the session, the last one if left out
+        #[arg(long)]
+        session: Option<String>,
+    },
+    /// Add the sessions of a csTimer or Twisty Timer export
+    Import {
+        /// csTimer `.json` export or Twisty Timer `.csv` backup
+        file: PathBuf,
+        /// Puzzle of the solves of Twisty Timer exports in the external
+        /// format, which don't tell it
+        #[arg(long, default_value = "333")]
+        puzzle: Puzzle,
+    },
+    /// Export sessions to csTimer, Twisty Timer or a text report
+    Export {
+        /// Name of the session to export, all of them if left out
+        #[arg(long)]
+        session: Option<String>,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Cstimer)]
+        format: ExportFormat,
+        /// File to write to, standard output if left out
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum StatsFormat {
+    Text,
+    Json,
+}
+
+fn parse_penalty(value: &str) -> Result<Penalty, String> {
+    match value.to_lowercase().as_str() {
+        "ok" => Ok(Penalty::Ok),
+        "+2" => Ok(Penalty::PlusTwo),
+        "dnf" => Ok(Penalty::Dnf),
+        _ => Err(String::from("expected ok, +2 or dnf")),
+    }
+}
+
+pub fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Scramble {
+            puzzle,
+            count,
+            length,
+        } => {
+            let length = length.or(Config::load()?.scramble_length(puzzle));
+
+            for _ in 0..count {
+                let scramble = Scramble::new(puzzle, length);
+                println!("{}", scramble.to_string().replace('\n', " "));
+            }
+
+            Ok(())
+        }
+        Command::Stats { session, format } => stats(session.as_deref(), format),
+        Command::Add {
+            time,
+            penalty,
+            scramble,
+            session,
+        } => add(&time, penalty, &scramble, session.as_deref()),
+        Command::Import { file, puzzle } => {
+            let imported = transfer::import(&file, puzzle)?;
+            let mut sessions = read_manifest()?;
+            history::import_sessions(
+                &mut sessions,
+                &imported.sessions,
+                &Config::load()?.session_settings(),
+            )?;
+
+            for session in &imported.sessions {
+                println!(
+                    "Imported {} solves of {} in {}",
+                    session.solves.len(),
+                    session.puzzle,
+                    session.name
+                );
+            }
+
+            for session in &imported.skipped {
+                eprintln!("Skipped {session}, whose puzzle isn't supported");
+            }
+
+            Ok(())
+        }
+        Command::Export {
+            session,
+            format,
+            output,
+        } => export(session.as_deref(), format, output),
+    }
+}
+
+/// Sessions of the manifest, reporting the lines of it that couldn't be read.
+fn read_manifest() -> Result<Vec<SessionInfo>> {
+    let manifest = history::read_manifest()?;
+
+    for error in &manifest.errors {
+        eprintln!("{error}");
+    }
+
+    Ok(manifest.sessions)
+}
+
+/// Sessions with the name, or all of them if there is none.
+fn find_sessions(name: Option<&str>) -> Result<Vec<SessionInfo>> {
+    let sessions = read_manifest()?
+        .into_iter()
+        .filter(|info| name.is_none_or(|name| info.name == name))
+        .collect::<Vec<SessionInfo>>();
+
+    if let (Some(name), true) = (name, sessions.is_empty()) {
+        bail!("There is no session named {name}");
+    }
+
+    Ok(sessions)
+}
+
+/// Reads the sessions with the name, or all of them if there is none, with
+/// the stats they track computed.
+fn read_sessions(name: Option<&str>) -> Result<Vec<(SessionInfo, Session)>> {
+    let mut sessions = Vec::new();
+
+    for info in find_sessions(name)? {
+        let mut session = history::read_session(&info)?;
+        session.tracked = info.settings.stats.clone();
+        session.recompute();
+
+        sessions.push((info, session));
+    }
+
+    Ok(sessions)
+}
+
+fn stats(name: Option<&str>, format: StatsFormat) -> Result<()> {
+    let sessions = read_sessions(name)?;
+
+    match format {
+        StatsFormat::Text => {
+            let text = sessions
+                .iter()
+                .map(|(info, session)| stats_text(info, session))
+                .collect::<Vec<String>>();
+
+            println!("{}", text.join("\n\n"));
+        }
+        StatsFormat::Json => {
+            let json = sessions
+                .iter()
+                .map(|(info, session)| stats_json(info, session))
+                .collect::<Vec<Value>>();
+
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Name of each line of the stats of the session, from the single.
+fn stat_names(session: &Session) -> Vec<(StatKind, String)> {
+    StatKind::all(session.tracked.len())
+        .map(|kind| {
+            let name = match kind {
+                StatKind::Single => String::from("single"),
+                StatKind::Tracked(idx) => session.tracked[idx].stat.to_string(),
+            };
+
+            (kind, name)
+        })
+        .collect()
+}
+
+fn stats_text(info: &SessionInfo, session: &Session) -> String {
+    let entry =
+        |entry: Option<StatEntry>| entry.map_or(String::from("-"), |entry| entry.to_string());
+
+    let mut lines = vec![
+        format!("{} ({})", info.name, session.puzzle),
+        format!(
+            "solves: {}/{}",
+            session.stats.valid_solve_count, session.stats.solve_count
+        ),
+    ];
+
+    if let Some(distribution) = Distribution::new(&session.solves, &[], info.settings.bucket_width)
+    {
+        lines.push(format!(
+            "mean: {}  σ: {}  median: {}",
+            millis_to_string_not_running(distribution.mean),
+            millis_to_string_not_running(distribution.std_dev),
+            millis_to_string_not_running(distribution.median)
+        ));
+    }
+
+    lines.push(format!("{:<8}{:<12}best", "", "current"));
+    for (kind, name) in stat_names(session) {
+        let line = session.stats.line(kind);
+
+        lines.push(format!(
+            "{name:<8}{:<12}{}",
+            entry(line.current()),
+            entry(line.best())
+        ));
+    }
+
+    lines.join("\n")
+}
+
+/// Stats of the session with times as they are shown, like `12.345`,
+/// `14.000+` or `DNF`, and `null` for the ones without a value.
+fn stats_json(info: &SessionInfo, session: &Session) -> Value {
+    let distribution = Distribution::new(&session.solves, &[], info.settings.bucket_width);
+    let entry = |entry: Option<StatEntry>| entry.map(|entry| entry.to_string());
+    let time = |time: fn(&Distribution) -> u128| {
+        distribution
+            .as_ref()
+            .map(|distribution| millis_to_string_not_running(time(distribution)))
+    };
+
+    let stats = stat_names(session)
+        .into_iter()
+        .map(|(kind, name)| {
+            let line = session.stats.line(kind);
+
+            json!({
+                "stat": name,
+                "current": entry(line.current()),
+                "best": entry(line.best()),
+            })
+        })
+        .collect::<Vec<Value>>();
+
+    json!({
+        "session": info.name,
+        "puzzle": session.puzzle.id(),
+        "solves": session.stats.solve_count as u64,
+        "valid_solves": session.stats.valid_solve_count as u64,
+        "mean": time(|distribution| distribution.mean),
+        "std_dev": time(|distribution| distribution.std_dev),
+        "median": time(|distribution| distribution.median),
+        "stats": stats,
+    })
+}
+
+fn add(time: &str, penalty: Penalty, scramble: &str, name: Option<&str>) -> Result<()> {
+    let Some(info) = find_sessions(name)?.pop() else {
+        bail!("There are no sessions yet");
+    };
+
+    let solve = Solve::from_history_file(
+        timer::parse_time(time)?,
+        penalty as u8,
+        scramble,
+        history::now(),
+        "",
+        info.puzzle,
+    )?;
+
+    history::add_to_history(
+        history::get_session_history_file(&info.file)?,
+        &solve,
+        info.puzzle,
+    )?;
+
+    println!("Added {} to {}", solve.time, info.name);
+
+    Ok(())
+}
+
+fn export(name: Option<&str>, format: ExportFormat, output: Option<PathBuf>) -> Result<()> {
+    let sessions = read_sessions(name)?;
+    let sessions = sessions
+        .iter()
+        .map(|(info, session)| (info.name.as_str(), session))
+        .collect::<Vec<(&str, &Session)>>();
+
+    let text = transfer::export(&sessions, format)?;
+
+    match output {
+        Some(path) => std::fs::write(path, text)?,
+        None => println!("{}", text.trim_end_matches('\n')),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,217 @@
+use crate::error::ParseError;
+use rand::Rng;
+use std::{fmt::Display, str::FromStr};
+
+/// Dials on each side of the clock, in rows from the top as seen from that
+/// side.
+pub const DIALS: usize = 9;
+
+/// Names of the pins in the order they are stored, as seen from the front.
+pub const PINS: [&str; 4] = ["UL", "UR", "DL", "DR"];
+
+// dials on the front turned along with each pin when it is up
+const PIN_DIALS: [[usize; 4]; 4] = [[0, 1, 3, 4], [1, 2, 4, 5], [3, 4, 6, 7], [4, 5, 7, 8]];
+
+// sets of pins raised by the moves of the notation, as flags in `PINS` order
+const PIN_SETS: [(&str, [bool; 4]); 9] = [
+    ("UR", [false, true, false, false]),
+    ("DR", [false, false, false, true]),
+    ("DL", [false, false, true, false]),
+    ("UL", [true, false, false, false]),
+    ("U", [true, true, false, false]),
+    ("R", [false, true, false, true]),
+    ("D", [false, false, true, true]),
+    ("L", [true, false, true, false]),
+    ("ALL", [true, true, true, true]),
+];
+
+// sets of pins, starting from `U`, that are turned again after turning the
+// puzzle over in a scramble
+const BACK_TURNS: usize = 4;
+
+#[derive(PartialEq, Clone)]
+pub enum Move {
+    /// Raises the pins of the set and turns the dials next to them
+    /// clockwise by the given hours, or counter-clockwise if negative.
+    Turn { pins: [bool; 4], hours: i8 },
+    /// Turns the puzzle over around the vertical axis, `y2`.
+    Flip,
+    /// Leaves the pin up at the end of a scramble.
+    Pin(usize),
+}
+
+impl FromStr for Move {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<Self, ParseError> {
+        if value.is_empty() {
+            return Err(ParseError::EmptyMove);
+        }
+
+        if value == "y2" {
+            return Ok(Move::Flip);
+        }
+
+        let name_len = value
+            .find(|ch: char| !ch.is_ascii_uppercase())
+            .unwrap_or(value.len());
+        let (name, rest) = value.split_at(name_len);
+
+        let Some((_, pins)) = PIN_SETS.iter().find(|(set, _)| *set == name) else {
+            return Err(ParseError::Pins(name.to_string()));
+        };
+
+        if rest.is_empty() {
+            return match PINS.iter().position(|pin| *pin == name) {
+                Some(pin) => Ok(Move::Pin(pin)),
+                None => Err(ParseError::MissingRotation),
+            };
+        }
+
+        let digits_len = rest
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let (digits, rest) = rest.split_at(digits_len);
+
+        let hours = digits
+            .parse::<i8>()
+            .ok()
+            .filter(|hours| *hours <= 6)
+            .ok_or_else(|| ParseError::Hours(digits.to_string()))?;
+
+        let mut chars = rest.chars();
+        let hours = match chars.next() {
+            Some('+') => hours,
+            Some('-') => -hours,
+            Some(ch) => return Err(ParseError::Rotation(ch)),
+            None => return Err(ParseError::MissingRotation),
+        };
+
+        let rest = chars.as_str();
+
+        if !rest.is_empty() {
+            return Err(ParseError::Trailing(rest.to_string()));
+        }
+
+        Ok(Move::Turn { pins: *pins, hours })
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Move::Turn { pins, hours } => {
+                let (name, _) = PIN_SETS.iter().find(|(_, set)| set == pins).unwrap();
+                let sign = if *hours < 0 { '-' } else { '+' };
+
+                write!(f, "{name}{}{sign}", hours.abs())
+            }
+            Move::Flip => write!(f, "y2"),
+            Move::Pin(pin) => write!(f, "{}", PINS[*pin]),
+        }
+    }
+}
+
+/// Dials and pins of a Rubik's Clock.
+///
+/// Dials hold the hour they point at, 0 being twelve o'clock. The corner
+/// dials of the two sides are geared together, so only 14 of the 18 dials
+/// are independent: turning a corner on one side turns the one behind it the
+/// other way as seen from its own side.
+#[derive(Clone)]
+pub struct Clock {
+    pub front: [u8; DIALS],
+    /// Back dials as seen from the back, so mirrored left to right.
+    pub back: [u8; DIALS],
+    /// Whether each pin sticks out of the front, listed in [`PINS`].
+    pub pins: [bool; 4],
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Self {
+            front: [0; DIALS],
+            back: [0; DIALS],
+            pins: [false; 4],
+        }
+    }
+
+    /// Applies the move. The pins are pushed back down after each turn so
+    /// that only the ones raised at the end of a scramble are left up.
+    pub fn apply_move(&mut self, r#move: &Move) {
+        match r#move {
+            Move::Turn { pins, hours } => {
+                let hours = hours.rem_euclid(12) as u8;
+
+                for dial in 0..DIALS {
+                    let turned = (0..4).any(|pin| pins[pin] && PIN_DIALS[pin].contains(&dial));
+
+                    if !turned {
+                        continue;
+                    }
+
+                    self.front[dial] = (self.front[dial] + hours) % 12;
+
+                    if is_corner(dial) {
+                        let behind = mirror(dial);
+                        self.back[behind] = (self.back[behind] + 12 - hours) % 12;
+                    }
+                }
+
+                self.pins = [false; 4];
+            }
+            Move::Flip => {
+                std::mem::swap(&mut self.front, &mut self.back);
+                self.pins = self.back_pins();
+            }
+            Move::Pin(pin) => self.pins[*pin] = true,
+        }
+    }
+
+    /// Whether each pin sticks out of the back, listed in [`PINS`] as seen
+    /// from the back. A pin sticking out of the front is pushed into the
+    /// back.
+    pub fn back_pins(&self) -> [bool; 4] {
+        std::array::from_fn(|pin| !self.pins[pin ^ 1])
+    }
+}
+
+fn is_corner(dial: usize) -> bool {
+    dial.is_multiple_of(2) && dial != 4
+}
+
+/// Dial on the other side of the clock behind the given one.
+fn mirror(dial: usize) -> usize {
+    dial / 3 * 3 + 2 - dial % 3
+}
+
+/// Random-state scramble in WCA notation: a turn of every set of pins on the
+/// front and then the back, each by a random number of hours, followed by
+/// a random selection of pins left up.
+///
+/// The turns reach every one of the 12^14 positions of the dials in exactly
+/// one way, so random hours give every position the same chance.
+pub fn random_state_scramble() -> Vec<Move> {
+    let mut rng = rand::rng();
+    let mut moves = Vec::new();
+
+    for (_, pins) in &PIN_SETS {
+        moves.push(Move::Turn {
+            pins: *pins,
+            hours: rng.random_range(-5..=6),
+        });
+    }
+
+    moves.push(Move::Flip);
+
+    for (_, pins) in &PIN_SETS[BACK_TURNS..] {
+        moves.push(Move::Turn {
+            pins: *pins,
+            hours: rng.random_range(-5..=6),
+        });
+    }
+
+    moves.extend((0..PINS.len()).filter(|_| rng.random()).map(Move::Pin));
+
+    moves
+}
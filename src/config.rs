@@ -0,0 +1,223 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::{io::ErrorKind, path::PathBuf};
+use toml_edit::{value, DocumentMut, Item};
+
+use crate::{puzzle::Puzzle, scramble, session::SessionSettings};
+
+const PACKAGE_NAME: &str = env!("CARGO_PKG_NAME");
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Settings that apply to every session, read from `config.toml` in the
+/// config directory. Settings left out of the file keep their default.
+pub struct Config {
+    /// Whether new sessions use inspection.
+    pub inspection_enabled: bool,
+    pub inspection_warning_enabled: bool,
+    /// Seconds of inspection before a solve gets a +2.
+    pub inspection_duration: u64,
+    /// Seconds of inspection at which the warnings sound, in ascending order,
+    /// which are left out if inspection is over by then.
+    pub inspection_warnings: Vec<u64>,
+    /// Frequency of the tone of each warning, in hertz.
+    pub warning_tones: Vec<f32>,
+    /// Width of the terminal above which the sessions and stats are shown
+    /// beside the timer.
+    pub wide_layout_width: u16,
+    /// Length of random move scrambles of the puzzles set in the file,
+    /// instead of their default one.
+    pub scramble_lengths: Vec<(Puzzle, usize)>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            inspection_enabled: true,
+            inspection_warning_enabled: true,
+            inspection_duration: 15,
+            inspection_warnings: vec![8, 12],
+            warning_tones: vec![425.0, 480.0],
+            wide_layout_width: 140,
+            scramble_lengths: Vec::new(),
+        }
+    }
+}
+
+fn get_config_file() -> Result<PathBuf> {
+    match dirs::config_dir() {
+        Some(path) => Ok(path.join(PACKAGE_NAME).join(CONFIG_FILE_NAME)),
+        None => bail!("Couldn't find config directory"),
+    }
+}
+
+/// Contents of the config file, empty if there is none yet.
+fn read_document() -> Result<(PathBuf, DocumentMut)> {
+    let path = get_config_file()?;
+
+    let document = match std::fs::read_to_string(&path) {
+        Ok(text) => text
+            .parse()
+            .with_context(|| format!("Couldn't parse {}", path.display()))?,
+        Err(e) if e.kind() == ErrorKind::NotFound => DocumentMut::new(),
+        Err(e) => return Err(e).with_context(|| format!("Couldn't read {}", path.display())),
+    };
+
+    Ok((path, document))
+}
+
+fn integer(integer: Option<i64>) -> Result<u64> {
+    integer
+        .and_then(|integer| u64::try_from(integer).ok())
+        .ok_or_else(|| anyhow!("expected a whole number"))
+}
+
+fn boolean(item: &Item) -> Result<bool> {
+    item.as_bool()
+        .ok_or_else(|| anyhow!("expected true or false"))
+}
+
+fn integers(item: &Item) -> Result<Vec<u64>> {
+    let array = item
+        .as_array()
+        .ok_or_else(|| anyhow!("expected a list of whole numbers"))?;
+
+    array
+        .iter()
+        .map(|value| integer(value.as_integer()))
+        .collect()
+}
+
+/// Frequencies, which may be written as integers or floats.
+fn frequencies(item: &Item) -> Result<Vec<f32>> {
+    let array = item
+        .as_array()
+        .ok_or_else(|| anyhow!("expected a list of frequencies"))?;
+
+    array
+        .iter()
+        .map(|value| {
+            value
+                .as_float()
+                .or(value.as_integer().map(|integer| integer as f64))
+                .filter(|frequency| *frequency > 0.0)
+                .map(|frequency| frequency as f32)
+                .ok_or_else(|| anyhow!("expected a list of positive frequencies"))
+        })
+        .collect()
+}
+
+fn scramble_lengths(item: &Item) -> Result<Vec<(Puzzle, usize)>> {
+    let table = item
+        .as_table_like()
+        .ok_or_else(|| anyhow!("expected a table of puzzles"))?;
+
+    table
+        .iter()
+        .map(|(id, item)| {
+            let puzzle: Puzzle = id.parse()?;
+
+            if scramble::scramble_length(puzzle).is_none() {
+                bail!("{puzzle} doesn't use random move scrambles");
+            }
+
+            match integer(item.as_integer())? {
+                0 => bail!("scramble length of {puzzle} can't be 0"),
+                length => Ok((puzzle, length as usize)),
+            }
+        })
+        .collect()
+}
+
+impl Config {
+    /// Reads the config file, or the default settings if there is none.
+    pub fn load() -> Result<Self> {
+        let (path, document) = read_document()?;
+        let mut config = Self::default();
+
+        config
+            .read(&document)
+            .with_context(|| format!("Couldn't load {}", path.display()))?;
+
+        Ok(config)
+    }
+
+    fn read(&mut self, document: &DocumentMut) -> Result<()> {
+        for (key, item) in document.iter() {
+            self.read_setting(key, item)
+                .with_context(|| format!("Couldn't read setting {key}"))?;
+        }
+
+        if self.inspection_duration == 0 {
+            bail!("inspection duration can't be 0");
+        }
+
+        if self.inspection_warnings.len() != self.warning_tones.len() {
+            bail!("each inspection warning needs a tone");
+        }
+
+        // the warnings sound in order, so each keeps its tone once sorted
+        let mut warnings = self
+            .inspection_warnings
+            .iter()
+            .copied()
+            .zip(self.warning_tones.iter().copied())
+            .collect::<Vec<(u64, f32)>>();
+        warnings.sort_by_key(|(second, _)| *second);
+        (self.inspection_warnings, self.warning_tones) = warnings.into_iter().unzip();
+
+        Ok(())
+    }
+
+    fn read_setting(&mut self, key: &str, item: &Item) -> Result<()> {
+        match key {
+            "inspection_enabled" => self.inspection_enabled = boolean(item)?,
+            "inspection_warning_enabled" => self.inspection_warning_enabled = boolean(item)?,
+            "inspection_duration" => self.inspection_duration = integer(item.as_integer())?,
+            "inspection_warnings" => self.inspection_warnings = integers(item)?,
+            "warning_tones" => self.warning_tones = frequencies(item)?,
+            "wide_layout_width" => {
+                self.wide_layout_width = u16::try_from(integer(item.as_integer())?)
+                    .map_err(|_| anyhow!("expected a width in columns"))?;
+            }
+            "scramble_length" => self.scramble_lengths = scramble_lengths(item)?,
+            _ => bail!("unknown setting"),
+        }
+
+        Ok(())
+    }
+
+    /// Writes the settings toggled in the timer to the config file, leaving
+    /// the rest of it as it is.
+    pub fn save(&self) -> Result<()> {
+        let (path, mut document) = read_document()?;
+
+        document["inspection_enabled"] = value(self.inspection_enabled);
+        document["inspection_warning_enabled"] = value(self.inspection_warning_enabled);
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        std::fs::write(path.with_extension("tmp"), document.to_string())?;
+        std::fs::rename(path.with_extension("tmp"), path)?;
+
+        Ok(())
+    }
+
+    /// Length of the random move scrambles of the puzzle, or `None` if the
+    /// puzzle doesn't use them.
+    pub fn scramble_length(&self, puzzle: Puzzle) -> Option<usize> {
+        self.scramble_lengths
+            .iter()
+            .find(|(other, _)| *other == puzzle)
+            .map(|(_, length)| *length)
+            .or(scramble::scramble_length(puzzle))
+    }
+
+    /// Settings of a new session.
+    pub fn session_settings(&self) -> SessionSettings {
+        SessionSettings {
+            inspection: self.inspection_enabled,
+            ..SessionSettings::default()
+        }
+    }
+}
@@ -0,0 +1,367 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+use crate::{
+    app::Penalty,
+    history::{self, Imported, ImportedSession},
+    puzzle::Puzzle,
+    session::Session,
+};
+
+// penalties of csTimer solves, which store the time without them
+const CSTIMER_OK: i64 = 0;
+const CSTIMER_PLUS_TWO: i64 = 2000;
+const CSTIMER_DNF: i64 = -1;
+
+/// Puzzle of a csTimer scramble type, like `333oh` or `pyrso`, which is
+/// unknown for the puzzles tsctimer doesn't support.
+fn puzzle_from_scramble_type(scramble_type: &str) -> Option<Puzzle> {
+    // the types of other puzzles like Square-2 also start with `sq`
+    if ["sqrs", "sq1h", "sq1t"].contains(&scramble_type) {
+        return Some(Puzzle::Square1);
+    }
+
+    let puzzles = [
+        ("333", Puzzle::Cube(3)),
+        ("222", Puzzle::Cube(2)),
+        ("444", Puzzle::Cube(4)),
+        ("555", Puzzle::Cube(5)),
+        ("666", Puzzle::Cube(6)),
+        ("777", Puzzle::Cube(7)),
+        ("pyr", Puzzle::Pyraminx),
+        ("mgm", Puzzle::Megaminx),
+        ("minx", Puzzle::Megaminx),
+        ("skb", Puzzle::Skewb),
+        ("clk", Puzzle::Clock),
+    ];
+
+    puzzles
+        .into_iter()
+        .find(|(prefix, _)| scramble_type.starts_with(prefix))
+        .map(|(_, puzzle)| puzzle)
+}
+
+/// Values of a csTimer export that older versions stored as JSON strings.
+fn nested(value: &Value) -> Result<Value> {
+    match value {
+        Value::String(text) => Ok(serde_json::from_str(text)?),
+        value => Ok(value.clone()),
+    }
+}
+
+/// Reads the sessions of a csTimer export, in the order csTimer lists them.
+/// Sessions without solves are left out, and so are the ones of puzzles
+/// tsctimer doesn't support, which are listed as skipped.
+pub fn read_export(path: &Path) -> Result<Imported> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Couldn't read {}", path.display()))?;
+    let export: Map<String, Value> = serde_json::from_str(&text)
+        .with_context(|| format!("{} isn't a csTimer export", path.display()))?;
+
+    // settings of the sessions, keyed by their number
+    let data = match export
+        .get("properties")
+        .and_then(|properties| properties.get("sessionData"))
+    {
+        Some(data) => nested(data).context("Couldn't read the csTimer session data")?,
+        None => Value::Null,
+    };
+
+    let mut numbers = export
+        .keys()
+        .filter_map(|key| key.strip_prefix("session")?.parse().ok())
+        .collect::<Vec<usize>>();
+
+    if numbers.is_empty() {
+        bail!("{} has no csTimer sessions", path.display());
+    }
+
+    let rank = |number: usize| {
+        data.get(number.to_string())
+            .and_then(|session| session.get("rank"))
+            .and_then(Value::as_u64)
+            .unwrap_or(number as u64)
+    };
+    numbers.sort_by_key(|&number| (rank(number), number));
+
+    let mut sessions = Vec::new();
+    let mut skipped = Vec::new();
+
+    for number in numbers {
+        let session = data.get(number.to_string());
+        let name = match session.and_then(|session| session.get("name")) {
+            Some(Value::String(name)) => name.clone(),
+            // sessions that were never renamed are named by their number
+            Some(name) => name.to_string(),
+            None => number.to_string(),
+        };
+        let solves = nested(&export[&format!("session{number}")])
+            .with_context(|| format!("Couldn't read csTimer session {name}"))?;
+        let solves = solves
+            .as_array()
+            .ok_or_else(|| anyhow!("csTimer session {name} isn't a list of solves"))?;
+
+        if solves.is_empty() {
+            continue;
+        }
+
+        // sessions that were never given a scramble type are of 3x3
+        let scramble_type = session
+            .and_then(|session| session.pointer("/opt/scrType"))
+            .and_then(Value::as_str)
+            .unwrap_or("333");
+        let Some(puzzle) = puzzle_from_scramble_type(scramble_type) else {
+            skipped.push(format!("{name} ({scramble_type})"));
+            continue;
+        };
+
+        let mut imported = ImportedSession::new(&name, puzzle);
+
+        for (idx, solve) in solves.iter().enumerate() {
+            push_solve(solve, &mut imported).with_context(|| {
+                format!("Couldn't read solve {} of csTimer session {name}", idx + 1)
+            })?;
+        }
+
+        sessions.push(imported);
+    }
+
+    Ok(Imported { sessions, skipped })
+}
+
+/// Solve stored by csTimer as `[[penalty, time], scramble, comment, date]`,
+/// where the scramble, comment and date may be missing.
+fn push_solve(value: &Value, session: &mut ImportedSession) -> Result<()> {
+    let fields = value
+        .as_array()
+        .ok_or_else(|| anyhow!("solve isn't a list"))?;
+    let result = fields
+        .first()
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("missing time"))?;
+
+    let penalty = match result.first().and_then(Value::as_i64) {
+        Some(CSTIMER_OK) => Penalty::Ok,
+        Some(CSTIMER_PLUS_TWO) => Penalty::PlusTwo,
+        Some(CSTIMER_DNF) => Penalty::Dnf,
+        _ => bail!("unknown penalty {}", result.first().unwrap_or(&Value::Null)),
+    };
+    let time = result
+        .get(1)
+        .and_then(Value::as_u64)
+        .ok_or_else(|| anyhow!("missing time"))?;
+
+    let scramble = fields.get(1).and_then(Value::as_str).unwrap_or("");
+    let comment = fields.get(2).and_then(Value::as_str).unwrap_or("");
+    let date = fields
+        .get(3)
+        .and_then(Value::as_u64)
+        .unwrap_or_else(history::now);
+
+    session.push(time.into(), penalty, scramble, date, comment)
+}
+
+/// Scramble type csTimer uses for the WCA event of the puzzle.
+fn scramble_type(puzzle: Puzzle) -> &'static str {
+    match puzzle {
+        Puzzle::Cube(2) => "222so",
+        Puzzle::Cube(3) => "333",
+        Puzzle::Cube(4) => "444wca",
+        Puzzle::Cube(5) => "555wca",
+        Puzzle::Cube(6) => "666wca",
+        Puzzle::Cube(7) => "777wca",
+        Puzzle::Cube(_) => unreachable!(),
+        Puzzle::Pyraminx => "pyrso",
+        Puzzle::Megaminx => "mgmp",
+        Puzzle::Skewb => "skbso",
+        Puzzle::Square1 => "sqrs",
+        Puzzle::Clock => "clkwca",
+    }
+}
+
+/// Export file csTimer can import, holding the sessions in order.
+pub fn export(sessions: &[(&str, &Session)]) -> Result<String> {
+    let mut export = Map::new();
+    let mut data = Map::new();
+
+    for (idx, (name, session)) in sessions.iter().enumerate() {
+        let number = idx + 1;
+        let solves = session
+            .solves
+            .iter()
+            .map(|solve| {
+                let penalty = match solve.time.penalty {
+                    Penalty::Ok => CSTIMER_OK,
+                    Penalty::PlusTwo => CSTIMER_PLUS_TWO,
+                    Penalty::Dnf => CSTIMER_DNF,
+                };
+
+                json!([
+                    [penalty, solve.time.raw_time() as u64],
+                    solve.scramble.to_string(),
+                    solve.comment,
+                    solve.date,
+                ])
+            })
+            .collect();
+
+        export.insert(format!("session{number}"), Value::Array(solves));
+        data.insert(
+            number.to_string(),
+            json!({
+                "name": name,
+                "opt": { "scrType": scramble_type(session.puzzle) },
+                "rank": number,
+            }),
+        );
+    }
+
+    // csTimer stores the session data as a string of JSON
+    export.insert(
+        String::from("properties"),
+        json!({
+            "sessionData": Value::Object(data).to_string(),
+            "session": 1,
+        }),
+    );
+
+    Ok(serde_json::to_string(&export)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // session 2 comes first by its rank, stores its solves as a string like
+    // older versions did and was never given a scramble type, session 3 is of
+    // a puzzle that isn't supported and session 4 has no solves
+    const EXPORT: &str = r#"{
+        "session1": [
+            [[0, 8500], "R U R' U'", "", 1700000300],
+            [[2000, 9000], "L' U B u'", "", 1700000400]
+        ],
+        "session2": "[[[0,12345],\"R U R' U'\",\"nice\",1700000000],[[2000,12000],\"F2\",\"\",1700000100],[[-1,9000],\"\",\"\",1700000200]]",
+        "session3": [[[0, 40000], "R L", "", 1700000500]],
+        "session4": [],
+        "properties": {
+            "sessionData": "{\"1\":{\"name\":\"Pyra\",\"opt\":{\"scrType\":\"pyrso\"},\"rank\":2},\"2\":{\"name\":\"Main\",\"opt\":{},\"rank\":1},\"3\":{\"name\":\"FTO\",\"opt\":{\"scrType\":\"fto\"},\"rank\":3},\"4\":{\"name\":\"Empty\",\"rank\":4}}"
+        }
+    }"#;
+
+    /// Writes the export to a temporary file of this test run.
+    fn export_file(name: &str, text: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "{}-{}-{name}",
+            env!("CARGO_PKG_NAME"),
+            std::process::id()
+        ));
+        std::fs::write(&path, text).unwrap();
+
+        path
+    }
+
+    fn read(name: &str, text: &str) -> Imported {
+        let path = export_file(name, text);
+        let imported = read_export(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        imported
+    }
+
+    // time with the penalty, raw time, penalty, scramble, comment and date
+    type SolveFields = (u128, u128, u8, String, String, u64);
+
+    /// Name and puzzle of each session, with the fields of its solves.
+    fn sessions(imported: &Imported) -> Vec<(String, String, Vec<SolveFields>)> {
+        imported
+            .sessions
+            .iter()
+            .map(|session| {
+                let solves = session
+                    .solves
+                    .iter()
+                    .map(|solve| {
+                        (
+                            solve.time.time,
+                            solve.time.raw_time(),
+                            solve.time.penalty as u8,
+                            solve.scramble.to_string(),
+                            solve.comment.clone(),
+                            solve.date,
+                        )
+                    })
+                    .collect();
+
+                (session.name.clone(), session.puzzle.to_string(), solves)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reads_export() {
+        let imported = read("read.json", EXPORT);
+
+        let solve = |time, raw, penalty: Penalty, scramble: &str, comment: &str, date| {
+            (
+                time,
+                raw,
+                penalty as u8,
+                scramble.to_string(),
+                comment.to_string(),
+                date,
+            )
+        };
+
+        assert_eq!(
+            sessions(&imported),
+            [
+                (
+                    String::from("Main"),
+                    Puzzle::Cube(3).to_string(),
+                    vec![
+                        solve(12345, 12345, Penalty::Ok, "R U R' U'", "nice", 1700000000),
+                        solve(14000, 12000, Penalty::PlusTwo, "F2", "", 1700000100),
+                        solve(9000, 9000, Penalty::Dnf, "", "", 1700000200),
+                    ]
+                ),
+                (
+                    String::from("Pyra"),
+                    Puzzle::Pyraminx.to_string(),
+                    vec![
+                        solve(8500, 8500, Penalty::Ok, "R U R' U'", "", 1700000300),
+                        solve(11000, 9000, Penalty::PlusTwo, "L' U B u'", "", 1700000400),
+                    ]
+                ),
+            ]
+        );
+        assert_eq!(imported.skipped, ["FTO (fto)"]);
+    }
+
+    #[test]
+    fn exports_what_it_reads() {
+        let imported = read("export.json", EXPORT);
+
+        let exported = imported
+            .sessions
+            .iter()
+            .map(|session| Session {
+                puzzle: session.puzzle,
+                solves: session.solves.clone(),
+                ..Session::default()
+            })
+            .collect::<Vec<Session>>();
+        let exported = imported
+            .sessions
+            .iter()
+            .zip(&exported)
+            .map(|(imported, session)| (imported.name.as_str(), session))
+            .collect::<Vec<(&str, &Session)>>();
+
+        let reread = read("reread.json", &export(&exported).unwrap());
+
+        assert_eq!(sessions(&reread), sessions(&imported));
+        assert!(reread.skipped.is_empty());
+    }
+}
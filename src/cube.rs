@@ -1,7 +1,13 @@
+use crate::error::ParseError;
 use ratatui::style::Color;
-use std::fmt::Display;
-
-#[derive(PartialEq, Clone, Copy)]
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    str::FromStr,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Face {
     Up,
     Down,
@@ -12,6 +18,30 @@ pub enum Face {
 }
 
 impl Face {
+    /// Axis the face turns around (x, y or z) and the direction it faces along it.
+    pub fn axis(self) -> (usize, i32) {
+        match self {
+            Face::Right => (0, 1),
+            Face::Left => (0, -1),
+            Face::Up => (1, 1),
+            Face::Down => (1, -1),
+            Face::Front => (2, 1),
+            Face::Back => (2, -1),
+        }
+    }
+
+    /// Rotates a point by a clockwise quarter turn as seen from the face.
+    fn turn(self, [x, y, z]: [i32; 3]) -> [i32; 3] {
+        match self {
+            Face::Up => [-z, y, x],
+            Face::Down => [z, y, -x],
+            Face::Right => [x, z, -y],
+            Face::Left => [x, -z, y],
+            Face::Front => [y, -x, z],
+            Face::Back => [-y, x, z],
+        }
+    }
+
     pub fn opposite_face(self) -> Self {
         match self {
             Face::Up => Face::Down,
@@ -41,17 +71,19 @@ impl Display for Face {
     }
 }
 
-impl From<char> for Face {
-    fn from(value: char) -> Self {
-        match value {
+impl TryFrom<char> for Face {
+    type Error = ParseError;
+
+    fn try_from(value: char) -> Result<Self, ParseError> {
+        Ok(match value {
             'U' => Face::Up,
             'D' => Face::Down,
             'F' => Face::Front,
             'B' => Face::Back,
             'R' => Face::Right,
             'L' => Face::Left,
-            _ => panic!("Unexpected face character")
-        }
+            _ => return Err(ParseError::Face(value)),
+        })
     }
 }
 
@@ -89,610 +121,409 @@ impl Display for Rotation {
     }
 }
 
-impl From<char> for Rotation {
-    fn from(value: char) -> Self {
-        match value {
-            '\0' => Rotation::Clockwise,
+impl TryFrom<char> for Rotation {
+    type Error = ParseError;
+
+    fn try_from(value: char) -> Result<Self, ParseError> {
+        Ok(match value {
             '\'' => Rotation::CounterClockwise,
             '2' => Rotation::DoubleTurn,
-            _ => panic!("Unexpected rotation character"),
-        }
+            _ => return Err(ParseError::Rotation(value)),
+        })
     }
 }
 
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Layers {
+    /// The given number of layers starting from the face, more than one for
+    /// wide moves.
+    Outer(usize),
+    /// Every layer between the face and the opposite one, as in slice moves.
+    Inner,
+    /// The whole cube, as in cube rotations.
+    All,
+}
+
 #[derive(PartialEq, Clone)]
 pub struct Move {
     pub face: Face,
     pub rotation: Rotation,
+    pub layers: Layers,
 }
 
-impl Move {
-    pub fn new() -> Self {
-        let face: Face = rand::random();
-        let rotation: Rotation = rand::random();
+impl FromStr for Move {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<Self, ParseError> {
+        let digits = value.chars().take_while(char::is_ascii_digit).count();
+        let (count, rest) = value.split_at(digits);
+        let mut chars = rest.chars().peekable();
+        let wide_layers = match count {
+            "" => 2,
+            count => count
+                .parse()
+                .map_err(|_| ParseError::LayerCount(count.to_string()))?,
+        };
+
+        let letter = chars.next().ok_or(ParseError::EmptyMove)?;
+        let (face, mut layers) = match letter {
+            'M' => (Face::Left, Layers::Inner),
+            'E' => (Face::Down, Layers::Inner),
+            'S' => (Face::Front, Layers::Inner),
+            'x' => (Face::Right, Layers::All),
+            'y' => (Face::Up, Layers::All),
+            'z' => (Face::Front, Layers::All),
+            ch if ch.is_ascii_lowercase() => (
+                Face::try_from(ch.to_ascii_uppercase()).map_err(|_| ParseError::Face(ch))?,
+                Layers::Outer(wide_layers),
+            ),
+            ch => (Face::try_from(ch)?, Layers::Outer(1)),
+        };
+
+        if chars.next_if_eq(&'w').is_some() {
+            // only turns of a face, written in uppercase, are made wide by a `w`
+            if !letter.is_ascii_uppercase() || layers != Layers::Outer(1) {
+                return Err(ParseError::Wide(letter));
+            }
 
-        Self { face, rotation }
+            layers = Layers::Outer(wide_layers);
+        }
+
+        if !count.is_empty() && !matches!(layers, Layers::Outer(2..)) {
+            return Err(ParseError::LayerCount(count.to_string()));
+        }
+
+        let rotation = match chars.next() {
+            Some(ch) => Rotation::try_from(ch)?,
+            None => Rotation::Clockwise,
+        };
+
+        let rest = chars.collect::<String>();
+
+        if !rest.is_empty() {
+            return Err(ParseError::Trailing(rest));
+        }
+
+        Ok(Self {
+            face,
+            rotation,
+            layers,
+        })
     }
 }
 
-impl From<&str> for Move {
-    fn from(value: &str) -> Self {
-        let mut chars = value.chars();
+impl Move {
+    /// Move with the given index in the cubie move table, where moves are
+    /// grouped by face in `U R F D L B` order with the clockwise, double and
+    /// counter-clockwise turns of each face next to each other.
+    pub fn from_index(index: usize) -> Self {
+        Self {
+            face: FACES[index / 3],
+            rotation: match index % 3 {
+                0 => Rotation::Clockwise,
+                1 => Rotation::DoubleTurn,
+                _ => Rotation::CounterClockwise,
+            },
+            layers: Layers::Outer(1),
+        }
+    }
 
+    pub fn inverse(&self) -> Self {
         Self {
-            face: chars.next().unwrap().into(),
-            rotation: chars.next().unwrap_or_default().into(),
+            rotation: match self.rotation {
+                Rotation::Clockwise => Rotation::CounterClockwise,
+                Rotation::CounterClockwise => Rotation::Clockwise,
+                Rotation::DoubleTurn => Rotation::DoubleTurn,
+            },
+            ..self.clone()
         }
     }
 }
 
 impl Display for Move {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}{}", self.face, self.rotation)
+        let axis = self.face.axis().0;
+
+        match self.layers {
+            Layers::Outer(1) => write!(f, "{}{}", self.face, self.rotation),
+            Layers::Outer(2) => write!(f, "{}w{}", self.face, self.rotation),
+            Layers::Outer(layers) => write!(f, "{layers}{}w{}", self.face, self.rotation),
+            Layers::Inner => write!(f, "{}{}", ["M", "E", "S"][axis], self.rotation),
+            Layers::All => write!(f, "{}{}", ["x", "y", "z"][axis], self.rotation),
+        }
     }
 }
 
+const FACES: [Face; 6] = [
+    Face::Up,
+    Face::Right,
+    Face::Front,
+    Face::Down,
+    Face::Left,
+    Face::Back,
+];
+
+// faces in the order they are stored in the facelet view
+const NET_FACES: [Face; 6] = [
+    Face::Up,
+    Face::Left,
+    Face::Front,
+    Face::Right,
+    Face::Back,
+    Face::Down,
+];
+
+/// Sticker colors of an NxN cube.
+///
+/// Faces are stored in `U L F R B D` order, each one row by row as it is laid
+/// out in the scramble preview net.
+#[derive(Clone)]
 pub struct Cube {
+    pub size: usize,
     pub facelets: Vec<Face>,
+    // copy of the facelets before the move being applied
+    scratch: Vec<Face>,
 }
 
 impl Cube {
-    pub fn new() -> Self {
-        let mut facelets = Vec::new();
+    pub fn new(size: usize) -> Self {
+        let facelets = NET_FACES
+            .iter()
+            .flat_map(|&face| std::iter::repeat_n(face, size * size))
+            .collect::<Vec<Face>>();
 
-        for _ in 0..9 {
-            facelets.push(Face::Up);
+        Self {
+            size,
+            scratch: facelets.clone(),
+            facelets,
         }
+    }
 
-        for _ in 0..9 {
-            facelets.push(Face::Left);
-        }
+    pub fn face(&self, face: Face) -> &[Face] {
+        let area = self.size * self.size;
+        let offset = NET_FACES.iter().position(|f| *f == face).unwrap() * area;
 
-        for _ in 0..9 {
-            facelets.push(Face::Front);
-        }
+        &self.facelets[offset..offset + area]
+    }
 
-        for _ in 0..9 {
-            facelets.push(Face::Right);
-        }
+    pub fn apply_move(&mut self, r#move: &Move) {
+        let turns = match r#move.rotation {
+            Rotation::Clockwise => 1,
+            Rotation::DoubleTurn => 2,
+            Rotation::CounterClockwise => 3,
+        };
+        let permutation = turn_permutation(self.size, r#move.face, r#move.layers, turns);
 
-        for _ in 0..9 {
-            facelets.push(Face::Back);
-        }
+        self.scratch.copy_from_slice(&self.facelets);
 
-        for _ in 0..9 {
-            facelets.push(Face::Down);
+        for (idx, &face) in self.scratch.iter().enumerate() {
+            self.facelets[permutation[idx]] = face;
         }
-
-        Self { facelets }
     }
+}
 
-    pub fn apply_move(&mut self, r#move: &Move) {
-        let c = self.facelets.clone();
-
-        match r#move {
-            Move {
-                face: Face::Up,
-                rotation: Rotation::Clockwise,
-            } => {
-                self.facelets[0] = c[6];
-                self.facelets[1] = c[3];
-                self.facelets[2] = c[0];
-                self.facelets[3] = c[7];
-                self.facelets[5] = c[1];
-                self.facelets[6] = c[8];
-                self.facelets[7] = c[5];
-                self.facelets[8] = c[2];
-
-                self.facelets[9] = c[18];
-                self.facelets[10] = c[19];
-                self.facelets[11] = c[20];
-
-                self.facelets[18] = c[27];
-                self.facelets[19] = c[28];
-                self.facelets[20] = c[29];
-
-                self.facelets[27] = c[36];
-                self.facelets[28] = c[37];
-                self.facelets[29] = c[38];
-
-                self.facelets[36] = c[9];
-                self.facelets[37] = c[10];
-                self.facelets[38] = c[11];
-            }
-            Move {
-                face: Face::Up,
-                rotation: Rotation::CounterClockwise,
-            } => {
-                self.facelets[0] = c[2];
-                self.facelets[1] = c[5];
-                self.facelets[2] = c[8];
-                self.facelets[3] = c[1];
-                self.facelets[5] = c[7];
-                self.facelets[6] = c[0];
-                self.facelets[7] = c[3];
-                self.facelets[8] = c[6];
-
-                self.facelets[9] = c[36];
-                self.facelets[10] = c[37];
-                self.facelets[11] = c[38];
-
-                self.facelets[18] = c[9];
-                self.facelets[19] = c[10];
-                self.facelets[20] = c[11];
-
-                self.facelets[27] = c[18];
-                self.facelets[28] = c[19];
-                self.facelets[29] = c[20];
-
-                self.facelets[36] = c[27];
-                self.facelets[37] = c[28];
-                self.facelets[38] = c[29];
-            }
-            Move {
-                face: Face::Up,
-                rotation: Rotation::DoubleTurn,
-            } => {
-                self.facelets[0] = c[8];
-                self.facelets[1] = c[7];
-                self.facelets[2] = c[6];
-                self.facelets[3] = c[5];
-                self.facelets[5] = c[3];
-                self.facelets[6] = c[2];
-                self.facelets[7] = c[1];
-                self.facelets[8] = c[0];
-
-                self.facelets[9] = c[27];
-                self.facelets[10] = c[28];
-                self.facelets[11] = c[29];
-
-                self.facelets[18] = c[36];
-                self.facelets[19] = c[37];
-                self.facelets[20] = c[38];
-
-                self.facelets[27] = c[9];
-                self.facelets[28] = c[10];
-                self.facelets[29] = c[11];
-
-                self.facelets[36] = c[18];
-                self.facelets[37] = c[19];
-                self.facelets[38] = c[20];
-            }
-            Move {
-                face: Face::Down,
-                rotation: Rotation::Clockwise,
-            } => {
-                self.facelets[45] = c[51];
-                self.facelets[46] = c[48];
-                self.facelets[47] = c[45];
-                self.facelets[48] = c[52];
-                self.facelets[50] = c[46];
-                self.facelets[51] = c[53];
-                self.facelets[52] = c[50];
-                self.facelets[53] = c[47];
-
-                self.facelets[15] = c[42];
-                self.facelets[16] = c[43];
-                self.facelets[17] = c[44];
-
-                self.facelets[24] = c[15];
-                self.facelets[25] = c[16];
-                self.facelets[26] = c[17];
-
-                self.facelets[33] = c[24];
-                self.facelets[34] = c[25];
-                self.facelets[35] = c[26];
-
-                self.facelets[42] = c[33];
-                self.facelets[43] = c[34];
-                self.facelets[44] = c[35];
-            }
-            Move {
-                face: Face::Down,
-                rotation: Rotation::CounterClockwise,
-            } => {
-                self.facelets[45] = c[47];
-                self.facelets[46] = c[50];
-                self.facelets[47] = c[53];
-                self.facelets[48] = c[46];
-                self.facelets[50] = c[52];
-                self.facelets[51] = c[45];
-                self.facelets[52] = c[48];
-                self.facelets[53] = c[51];
-
-                self.facelets[15] = c[24];
-                self.facelets[16] = c[25];
-                self.facelets[17] = c[26];
-
-                self.facelets[24] = c[33];
-                self.facelets[25] = c[34];
-                self.facelets[26] = c[35];
-
-                self.facelets[33] = c[42];
-                self.facelets[34] = c[43];
-                self.facelets[35] = c[44];
-
-                self.facelets[42] = c[15];
-                self.facelets[43] = c[16];
-                self.facelets[44] = c[17];
-            }
-            Move {
-                face: Face::Down,
-                rotation: Rotation::DoubleTurn,
-            } => {
-                self.facelets[45] = c[53];
-                self.facelets[46] = c[52];
-                self.facelets[47] = c[51];
-                self.facelets[48] = c[50];
-                self.facelets[50] = c[48];
-                self.facelets[51] = c[47];
-                self.facelets[52] = c[46];
-                self.facelets[53] = c[45];
-
-                self.facelets[15] = c[33];
-                self.facelets[16] = c[34];
-                self.facelets[17] = c[35];
-
-                self.facelets[24] = c[42];
-                self.facelets[25] = c[43];
-                self.facelets[26] = c[44];
-
-                self.facelets[33] = c[15];
-                self.facelets[34] = c[16];
-                self.facelets[35] = c[17];
-
-                self.facelets[42] = c[24];
-                self.facelets[43] = c[25];
-                self.facelets[44] = c[26];
-            }
-            Move {
-                face: Face::Front,
-                rotation: Rotation::Clockwise,
-            } => {
-                self.facelets[18] = c[24];
-                self.facelets[19] = c[21];
-                self.facelets[20] = c[18];
-                self.facelets[21] = c[25];
-                self.facelets[23] = c[19];
-                self.facelets[24] = c[26];
-                self.facelets[25] = c[23];
-                self.facelets[26] = c[20];
-
-                self.facelets[6] = c[17];
-                self.facelets[7] = c[14];
-                self.facelets[8] = c[11];
-
-                self.facelets[11] = c[45];
-                self.facelets[14] = c[46];
-                self.facelets[17] = c[47];
-
-                self.facelets[27] = c[6];
-                self.facelets[30] = c[7];
-                self.facelets[33] = c[8];
-
-                self.facelets[45] = c[33];
-                self.facelets[46] = c[30];
-                self.facelets[47] = c[27];
-            }
-            Move {
-                face: Face::Front,
-                rotation: Rotation::CounterClockwise,
-            } => {
-                self.facelets[18] = c[20];
-                self.facelets[19] = c[23];
-                self.facelets[20] = c[26];
-                self.facelets[21] = c[19];
-                self.facelets[23] = c[25];
-                self.facelets[24] = c[18];
-                self.facelets[25] = c[21];
-                self.facelets[26] = c[24];
-
-                self.facelets[6] = c[27];
-                self.facelets[7] = c[30];
-                self.facelets[8] = c[33];
-
-                self.facelets[11] = c[8];
-                self.facelets[14] = c[7];
-                self.facelets[17] = c[6];
-
-                self.facelets[27] = c[47];
-                self.facelets[30] = c[46];
-                self.facelets[33] = c[45];
-
-                self.facelets[45] = c[11];
-                self.facelets[46] = c[14];
-                self.facelets[47] = c[17];
-            }
-            Move {
-                face: Face::Front,
-                rotation: Rotation::DoubleTurn,
-            } => {
-                self.facelets[18] = c[26];
-                self.facelets[19] = c[25];
-                self.facelets[20] = c[24];
-                self.facelets[21] = c[23];
-                self.facelets[23] = c[21];
-                self.facelets[24] = c[20];
-                self.facelets[25] = c[19];
-                self.facelets[26] = c[18];
-
-                self.facelets[6] = c[47];
-                self.facelets[7] = c[46];
-                self.facelets[8] = c[45];
-
-                self.facelets[11] = c[33];
-                self.facelets[14] = c[30];
-                self.facelets[17] = c[27];
-
-                self.facelets[27] = c[17];
-                self.facelets[30] = c[14];
-                self.facelets[33] = c[11];
-
-                self.facelets[45] = c[8];
-                self.facelets[46] = c[7];
-                self.facelets[47] = c[6];
-            }
-            Move {
-                face: Face::Back,
-                rotation: Rotation::Clockwise,
-            } => {
-                self.facelets[36] = c[42];
-                self.facelets[37] = c[39];
-                self.facelets[38] = c[36];
-                self.facelets[39] = c[43];
-                self.facelets[41] = c[37];
-                self.facelets[42] = c[44];
-                self.facelets[43] = c[41];
-                self.facelets[44] = c[38];
-
-                self.facelets[0] = c[29];
-                self.facelets[1] = c[32];
-                self.facelets[2] = c[35];
-
-                self.facelets[9] = c[2];
-                self.facelets[12] = c[1];
-                self.facelets[15] = c[0];
-
-                self.facelets[29] = c[53];
-                self.facelets[32] = c[52];
-                self.facelets[35] = c[51];
-
-                self.facelets[51] = c[9];
-                self.facelets[52] = c[12];
-                self.facelets[53] = c[15];
-            }
-            Move {
-                face: Face::Back,
-                rotation: Rotation::CounterClockwise,
-            } => {
-                self.facelets[36] = c[38];
-                self.facelets[37] = c[41];
-                self.facelets[38] = c[44];
-                self.facelets[39] = c[37];
-                self.facelets[41] = c[43];
-                self.facelets[42] = c[36];
-                self.facelets[43] = c[39];
-                self.facelets[44] = c[42];
-
-                self.facelets[0] = c[15];
-                self.facelets[1] = c[12];
-                self.facelets[2] = c[9];
-
-                self.facelets[9] = c[51];
-                self.facelets[12] = c[52];
-                self.facelets[15] = c[53];
-
-                self.facelets[29] = c[0];
-                self.facelets[32] = c[1];
-                self.facelets[35] = c[2];
-
-                self.facelets[51] = c[35];
-                self.facelets[52] = c[32];
-                self.facelets[53] = c[29];
-            }
-            Move {
-                face: Face::Back,
-                rotation: Rotation::DoubleTurn,
-            } => {
-                self.facelets[36] = c[44];
-                self.facelets[37] = c[43];
-                self.facelets[38] = c[42];
-                self.facelets[39] = c[41];
-                self.facelets[41] = c[39];
-                self.facelets[42] = c[38];
-                self.facelets[43] = c[37];
-                self.facelets[44] = c[36];
-
-                self.facelets[0] = c[53];
-                self.facelets[1] = c[52];
-                self.facelets[2] = c[51];
-
-                self.facelets[9] = c[35];
-                self.facelets[12] = c[32];
-                self.facelets[15] = c[29];
-
-                self.facelets[29] = c[15];
-                self.facelets[32] = c[12];
-                self.facelets[35] = c[9];
-
-                self.facelets[51] = c[2];
-                self.facelets[52] = c[1];
-                self.facelets[53] = c[0];
-            }
-            Move {
-                face: Face::Right,
-                rotation: Rotation::Clockwise,
-            } => {
-                self.facelets[27] = c[33];
-                self.facelets[28] = c[30];
-                self.facelets[29] = c[27];
-                self.facelets[30] = c[34];
-                self.facelets[32] = c[28];
-                self.facelets[33] = c[35];
-                self.facelets[34] = c[32];
-                self.facelets[35] = c[29];
-
-                self.facelets[2] = c[20];
-                self.facelets[5] = c[23];
-                self.facelets[8] = c[26];
-
-                self.facelets[20] = c[47];
-                self.facelets[23] = c[50];
-                self.facelets[26] = c[53];
-
-                self.facelets[36] = c[8];
-                self.facelets[39] = c[5];
-                self.facelets[42] = c[2];
-
-                self.facelets[47] = c[42];
-                self.facelets[50] = c[39];
-                self.facelets[53] = c[36];
-            }
-            Move {
-                face: Face::Right,
-                rotation: Rotation::CounterClockwise,
-            } => {
-                self.facelets[27] = c[29];
-                self.facelets[28] = c[32];
-                self.facelets[29] = c[35];
-                self.facelets[30] = c[28];
-                self.facelets[32] = c[34];
-                self.facelets[33] = c[27];
-                self.facelets[34] = c[30];
-                self.facelets[35] = c[33];
-
-                self.facelets[2] = c[42];
-                self.facelets[5] = c[39];
-                self.facelets[8] = c[36];
-
-                self.facelets[20] = c[2];
-                self.facelets[23] = c[5];
-                self.facelets[26] = c[8];
-
-                self.facelets[36] = c[53];
-                self.facelets[39] = c[50];
-                self.facelets[42] = c[47];
-
-                self.facelets[47] = c[20];
-                self.facelets[50] = c[23];
-                self.facelets[53] = c[26];
-            }
-            Move {
-                face: Face::Right,
-                rotation: Rotation::DoubleTurn,
-            } => {
-                self.facelets[27] = c[35];
-                self.facelets[28] = c[34];
-                self.facelets[29] = c[33];
-                self.facelets[30] = c[32];
-                self.facelets[32] = c[30];
-                self.facelets[33] = c[29];
-                self.facelets[34] = c[28];
-                self.facelets[35] = c[27];
-
-                self.facelets[2] = c[47];
-                self.facelets[5] = c[50];
-                self.facelets[8] = c[53];
-
-                self.facelets[20] = c[42];
-                self.facelets[23] = c[39];
-                self.facelets[26] = c[36];
-
-                self.facelets[36] = c[26];
-                self.facelets[39] = c[23];
-                self.facelets[42] = c[20];
-
-                self.facelets[47] = c[2];
-                self.facelets[50] = c[5];
-                self.facelets[53] = c[8];
-            }
-            Move {
-                face: Face::Left,
-                rotation: Rotation::Clockwise,
-            } => {
-                self.facelets[9] = c[15];
-                self.facelets[10] = c[12];
-                self.facelets[11] = c[9];
-                self.facelets[12] = c[16];
-                self.facelets[14] = c[10];
-                self.facelets[15] = c[17];
-                self.facelets[16] = c[14];
-                self.facelets[17] = c[11];
-
-                self.facelets[0] = c[44];
-                self.facelets[3] = c[41];
-                self.facelets[6] = c[38];
-
-                self.facelets[18] = c[0];
-                self.facelets[21] = c[3];
-                self.facelets[24] = c[6];
-
-                self.facelets[38] = c[51];
-                self.facelets[41] = c[48];
-                self.facelets[44] = c[45];
-
-                self.facelets[45] = c[18];
-                self.facelets[48] = c[21];
-                self.facelets[51] = c[24];
-            }
-            Move {
-                face: Face::Left,
-                rotation: Rotation::CounterClockwise,
-            } => {
-                self.facelets[9] = c[11];
-                self.facelets[10] = c[14];
-                self.facelets[11] = c[17];
-                self.facelets[12] = c[10];
-                self.facelets[14] = c[16];
-                self.facelets[15] = c[9];
-                self.facelets[16] = c[12];
-                self.facelets[17] = c[15];
-
-                self.facelets[0] = c[18];
-                self.facelets[3] = c[21];
-                self.facelets[6] = c[24];
-
-                self.facelets[18] = c[45];
-                self.facelets[21] = c[48];
-                self.facelets[24] = c[51];
-
-                self.facelets[38] = c[6];
-                self.facelets[41] = c[3];
-                self.facelets[44] = c[0];
-
-                self.facelets[45] = c[44];
-                self.facelets[48] = c[41];
-                self.facelets[51] = c[38];
-            }
-            Move {
-                face: Face::Left,
-                rotation: Rotation::DoubleTurn,
-            } => {
-                self.facelets[9] = c[17];
-                self.facelets[10] = c[16];
-                self.facelets[11] = c[15];
-                self.facelets[12] = c[14];
-                self.facelets[14] = c[12];
-                self.facelets[15] = c[11];
-                self.facelets[16] = c[10];
-                self.facelets[17] = c[9];
-
-                self.facelets[0] = c[45];
-                self.facelets[3] = c[48];
-                self.facelets[6] = c[51];
-
-                self.facelets[18] = c[44];
-                self.facelets[21] = c[41];
-                self.facelets[24] = c[38];
-
-                self.facelets[38] = c[24];
-                self.facelets[41] = c[21];
-                self.facelets[44] = c[18];
-
-                self.facelets[45] = c[0];
-                self.facelets[48] = c[3];
-                self.facelets[51] = c[6];
+/// Destination of every sticker after the given number of clockwise quarter
+/// turns of the layers, computed once for each size of cube.
+fn turn_permutation(size: usize, face: Face, layers: Layers, turns: usize) -> Arc<[usize]> {
+    type Permutations = HashMap<(usize, Face, Layers, usize), Arc<[usize]>>;
+    static PERMUTATIONS: OnceLock<Mutex<Permutations>> = OnceLock::new();
+
+    // turning more layers than the cube has turns all of them
+    let layers = match layers {
+        Layers::Outer(count) => Layers::Outer(count.min(size)),
+        layers => layers,
+    };
+
+    let mut permutations = PERMUTATIONS.get_or_init(Default::default).lock().unwrap();
+
+    permutations
+        .entry((size, face, layers, turns))
+        .or_insert_with(|| {
+            let quarter_turn = quarter_turn(size, face, layers);
+
+            (0..6 * size * size)
+                .map(|mut idx| {
+                    for _ in 0..turns {
+                        idx = quarter_turn[idx];
+                    }
+                    idx
+                })
+                .collect()
+        })
+        .clone()
+}
+
+/// Destination of every sticker after a clockwise quarter turn of the given
+/// layers.
+fn quarter_turn(size: usize, face: Face, layers: Layers) -> Vec<usize> {
+    let n = size as i32;
+    let (axis, direction) = face.axis();
+
+    (0..6 * size * size)
+        .map(|idx| {
+            let position = position(size, idx);
+            // stickers on the face itself belong to the first layer and the
+            // ones on the opposite face to the last
+            let layer = (n - 1 - direction * position[axis]).max(0) / 2;
+
+            let is_turned = match layers {
+                Layers::Outer(count) => layer < count as i32,
+                Layers::Inner => layer > 0 && layer < n - 1,
+                Layers::All => true,
+            };
+
+            if is_turned {
+                index(size, face.turn(position))
+            } else {
+                idx
             }
+        })
+        .collect()
+}
+
+/// Center of a sticker in a coordinate system where the cube spans from
+/// `-size` to `size` on every axis, with x pointing right, y up and z to the
+/// front.
+fn position(size: usize, idx: usize) -> [i32; 3] {
+    let n = size as i32;
+    let area = size * size;
+    let coordinate = |i: usize| 2 * i as i32 - (n - 1);
+
+    let (row, col) = (coordinate(idx % area / size), coordinate(idx % size));
+
+    match NET_FACES[idx / area] {
+        Face::Up => [col, n, row],
+        Face::Left => [-n, -row, col],
+        Face::Front => [col, -row, n],
+        Face::Right => [n, -row, -col],
+        Face::Back => [-col, -row, -n],
+        Face::Down => [col, -n, -row],
+    }
+}
+
+fn index(size: usize, [x, y, z]: [i32; 3]) -> usize {
+    let n = size as i32;
+    let coordinate = |v: i32| ((v + n - 1) / 2) as usize;
+
+    let (face, row, col) = if y == n {
+        (0, z, x)
+    } else if x == -n {
+        (1, -y, z)
+    } else if z == n {
+        (2, -y, x)
+    } else if x == n {
+        (3, -y, -z)
+    } else if z == -n {
+        (4, -y, -x)
+    } else {
+        (5, -z, x)
+    };
+
+    (face * size + coordinate(row)) * size + coordinate(col)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{puzzle::Puzzle, scramble::Scramble};
+
+    #[test]
+    fn parses_moves() {
+        let moves = [
+            ("M", Face::Left, Rotation::Clockwise, Layers::Inner, "M"),
+            (
+                "E'",
+                Face::Down,
+                Rotation::CounterClockwise,
+                Layers::Inner,
+                "E'",
+            ),
+            ("S2", Face::Front, Rotation::DoubleTurn, Layers::Inner, "S2"),
+            ("x", Face::Right, Rotation::Clockwise, Layers::All, "x"),
+            (
+                "y'",
+                Face::Up,
+                Rotation::CounterClockwise,
+                Layers::All,
+                "y'",
+            ),
+            ("z2", Face::Front, Rotation::DoubleTurn, Layers::All, "z2"),
+            (
+                "r",
+                Face::Right,
+                Rotation::Clockwise,
+                Layers::Outer(2),
+                "Rw",
+            ),
+            (
+                "3r",
+                Face::Right,
+                Rotation::Clockwise,
+                Layers::Outer(3),
+                "3Rw",
+            ),
+            (
+                "Rw",
+                Face::Right,
+                Rotation::Clockwise,
+                Layers::Outer(2),
+                "Rw",
+            ),
+            (
+                "3Rw2",
+                Face::Right,
+                Rotation::DoubleTurn,
+                Layers::Outer(3),
+                "3Rw2",
+            ),
+            (
+                "B'",
+                Face::Back,
+                Rotation::CounterClockwise,
+                Layers::Outer(1),
+                "B'",
+            ),
+        ];
+
+        for (text, face, rotation, layers, displayed) in moves {
+            let r#move: Move = text.parse().unwrap();
+
+            assert!(r#move.face == face, "{text}");
+            assert!(r#move.rotation == rotation, "{text}");
+            assert!(r#move.layers == layers, "{text}");
+            assert_eq!(r#move.to_string(), displayed);
+            assert!(displayed.parse::<Move>().unwrap() == r#move, "{text}");
         }
     }
+
+    #[test]
+    fn reports_invalid_moves() {
+        let scrambles = [
+            ("R Mw", "Mw", 2, ParseError::Wide('M')),
+            ("xw", "xw", 1, ParseError::Wide('x')),
+            ("U rw'", "rw'", 2, ParseError::Wide('r')),
+            (
+                "R U 1Rw",
+                "1Rw",
+                3,
+                ParseError::LayerCount(String::from("1")),
+            ),
+            ("R2' U", "R2'", 1, ParseError::Trailing(String::from("'"))),
+            ("F 3", "3", 2, ParseError::EmptyMove),
+            ("R Q2", "Q2", 2, ParseError::Face('Q')),
+        ];
+
+        for (scramble, token, position, error) in scrambles {
+            assert_eq!(
+                Scramble::parse(Puzzle::Cube(3), scramble).err(),
+                Some(ParseError::Move {
+                    token: token.to_string(),
+                    position,
+                    error: Box::new(error),
+                })
+            );
+        }
+
+        assert_eq!("".parse::<Move>().err(), Some(ParseError::EmptyMove));
+    }
 }
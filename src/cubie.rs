@@ -0,0 +1,353 @@
+use crate::cube::{Cube, Face};
+use anyhow::{bail, Result};
+use std::sync::OnceLock;
+
+pub const MOVE_COUNT: usize = 18;
+
+/// Cube state as the permutation and orientation of its corner and edge
+/// pieces.
+///
+/// Corners are ordered `URF, UFL, ULB, UBR, DFR, DLF, DBL, DRB` and edges
+/// `UR, UF, UL, UB, DR, DF, DL, DB, FR, FL, BL, BR`. `cp[i]` is the corner
+/// occupying position `i` and `co[i]` its twist, likewise for edges.
+#[derive(Clone, Copy, PartialEq)]
+pub struct CubieCube {
+    pub cp: [u8; 8],
+    pub co: [u8; 8],
+    pub ep: [u8; 12],
+    pub eo: [u8; 12],
+}
+
+pub const SOLVED: CubieCube = CubieCube {
+    cp: [0, 1, 2, 3, 4, 5, 6, 7],
+    co: [0; 8],
+    ep: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+    eo: [0; 12],
+};
+
+// clockwise quarter turns of U, R, F, D, L and B
+const BASIC_MOVES: [CubieCube; 6] = [
+    CubieCube {
+        cp: [3, 0, 1, 2, 4, 5, 6, 7],
+        co: [0, 0, 0, 0, 0, 0, 0, 0],
+        ep: [3, 0, 1, 2, 4, 5, 6, 7, 8, 9, 10, 11],
+        eo: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    },
+    CubieCube {
+        cp: [4, 1, 2, 0, 7, 5, 6, 3],
+        co: [2, 0, 0, 1, 1, 0, 0, 2],
+        ep: [8, 1, 2, 3, 11, 5, 6, 7, 4, 9, 10, 0],
+        eo: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    },
+    CubieCube {
+        cp: [1, 5, 2, 3, 0, 4, 6, 7],
+        co: [1, 2, 0, 0, 2, 1, 0, 0],
+        ep: [0, 9, 2, 3, 4, 8, 6, 7, 1, 5, 10, 11],
+        eo: [0, 1, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0],
+    },
+    CubieCube {
+        cp: [0, 1, 2, 3, 5, 6, 7, 4],
+        co: [0, 0, 0, 0, 0, 0, 0, 0],
+        ep: [0, 1, 2, 3, 5, 6, 7, 4, 8, 9, 10, 11],
+        eo: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    },
+    CubieCube {
+        cp: [0, 2, 6, 3, 4, 1, 5, 7],
+        co: [0, 1, 2, 0, 0, 2, 1, 0],
+        ep: [0, 1, 10, 3, 4, 5, 9, 7, 8, 2, 6, 11],
+        eo: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    },
+    CubieCube {
+        cp: [0, 1, 3, 7, 4, 5, 2, 6],
+        co: [0, 0, 1, 2, 0, 0, 2, 1],
+        ep: [0, 1, 2, 11, 4, 5, 6, 10, 8, 9, 3, 7],
+        eo: [0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 1, 1],
+    },
+];
+
+// offsets of the faces in the 3x3 facelet view: U, L, F, R, B, D
+const U: usize = 0;
+const L: usize = 9;
+const F: usize = 18;
+const R: usize = 27;
+const B: usize = 36;
+const D: usize = 45;
+
+const CENTERS: [(Face, usize); 6] = [
+    (Face::Up, U),
+    (Face::Left, L),
+    (Face::Front, F),
+    (Face::Right, R),
+    (Face::Back, B),
+    (Face::Down, D),
+];
+
+// facelets of each corner position, starting from the U or D facelet and
+// going clockwise
+const CORNER_FACELETS: [[usize; 3]; 8] = [
+    [U + 8, R, F + 2],
+    [U + 6, F, L + 2],
+    [U, L, B + 2],
+    [U + 2, B, R + 2],
+    [D + 2, F + 8, R + 6],
+    [D, L + 8, F + 6],
+    [D + 6, B + 8, L + 6],
+    [D + 8, R + 8, B + 6],
+];
+
+const CORNER_COLORS: [[Face; 3]; 8] = [
+    [Face::Up, Face::Right, Face::Front],
+    [Face::Up, Face::Front, Face::Left],
+    [Face::Up, Face::Left, Face::Back],
+    [Face::Up, Face::Back, Face::Right],
+    [Face::Down, Face::Front, Face::Right],
+    [Face::Down, Face::Left, Face::Front],
+    [Face::Down, Face::Back, Face::Left],
+    [Face::Down, Face::Right, Face::Back],
+];
+
+const EDGE_FACELETS: [[usize; 2]; 12] = [
+    [U + 5, R + 1],
+    [U + 7, F + 1],
+    [U + 3, L + 1],
+    [U + 1, B + 1],
+    [D + 5, R + 7],
+    [D + 1, F + 7],
+    [D + 3, L + 7],
+    [D + 7, B + 7],
+    [F + 5, R + 3],
+    [F + 3, L + 5],
+    [B + 5, L + 3],
+    [B + 3, R + 5],
+];
+
+const EDGE_COLORS: [[Face; 2]; 12] = [
+    [Face::Up, Face::Right],
+    [Face::Up, Face::Front],
+    [Face::Up, Face::Left],
+    [Face::Up, Face::Back],
+    [Face::Down, Face::Right],
+    [Face::Down, Face::Front],
+    [Face::Down, Face::Left],
+    [Face::Down, Face::Back],
+    [Face::Front, Face::Right],
+    [Face::Front, Face::Left],
+    [Face::Back, Face::Left],
+    [Face::Back, Face::Right],
+];
+
+fn move_table() -> &'static [CubieCube; MOVE_COUNT] {
+    static MOVE_TABLE: OnceLock<[CubieCube; MOVE_COUNT]> = OnceLock::new();
+
+    MOVE_TABLE.get_or_init(|| {
+        let mut table = [SOLVED; MOVE_COUNT];
+
+        for (idx, r#move) in table.iter_mut().enumerate() {
+            for _ in 0..=idx % 3 {
+                *r#move = r#move.multiply(&BASIC_MOVES[idx / 3]);
+            }
+        }
+
+        table
+    })
+}
+
+impl CubieCube {
+    pub fn new() -> Self {
+        SOLVED
+    }
+
+    pub fn multiply(&self, other: &Self) -> Self {
+        let mut result = *self;
+
+        for i in 0..8 {
+            let from = other.cp[i] as usize;
+            result.cp[i] = self.cp[from];
+            result.co[i] = (self.co[from] + other.co[i]) % 3;
+        }
+
+        for i in 0..12 {
+            let from = other.ep[i] as usize;
+            result.ep[i] = self.ep[from];
+            result.eo[i] = (self.eo[from] + other.eo[i]) % 2;
+        }
+
+        result
+    }
+
+    pub fn apply_move_index(&mut self, index: usize) {
+        *self = self.multiply(&move_table()[index]);
+    }
+}
+
+impl From<&CubieCube> for Cube {
+    fn from(cubie: &CubieCube) -> Self {
+        let mut cube = Cube::new(3);
+
+        for (i, facelets) in CORNER_FACELETS.iter().enumerate() {
+            let corner = cubie.cp[i] as usize;
+            let twist = cubie.co[i] as usize;
+
+            for n in 0..3 {
+                cube.facelets[facelets[(n + twist) % 3]] = CORNER_COLORS[corner][n];
+            }
+        }
+
+        for (i, facelets) in EDGE_FACELETS.iter().enumerate() {
+            let edge = cubie.ep[i] as usize;
+            let flip = cubie.eo[i] as usize;
+
+            for n in 0..2 {
+                cube.facelets[facelets[(n + flip) % 2]] = EDGE_COLORS[edge][n];
+            }
+        }
+
+        cube
+    }
+}
+
+impl TryFrom<&Cube> for CubieCube {
+    type Error = anyhow::Error;
+
+    fn try_from(cube: &Cube) -> Result<Self> {
+        if cube.size != 3 {
+            bail!("Only 3x3 cubes have a cubie representation");
+        }
+
+        let facelets = &cube.facelets;
+        let mut cube = SOLVED;
+
+        for i in 0..8 {
+            let Some(twist) = CORNER_FACELETS[i]
+                .iter()
+                .position(|&idx| matches!(facelets[idx], Face::Up | Face::Down))
+            else {
+                bail!("Corner at position {i} has no U or D sticker");
+            };
+
+            let colors = [
+                facelets[CORNER_FACELETS[i][(twist + 1) % 3]],
+                facelets[CORNER_FACELETS[i][(twist + 2) % 3]],
+            ];
+
+            let Some(corner) = CORNER_COLORS
+                .iter()
+                .position(|corner| corner[1..] == colors)
+            else {
+                bail!("Corner at position {i} has an invalid color combination");
+            };
+
+            cube.cp[i] = corner as u8;
+            cube.co[i] = twist as u8;
+        }
+
+        for (i, edge_facelets) in EDGE_FACELETS.iter().enumerate() {
+            let colors = edge_facelets.map(|idx| facelets[idx]);
+
+            let Some((edge, flip)) = EDGE_COLORS.iter().enumerate().find_map(|(edge, c)| {
+                if *c == colors {
+                    Some((edge, 0))
+                } else if [c[1], c[0]] == colors {
+                    Some((edge, 1))
+                } else {
+                    None
+                }
+            }) else {
+                bail!("Edge at position {i} has an invalid color combination");
+            };
+
+            cube.ep[i] = edge as u8;
+            cube.eo[i] = flip;
+        }
+
+        if CENTERS
+            .iter()
+            .any(|(face, offset)| facelets[offset + 4] != *face)
+        {
+            bail!("Centers are not in their standard orientation");
+        }
+
+        let mut corners = cube.cp;
+        let mut edges = cube.ep;
+        corners.sort_unstable();
+        edges.sort_unstable();
+
+        if corners != SOLVED.cp || edges != SOLVED.ep {
+            bail!("Some pieces are duplicated");
+        }
+
+        if cube.co.iter().sum::<u8>() % 3 != 0 {
+            bail!("Twisted corner");
+        }
+
+        if cube.eo.iter().sum::<u8>() % 2 != 0 {
+            bail!("Flipped edge");
+        }
+
+        if has_odd_parity(&cube.cp) != has_odd_parity(&cube.ep) {
+            bail!("Two pieces are swapped");
+        }
+
+        Ok(cube)
+    }
+}
+
+pub fn has_odd_parity(perm: &[u8]) -> bool {
+    let mut inversions = 0;
+
+    for i in 0..perm.len() {
+        inversions += perm[i + 1..].iter().filter(|&&p| p < perm[i]).count();
+    }
+
+    inversions % 2 == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube::Move;
+
+    /// Facelet cubes that can't be reached from the cube by turning it: with
+    /// a twisted corner, a flipped edge and two corners swapped.
+    fn invalid_cubes(cube: &Cube) -> [Cube; 3] {
+        let mut twisted = cube.clone();
+        let [a, b, c] = CORNER_FACELETS[0];
+        twisted.facelets[a] = cube.facelets[b];
+        twisted.facelets[b] = cube.facelets[c];
+        twisted.facelets[c] = cube.facelets[a];
+
+        let mut flipped = cube.clone();
+        flipped
+            .facelets
+            .swap(EDGE_FACELETS[0][0], EDGE_FACELETS[0][1]);
+
+        let mut swapped = cube.clone();
+        for (a, b) in CORNER_FACELETS[0].into_iter().zip(CORNER_FACELETS[1]) {
+            swapped.facelets.swap(a, b);
+        }
+
+        [twisted, flipped, swapped]
+    }
+
+    #[test]
+    fn converts_between_facelets_and_cubies() {
+        // each move is checked from the solved cube and after the ones before it
+        let mut turned = (SOLVED, Cube::new(3));
+
+        for index in 0..MOVE_COUNT {
+            let r#move = Move::from_index(index);
+
+            for (cubie, cube) in [&mut (SOLVED, Cube::new(3)), &mut turned] {
+                cubie.apply_move_index(index);
+                cube.apply_move(&r#move);
+
+                assert!(Cube::from(&*cubie).facelets == cube.facelets, "{move}");
+                assert!(CubieCube::try_from(&*cube).unwrap() == *cubie, "{move}");
+
+                for invalid in invalid_cubes(cube) {
+                    assert!(CubieCube::try_from(&invalid).is_err(), "{move}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::fmt::Display;
+
+/// Error returned when move notation or a field of a history file can't be
+/// parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    EmptyMove,
+    Face(char),
+    Rotation(char),
+    /// Move that needs an explicit direction given without one.
+    MissingRotation,
+    /// Layer count given for a move that isn't a wide move, or too large to
+    /// be read.
+    LayerCount(String),
+    /// Slice, rotation or lowercase wide move followed by a `w`, which only
+    /// makes face turns wide.
+    Wide(char),
+    /// Characters left over after a complete move.
+    Trailing(String),
+    /// Move of a scramble that failed to parse, along with its position in
+    /// the scramble starting from 1.
+    Move {
+        token: String,
+        position: usize,
+        error: Box<ParseError>,
+    },
+    /// Square-1 layer turn that isn't two numbers of twelfths in parentheses.
+    Turn(String),
+    /// Square-1 slice turn blocked by a piece across the slice.
+    Shape,
+    /// Clock move that doesn't start with a known set of pins.
+    Pins(String),
+    /// Clock dial turn of more than six hours either way.
+    Hours(String),
+    Penalty(u8),
+    /// Stat that isn't a mean or average of a valid size, like `mo3` or
+    /// `ao12`.
+    Stat(String),
+    /// Puzzle id that doesn't match any supported puzzle.
+    Puzzle(String),
+    /// Time that isn't in seconds or minutes and seconds, like `12.345` or
+    /// `1:05.2`.
+    Time(String),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::EmptyMove => write!(f, "empty move"),
+            ParseError::Face(ch) => write!(f, "unexpected face character '{ch}'"),
+            ParseError::Rotation(ch) => write!(f, "unexpected rotation character '{ch}'"),
+            ParseError::MissingRotation => write!(f, "missing rotation"),
+            ParseError::LayerCount(count) => write!(f, "invalid layer count '{count}'"),
+            ParseError::Wide(ch) => write!(f, "'{ch}' can't be followed by 'w'"),
+            ParseError::Trailing(rest) => write!(f, "unexpected characters '{rest}' after move"),
+            ParseError::Move {
+                token,
+                position,
+                error,
+            } => write!(f, "invalid move '{token}' at position {position}: {error}"),
+            ParseError::Turn(turn) => write!(f, "invalid layer turn '{turn}'"),
+            ParseError::Shape => write!(f, "a piece is in the way of the slice"),
+            ParseError::Pins(pins) => write!(f, "unknown pins '{pins}'"),
+            ParseError::Hours(hours) => write!(f, "invalid dial turn of '{hours}' hours"),
+            ParseError::Penalty(code) => write!(f, "unknown penalty code {code}"),
+            ParseError::Stat(stat) => write!(f, "invalid stat '{stat}'"),
+            ParseError::Puzzle(id) => write!(f, "unknown puzzle '{id}'"),
+            ParseError::Time(time) => write!(f, "invalid time '{time}'"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -0,0 +1,37 @@
+pub type Point = [f64; 3];
+
+pub fn dot(a: Point, b: Point) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn add(a: Point, b: Point) -> Point {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+pub fn scale(a: Point, k: f64) -> Point {
+    a.map(|v| v * k)
+}
+
+pub fn cross(a: Point, b: Point) -> Point {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub fn distance(a: Point, b: Point) -> f64 {
+    let d = add(a, scale(b, -1.0));
+    dot(d, d).sqrt()
+}
+
+/// Rotates the point around the unit axis, counter-clockwise as seen from
+/// the tip of the axis.
+pub fn rotate(p: Point, axis: Point, angle: f64) -> Point {
+    let (sin, cos) = angle.sin_cos();
+
+    add(
+        add(scale(p, cos), scale(cross(axis, p), sin)),
+        scale(axis, dot(axis, p) * (1.0 - cos)),
+    )
+}
@@ -1,9 +1,40 @@
-use anyhow::{bail, Result};
-use std::path::PathBuf;
+use anyhow::{anyhow, bail, Context, Result};
+use csv::StringRecord;
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use crate::{app::Solve, session::Session};
+use crate::{
+    app::{Penalty, Solve},
+    puzzle::Puzzle,
+    session::{Session, SessionInfo, SessionSettings},
+    stats,
+};
 
 const PACKAGE_NAME: &str = env!("CARGO_PKG_NAME");
+const MANIFEST_FILE_NAME: &str = "sessions.csv";
+// header of each version of the history file schema, starting from the
+// second version, as files of the first one have no header
+const HISTORY_HEADERS: [&[&str]; 2] = [
+    &["raw_time", "penalty", "scramble", "date", "puzzle"],
+    &[
+        "raw_time", "penalty", "scramble", "date", "puzzle", "comment",
+    ],
+];
+const HISTORY_VERSION: usize = HISTORY_HEADERS.len();
+const MANIFEST_HEADER: [&str; 9] = [
+    "file",
+    "name",
+    "puzzle",
+    "created",
+    "inspection",
+    "scramble_length",
+    "stats",
+    "thresholds",
+    "bucket_width",
+];
 
 fn get_history_dir() -> Result<PathBuf> {
     let path = match dirs::data_local_dir() {
@@ -29,30 +60,426 @@ pub fn get_session_history_file(file_name: &str) -> Result<PathBuf> {
     Ok(get_history_dir()?.join(file_name))
 }
 
-pub fn read_history(path: PathBuf) -> Result<Session> {
+/// Number in the name of a `session_N.csv` history file.
+fn session_file_number(path: &Path) -> Option<usize> {
+    path.file_stem()
+        .and_then(OsStr::to_str)
+        .filter(|_| path.extension() == Some(OsStr::new("csv")))
+        .and_then(|stem| stem.strip_prefix("session_"))
+        .and_then(|number| number.parse().ok())
+}
+
+/// Sessions listed in the manifest, along with the errors of the lines of it
+/// that couldn't be read and were left out.
+pub struct Manifest {
+    pub sessions: Vec<SessionInfo>,
+    pub errors: Vec<String>,
+}
+
+/// Reads the session manifest, adding to it the history files it doesn't
+/// list yet, such as the ones of versions that only numbered sessions by
+/// their file name.
+///
+/// Lines that can't be read are left out, so the history files of their
+/// sessions are listed again as new sessions. The manifest is backed up
+/// first, as it is written again without them.
+pub fn read_manifest() -> Result<Manifest> {
+    let path = get_history_dir()?.join(MANIFEST_FILE_NAME);
+    let mut sessions = Vec::new();
+    let mut errors = Vec::new();
+
+    if path.exists() {
+        let mut rdr = csv::ReaderBuilder::new().flexible(true).from_path(&path)?;
+
+        for (idx, result) in rdr.records().enumerate() {
+            match result
+                .map_err(anyhow::Error::from)
+                .and_then(|record| session_info_from_record(&record))
+            {
+                Ok(info) => sessions.push(info),
+                Err(e) => errors.push(format!(
+                    "Couldn't load line {} of {}: {e:#}",
+                    idx + 2,
+                    path.display()
+                )),
+            }
+        }
+
+        if !errors.is_empty() {
+            let backup = path.with_extension("bak");
+            std::fs::copy(&path, &backup)?;
+            errors.push(format!(
+                "The manifest was backed up to {}",
+                backup.display()
+            ));
+        }
+    }
+
+    let mut unlisted = get_sessions_list()?
+        .into_iter()
+        .filter_map(|path| {
+            let number = session_file_number(&path)?;
+            let file = path.file_name()?.to_str()?.to_string();
+            Some((number, file))
+        })
+        .filter(|(_, file)| sessions.iter().all(|session| session.file != *file))
+        .collect::<Vec<(usize, String)>>();
+
+    if unlisted.is_empty() {
+        return Ok(Manifest { sessions, errors });
+    }
+
+    unlisted.sort_by_key(|(number, _)| *number);
+
+    for (number, file) in unlisted {
+        // a file that can't be read is still listed, to be reported when the
+        // session is loaded
+        let history = read_history(get_session_history_file(&file)?).ok();
+
+        sessions.push(SessionInfo {
+            name: format!("Session {number}"),
+            puzzle: history
+                .as_ref()
+                .map_or(Puzzle::default(), |session| session.puzzle),
+            created: history
+                .and_then(|session| session.solves.first().map(|solve| solve.date))
+                .unwrap_or_else(now),
+            settings: SessionSettings::default(),
+            file,
+        });
+    }
+
+    write_manifest(&sessions)?;
+
+    Ok(Manifest { sessions, errors })
+}
+
+fn session_info_from_record(record: &StringRecord) -> Result<SessionInfo> {
+    let field = |idx: usize| {
+        record
+            .get(idx)
+            .ok_or_else(|| anyhow!("missing field {}", idx + 1))
+    };
+
+    let scramble_length = match field(5)? {
+        "" => None,
+        length => Some(length.parse()?),
+    };
+
+    // manifests written before these settings existed don't have their
+    // columns
+    let defaults = SessionSettings::default();
+
+    let stats = match record.get(6) {
+        Some(stats) => stats::parse_tracked_stats(stats)?,
+        None => defaults.stats,
+    };
+
+    let thresholds = match record.get(7) {
+        Some(thresholds) => stats::parse_thresholds(thresholds)?,
+        None => defaults.thresholds,
+    };
+
+    let bucket_width = match record.get(8) {
+        Some(width) => match width.parse()? {
+            0 => bail!("bucket width can't be 0"),
+            width => width,
+        },
+        None => defaults.bucket_width,
+    };
+
+    Ok(SessionInfo {
+        file: field(0)?.to_string(),
+        name: field(1)?.to_string(),
+        puzzle: field(2)?.parse()?,
+        created: field(3)?.parse()?,
+        settings: SessionSettings {
+            inspection: field(4)?.parse()?,
+            scramble_length,
+            stats,
+            thresholds,
+            bucket_width,
+        },
+    })
+}
+
+pub fn write_manifest(sessions: &[SessionInfo]) -> Result<()> {
+    let path = get_history_dir()?.join(MANIFEST_FILE_NAME);
+    let mut wtr = csv::Writer::from_path(path.with_extension("tmp"))?;
+
+    wtr.write_record(MANIFEST_HEADER)?;
+
+    for session in sessions {
+        wtr.write_record(&[
+            session.file.clone(),
+            session.name.clone(),
+            session.puzzle.id().to_string(),
+            session.created.to_string(),
+            session.settings.inspection.to_string(),
+            session
+                .settings
+                .scramble_length
+                .map_or(String::new(), |length| length.to_string()),
+            stats::join_tracked_stats(&session.settings.stats),
+            stats::join_thresholds(&session.settings.thresholds),
+            session.settings.bucket_width.to_string(),
+        ])?;
+    }
+
+    wtr.flush()?;
+
+    std::fs::rename(path.with_extension("tmp"), path)?;
+
+    Ok(())
+}
+
+/// Sessions read from the export of another timer.
+pub struct Imported {
+    pub sessions: Vec<ImportedSession>,
+    /// Sessions that were left out as their puzzle isn't supported, named as
+    /// `name (puzzle)` with the puzzle as the other timer calls it.
+    pub skipped: Vec<String>,
+}
+
+/// Session read from the export of another timer, to be added after the
+/// sessions already there.
+pub struct ImportedSession {
+    pub name: String,
+    pub puzzle: Puzzle,
+    pub solves: Vec<Solve>,
+    /// Number of solves whose scramble couldn't be parsed, which are imported
+    /// without one.
+    pub unparsed_scrambles: usize,
+}
+
+impl ImportedSession {
+    pub fn new(name: &str, puzzle: Puzzle) -> Self {
+        Self {
+            name: name.to_string(),
+            puzzle,
+            solves: Vec::new(),
+            unparsed_scrambles: 0,
+        }
+    }
+
+    /// Adds a solve with the time before its penalty, keeping it without its
+    /// scramble if the scramble is in a notation that can't be read.
+    pub fn push(
+        &mut self,
+        time: u128,
+        penalty: Penalty,
+        scramble: &str,
+        date: u64,
+        comment: &str,
+    ) -> Result<()> {
+        let solve = |scramble| {
+            Solve::from_history_file(time, penalty as u8, scramble, date, comment, self.puzzle)
+        };
+
+        let solve = match solve(scramble) {
+            Ok(solve) => solve,
+            Err(_) => {
+                self.unparsed_scrambles += 1;
+                solve("")?
+            }
+        };
+
+        self.solves.push(solve);
+
+        Ok(())
+    }
+}
+
+/// Adds the imported sessions after the listed ones, with the settings given,
+/// writing their history files and then the manifest.
+pub fn import_sessions(
+    sessions: &mut Vec<SessionInfo>,
+    imported: &[ImportedSession],
+    settings: &SessionSettings,
+) -> Result<()> {
+    for session in imported {
+        let file = new_session_file(sessions)?;
+        update_history(
+            get_session_history_file(&file)?,
+            &session.solves,
+            session.puzzle,
+        )?;
+
+        sessions.push(SessionInfo {
+            file,
+            name: session.name.clone(),
+            puzzle: session.puzzle,
+            created: session.solves.first().map_or_else(now, |solve| solve.date),
+            settings: settings.clone(),
+        });
+    }
+
+    write_manifest(sessions)
+}
+
+/// Name for the history file of a new session, not used by any session or
+/// file already there.
+pub fn new_session_file(sessions: &[SessionInfo]) -> Result<String> {
+    let dir = get_history_dir()?;
+
+    Ok((sessions.len() + 1..)
+        .map(|number| format!("session_{number}.csv"))
+        .find(|file| {
+            sessions.iter().all(|session| session.file != *file) && !dir.join(file).exists()
+        })
+        .unwrap())
+}
+
+/// Current date in seconds since the epoch, as stored in history files.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Version of the history file schema, which is told by the header of the
+/// file. Files without a header, which were written before the schema was
+/// versioned, are version 0 and store times with the +2 penalty added.
+fn schema_version(first_record: Option<&StringRecord>) -> usize {
+    first_record
+        .and_then(|record| {
+            HISTORY_HEADERS
+                .iter()
+                .position(|header| record.iter().eq(header.iter().copied()))
+        })
+        .map_or(0, |idx| idx + 1)
+}
+
+fn file_schema_version(path: &Path) -> Result<usize> {
     let mut rdr = csv::ReaderBuilder::new()
         .has_headers(false)
+        .flexible(true)
         .from_path(path)?;
 
-    let mut session = Session::default();
+    Ok(schema_version(rdr.records().next().transpose()?.as_ref()))
+}
 
-    for result in rdr.records() {
-        let record = result?;
+/// Reads the solves of the session, which has no history file until a solve
+/// is added to it.
+pub fn read_session(info: &SessionInfo) -> Result<Session> {
+    let path = get_session_history_file(&info.file)?;
 
-        let solve = Solve::from_history_file(
-            record[0].parse()?,
-            record[1].parse()?,
-            &record[2],
-            record[3].parse()?,
-        );
+    if !path.exists() {
+        return Ok(Session {
+            puzzle: info.puzzle,
+            ..Session::default()
+        });
+    }
+
+    read_history(path)
+}
+
+/// Reads the solves of a history file, rewriting the file in the current
+/// schema if it was written in an older one.
+pub fn read_history(path: PathBuf) -> Result<Session> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_path(&path)?;
+
+    let mut records = rdr.records().enumerate().peekable();
+    let version = schema_version(records.peek().and_then(|(_, result)| result.as_ref().ok()));
+
+    if version > 0 {
+        records.next();
+    }
+
+    let mut session = Session::default();
 
+    for (idx, result) in records {
+        let (puzzle, solve) = result
+            .map_err(anyhow::Error::from)
+            .and_then(|record| solve_from_record(&record, version))
+            .with_context(|| format!("Couldn't load line {} of {}", idx + 1, path.display()))?;
+
+        session.puzzle = puzzle;
         session.solves.push(solve);
     }
 
+    if version < HISTORY_VERSION {
+        update_history(path, &session.solves, session.puzzle)?;
+    }
+
     Ok(session)
 }
 
-pub fn add_to_history(path: PathBuf, solve: &Solve) -> Result<()> {
+fn solve_from_record(record: &StringRecord, version: usize) -> Result<(Puzzle, Solve)> {
+    let field = |idx: usize| {
+        record
+            .get(idx)
+            .ok_or_else(|| anyhow!("missing field {}", idx + 1))
+    };
+
+    // files written before other puzzles were supported only hold 3x3 solves
+    let puzzle = match record.get(4) {
+        Some(id) => id.parse()?,
+        None => Puzzle::default(),
+    };
+
+    let time: u128 = field(0)?.parse()?;
+    let penalty: u8 = field(1)?.parse()?;
+
+    // the first version stored +2 solves with the penalty already added, so
+    // times below it can't have had it added and are kept as they are
+    let time = if version == 0 && penalty == Penalty::PlusTwo as u8 && time >= 2000 {
+        time - 2000
+    } else {
+        time
+    };
+
+    // comments were added in the third version
+    let comment = record.get(5).filter(|_| version >= 2).unwrap_or("");
+
+    let solve = Solve::from_history_file(
+        time,
+        penalty,
+        field(2)?,
+        field(3)?.parse()?,
+        comment,
+        puzzle,
+    )?;
+
+    Ok((puzzle, solve))
+}
+
+fn solve_to_record(solve: &Solve, puzzle: Puzzle, version: usize) -> Vec<String> {
+    let time = if version == 0 {
+        solve.time.time
+    } else {
+        solve.time.raw_time()
+    };
+
+    let mut record = vec![
+        time.to_string(),
+        (solve.time.penalty as u8).to_string(),
+        solve.scramble.to_string(),
+        solve.date.to_string(),
+        puzzle.id().to_string(),
+    ];
+
+    if version >= 2 {
+        record.push(solve.comment.clone());
+    }
+
+    record
+}
+
+/// Appends the solve to the history file, in the schema the file was
+/// written in so that files which couldn't be loaded and migrated stay
+/// consistent.
+pub fn add_to_history(path: PathBuf, solve: &Solve, puzzle: Puzzle) -> Result<()> {
+    let version = if path.exists() && std::fs::metadata(&path)?.len() > 0 {
+        Some(file_schema_version(&path)?)
+    } else {
+        None
+    };
+
     let file = std::fs::OpenOptions::new()
         .append(true)
         .create(true)
@@ -62,19 +489,22 @@ pub fn add_to_history(path: PathBuf, solve: &Solve) -> Result<()> {
         .has_headers(false)
         .from_writer(file);
 
-    wtr.write_record(&[
-        solve.time.time.to_string(),
-        (solve.time.penalty as u8).to_string(),
-        solve.scramble.to_string(),
-        solve.date.to_string(),
-    ])?;
+    if version.is_none() {
+        wtr.write_record(HISTORY_HEADERS[HISTORY_VERSION - 1])?;
+    }
+
+    wtr.write_record(solve_to_record(
+        solve,
+        puzzle,
+        version.unwrap_or(HISTORY_VERSION),
+    ))?;
 
     wtr.flush()?;
 
     Ok(())
 }
 
-pub fn update_history(path: PathBuf, solves: &[Solve]) -> Result<()> {
+pub fn update_history(path: PathBuf, solves: &[Solve], puzzle: Puzzle) -> Result<()> {
     let out_file = std::fs::OpenOptions::new()
         .write(true)
         .create(true)
@@ -85,13 +515,10 @@ pub fn update_history(path: PathBuf, solves: &[Solve]) -> Result<()> {
         .has_headers(false)
         .from_writer(out_file);
 
+    wtr.write_record(HISTORY_HEADERS[HISTORY_VERSION - 1])?;
+
     for solve in solves {
-        wtr.write_record(&[
-            solve.time.time.to_string(),
-            (solve.time.penalty as u8).to_string(),
-            solve.scramble.to_string(),
-            solve.date.to_string(),
-        ])?;
+        wtr.write_record(solve_to_record(solve, puzzle, HISTORY_VERSION))?;
     }
 
     wtr.flush()?;
@@ -100,3 +527,163 @@ pub fn update_history(path: PathBuf, solves: &[Solve]) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes the lines to a temporary history file of this test run.
+    fn history_file(name: &str, lines: &[&str]) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("{PACKAGE_NAME}-{}-{name}", std::process::id()));
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+
+        path
+    }
+
+    /// Time with the penalty, raw time, penalty, scramble, date and comment
+    /// of each solve.
+    fn solves(session: &Session) -> Vec<(u128, u128, u8, String, u64, String)> {
+        session
+            .solves
+            .iter()
+            .map(|solve| {
+                (
+                    solve.time.time,
+                    solve.time.raw_time(),
+                    solve.time.penalty as u8,
+                    solve.scramble.to_string(),
+                    solve.date,
+                    solve.comment.clone(),
+                )
+            })
+            .collect()
+    }
+
+    fn current_header() -> String {
+        HISTORY_HEADERS[HISTORY_VERSION - 1].join(",")
+    }
+
+    /// Reads the file, which must then be in the current schema, and reads it
+    /// again to check that it isn't migrated twice, before removing it.
+    fn read_and_reread(path: &Path) -> (Session, String) {
+        let session = read_history(path.to_path_buf()).unwrap();
+        let text = std::fs::read_to_string(path).unwrap();
+
+        let reread = read_history(path.to_path_buf()).unwrap();
+        assert_eq!(solves(&reread), solves(&session));
+        assert_eq!(std::fs::read_to_string(path).unwrap(), text);
+        std::fs::remove_file(path).unwrap();
+
+        (session, text)
+    }
+
+    #[test]
+    fn migrates_first_version() {
+        let path = history_file(
+            "version_0.csv",
+            &[
+                "12345,0,R U,1700000000",
+                "14000,1,R' F,1700000100",
+                "9000,2,,1700000200,333",
+                "1500,1,,1700000300,333",
+            ],
+        );
+
+        let (session, text) = read_and_reread(&path);
+
+        assert!(session.puzzle == Puzzle::Cube(3));
+        assert_eq!(
+            solves(&session),
+            [
+                (12345, 12345, 0, "R U", 1700000000),
+                (14000, 12000, 1, "R' F", 1700000100),
+                (9000, 9000, 2, "", 1700000200),
+                // times below the penalty can't have had it added
+                (3500, 1500, 1, "", 1700000300),
+            ]
+            .map(|(time, raw, penalty, scramble, date)| {
+                (
+                    time,
+                    raw,
+                    penalty,
+                    scramble.to_string(),
+                    date,
+                    String::new(),
+                )
+            })
+        );
+        assert_eq!(
+            text.lines().collect::<Vec<&str>>(),
+            [
+                &current_header(),
+                "12345,0,R U,1700000000,333,",
+                "12000,1,R' F,1700000100,333,",
+                "9000,2,,1700000200,333,",
+                "1500,1,,1700000300,333,",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_line_that_fails() {
+        let path = history_file(
+            "invalid.csv",
+            &[
+                &current_header(),
+                "12000,0,R U,1700000000,333,",
+                "9000,0,R U Q2,1700000100,333,",
+            ],
+        );
+
+        let error = format!("{:#}", read_history(path.clone()).err().unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(error.starts_with(&format!("Couldn't load line 3 of {}", path.display())));
+        assert!(error.ends_with("invalid move 'Q2' at position 3: unexpected face character 'Q'"));
+    }
+
+    #[test]
+    fn migrates_second_version() {
+        let path = history_file(
+            "version_1.csv",
+            &[
+                "raw_time,penalty,scramble,date,puzzle",
+                "12000,1,R U,1700000000,222",
+                "9000,2,R,1700000100,222",
+                "1500,1,,1700000200,222",
+            ],
+        );
+
+        let (session, text) = read_and_reread(&path);
+
+        assert!(session.puzzle == Puzzle::Cube(2));
+        assert_eq!(
+            solves(&session),
+            [
+                (14000, 12000, 1, "R U", 1700000000),
+                (9000, 9000, 2, "R", 1700000100),
+                (3500, 1500, 1, "", 1700000200),
+            ]
+            .map(|(time, raw, penalty, scramble, date)| {
+                (
+                    time,
+                    raw,
+                    penalty,
+                    scramble.to_string(),
+                    date,
+                    String::new(),
+                )
+            })
+        );
+        assert_eq!(
+            text.lines().collect::<Vec<&str>>(),
+            [
+                &current_header(),
+                "12000,1,R U,1700000000,222,",
+                "9000,2,R,1700000100,222,",
+                "1500,1,,1700000200,222,",
+            ]
+        );
+    }
+}
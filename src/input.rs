@@ -1,5 +1,6 @@
-use crate::app::{App, AppState, Confirmation};
+use crate::app::{App, AppState, Confirmation, Prompt};
 use anyhow::Result;
+use chrono::Local;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 pub fn handle_key(key: KeyEvent, app: &mut App) -> Result<bool> {
@@ -11,6 +12,26 @@ pub fn handle_key(key: KeyEvent, app: &mut App) -> Result<bool> {
         return Ok(false);
     }
 
+    if app.error.is_some() || app.notice.is_some() {
+        app.error = None;
+        app.notice = None;
+        return Ok(false);
+    }
+
+    if let Some(input) = &mut app.input {
+        match key.code {
+            KeyCode::Enter => app.submit_input()?,
+            KeyCode::Esc => app.input = None,
+            KeyCode::Backspace => {
+                input.text.pop();
+            }
+            KeyCode::Char(ch) => input.text.push(ch),
+            _ => (),
+        }
+
+        return Ok(false);
+    }
+
     match app.state {
         _ if app.confirmation.is_some() => match key.code {
             KeyCode::Char('y') => match app.confirmation {
@@ -23,6 +44,11 @@ pub fn handle_key(key: KeyEvent, app: &mut App) -> Result<bool> {
         },
         AppState::Idle if !app.inspection.is_running() => (),
         AppState::SolveInfo => return handle_solve_info_key(key, app),
+        AppState::Histogram => return handle_histogram_key(key, app),
+        AppState::PbHistory => return handle_pb_history_key(key, app),
+        AppState::AverageDetail => return handle_average_detail_key(key, app),
+        AppState::GlobalStats => return handle_global_stats_key(key, app),
+        AppState::Calendar => return handle_calendar_key(key, app),
         _ => return handle_timer_key(key, app),
     }
 
@@ -47,33 +73,45 @@ pub fn handle_key(key: KeyEvent, app: &mut App) -> Result<bool> {
             KeyCode::Char('r') => app.generate_scramble(),
             KeyCode::Char('R') => {
                 if let Some(scramble) = &app.last_scramble {
-                    app.scramble = scramble.clone();
+                    app.scramble = Some(scramble.clone());
                     app.generate_scramble_preview();
                 }
             }
-            KeyCode::Char('i') => {
-                if app.session.selected_idx().is_some() {
-                    app.state = AppState::SolveInfo
-                }
-            }
-            KeyCode::Char('e') => app.inspection_enabled = !app.inspection_enabled,
-            KeyCode::Char('E') => {
-                app.inspection_warning_enabled = !app.inspection_warning_enabled;
+            KeyCode::Char('i') if app.session.selected_idx().is_some() => {
+                app.state = AppState::SolveInfo;
             }
+            KeyCode::Char('h') => app.state = AppState::Histogram,
+            KeyCode::Char('b') => app.state = AppState::PbHistory,
+            KeyCode::Char('w') => app.show_average_detail(),
+            KeyCode::Char('o') => app.show_global_stats()?,
+            KeyCode::Char('m') => app.show_calendar(),
+            KeyCode::Char('e') => app.toggle_inspection()?,
+            KeyCode::Char('E') => app.toggle_inspection_warning(),
             KeyCode::Char('d') => app.delete_selected_solve()?,
             KeyCode::Char('p') => app.toggle_plus_two()?,
             KeyCode::Char('D') => app.toggle_dnf()?,
             KeyCode::Char('c') => app.delete_session()?,
+            KeyCode::Char('t') => app.next_puzzle()?,
+            KeyCode::Char('T') => app.previous_puzzle()?,
             KeyCode::Char('s') => app.next_session()?,
             KeyCode::Char('S') => app.previous_session()?,
-            KeyCode::Char(' ') => {
-                if !app.inspection.has_expired() {
-                    if app.inspection_enabled && !app.inspection.is_running() {
-                        app.start_inspecting();
-                    }
-
-                    app.state = AppState::Ready;
+            KeyCode::Char('>') => app.move_session(1)?,
+            KeyCode::Char('<') => app.move_session(-1)?,
+            KeyCode::Char('n') => app.start_input(Prompt::Rename),
+            KeyCode::Char('a') => app.start_input(Prompt::Stats),
+            KeyCode::Char('I') => app.start_input(Prompt::Import),
+            KeyCode::Char('X') => app.start_input(Prompt::Export),
+            KeyCode::Char('v') => app.chart_enabled = !app.chart_enabled,
+            KeyCode::Char('z') => app.zoom_chart_in(),
+            KeyCode::Char('Z') => app.zoom_chart_out(),
+            KeyCode::Char('+') => app.change_scramble_length(5)?,
+            KeyCode::Char('-') => app.change_scramble_length(-5)?,
+            KeyCode::Char(' ') if !app.inspection.has_expired() => {
+                if app.settings().inspection && !app.inspection.is_running() {
+                    app.start_inspecting();
                 }
+
+                app.state = AppState::Ready;
             }
             _ => (),
         }
@@ -119,6 +157,77 @@ fn handle_solve_info_key(key: KeyEvent, app: &mut App) -> Result<bool> {
     Ok(false)
 }
 
+fn handle_histogram_key(key: KeyEvent, app: &mut App) -> Result<bool> {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('h') => app.state = AppState::Idle,
+        KeyCode::Char('q') => return Ok(true),
+        KeyCode::Char('+') => app.change_bucket_width(1)?,
+        KeyCode::Char('-') => app.change_bucket_width(-1)?,
+        KeyCode::Char('x') => app.start_input(Prompt::Thresholds),
+        _ => (),
+    }
+
+    Ok(false)
+}
+
+fn handle_pb_history_key(key: KeyEvent, app: &mut App) -> Result<bool> {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('b') => app.state = AppState::Idle,
+        KeyCode::Char('q') => return Ok(true),
+        KeyCode::Char('l') | KeyCode::Right | KeyCode::Tab => app.cycle_pb_history(1),
+        KeyCode::Char('h') | KeyCode::Left | KeyCode::BackTab => app.cycle_pb_history(-1),
+        _ => (),
+    }
+
+    Ok(false)
+}
+
+fn handle_average_detail_key(key: KeyEvent, app: &mut App) -> Result<bool> {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('w') => app.state = AppState::Idle,
+        KeyCode::Char('q') => return Ok(true),
+        KeyCode::Char('l') | KeyCode::Right => app.cycle_average_detail(1),
+        KeyCode::Char('h') | KeyCode::Left => app.cycle_average_detail(-1),
+        KeyCode::Char('j') | KeyCode::Char('k') | KeyCode::Down | KeyCode::Up | KeyCode::Tab => {
+            app.detail_best = !app.detail_best;
+        }
+        _ => (),
+    }
+
+    Ok(false)
+}
+
+fn handle_global_stats_key(key: KeyEvent, app: &mut App) -> Result<bool> {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('o') => {
+            app.state = AppState::Idle;
+            app.global_stats = None;
+        }
+        KeyCode::Char('q') => return Ok(true),
+        _ => (),
+    }
+
+    Ok(false)
+}
+
+fn handle_calendar_key(key: KeyEvent, app: &mut App) -> Result<bool> {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('m') => {
+            app.state = AppState::Idle;
+            app.calendar = None;
+        }
+        KeyCode::Char('q') => return Ok(true),
+        KeyCode::Char('h') | KeyCode::Left => app.move_calendar_day(-7),
+        KeyCode::Char('l') | KeyCode::Right => app.move_calendar_day(7),
+        KeyCode::Char('k') | KeyCode::Up => app.move_calendar_day(-1),
+        KeyCode::Char('j') | KeyCode::Down => app.move_calendar_day(1),
+        KeyCode::Char('g') => app.calendar_day = Local::now().date_naive(),
+        _ => (),
+    }
+
+    Ok(false)
+}
+
 pub fn on_space_release(app: &mut App) {
     match app.state {
         AppState::Set => {
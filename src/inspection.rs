@@ -1,22 +1,34 @@
-use crate::app::Penalty;
+use crate::{app::Penalty, config::Config};
 use anyhow::Result;
 use rodio::{Sink, Source};
 use std::time::{Duration, SystemTime};
 
-pub const INSPECTION_DURATION: u64 = 15;
+// seconds after the end of inspection during which starting the solve only
+// gets a +2 instead of a DNF
+const PLUS_TWO_DURATION: u64 = 2;
 
 pub struct Inspection {
     starting_time: Option<SystemTime>,
     pub penalty: Penalty,
-    played_sound: u8,
+    played_sound: usize,
+    duration: u64,
+    /// Seconds at which the warnings sound, with the frequency of their tone.
+    warnings: Vec<(u64, f32)>,
 }
 
 impl Inspection {
-    pub fn new() -> Self {
+    pub fn new(config: &Config) -> Self {
         Inspection {
             starting_time: None,
             penalty: Penalty::Ok,
             played_sound: 0,
+            duration: config.inspection_duration,
+            warnings: config
+                .inspection_warnings
+                .iter()
+                .copied()
+                .zip(config.warning_tones.iter().copied())
+                .collect(),
         }
     }
 
@@ -27,8 +39,8 @@ impl Inspection {
     pub fn stop(&mut self) {
         if let Some(elapsed) = self.starting_time.and_then(|time| time.elapsed().ok()) {
             self.penalty = match elapsed.as_secs() {
-                ..15 => Penalty::Ok,
-                15..17 => Penalty::PlusTwo,
+                secs if secs < self.duration => Penalty::Ok,
+                secs if secs < self.duration + PLUS_TWO_DURATION => Penalty::PlusTwo,
                 _ => Penalty::Dnf,
             }
         }
@@ -43,24 +55,27 @@ impl Inspection {
             .map(|elapsed| elapsed.as_secs())
     }
 
+    /// Seconds left before the end of inspection, while it is running.
+    pub fn remaining(&self) -> Option<u64> {
+        self.elapsed()
+            .map(|elapsed| self.duration.saturating_sub(elapsed))
+    }
+
     pub fn tick(&mut self, warning: bool) -> bool {
         if !self.is_running() {
             return true;
         }
 
         if let Some(elapsed) = self.elapsed() {
-            if elapsed < 15 {
-                if warning {
-                    if elapsed == 8 && self.played_sound == 0 {
-                        play_sound(425.0);
-                        self.played_sound = 1;
-                    } else if elapsed == 12 && self.played_sound == 1 {
-                        play_sound(480.0);
-                        self.played_sound = 2;
+            if elapsed < self.duration {
+                if let Some(&(second, frequency)) = self.warnings.get(self.played_sound) {
+                    if warning && elapsed == second {
+                        play_sound(frequency);
+                        self.played_sound += 1;
                     }
                 }
                 return true;
-            } else if elapsed < 17 {
+            } else if elapsed < self.duration + PLUS_TWO_DURATION {
                 return true;
             }
         }
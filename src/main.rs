@@ -1,16 +1,33 @@
 mod app;
+mod cli;
+mod clock;
+mod config;
+mod cstimer;
 mod cube;
+mod cubie;
+mod error;
+mod geometry;
 mod history;
 mod input;
 mod inspection;
+mod megaminx;
+mod puzzle;
+mod pyraminx;
 mod scramble;
 mod session;
+mod skewb;
+mod solver;
+mod square1;
 mod stats;
 mod timer;
+mod transfer;
+mod twisty;
 mod ui;
 
 use anyhow::Result;
 use app::{App, AppState, Penalty};
+use clap::Parser;
+use cli::Cli;
 use crossterm::{
     event::{self, Event, KeyboardEnhancementFlags},
     execute, queue,
@@ -26,10 +43,15 @@ use std::time::{Duration, Instant};
 use ui::render;
 
 fn main() -> Result<()> {
-    let mut terminal = init_terminal()?;
-    terminal.clear()?;
+    if let Some(command) = Cli::parse().command {
+        return cli::run(command);
+    }
 
+    // errors while loading are printed before the terminal is taken over
     let mut app = App::new()?;
+
+    let mut terminal = init_terminal()?;
+    terminal.clear()?;
     let res = run_tui(&mut terminal, &mut app);
 
     reset_terminal()?;
@@ -46,7 +68,9 @@ fn run_tui(terminal: &mut DefaultTerminal, app: &mut App) -> Result<()> {
     let mut last_tick = Instant::now();
 
     loop {
-        if !app.inspection.tick(app.inspection_warning_enabled) {
+        app.poll_scramble();
+
+        if !app.inspection.tick(app.config.inspection_warning_enabled) {
             app.add_solve()?;
             app.state = AppState::Idle;
             app.generate_scramble();
@@ -0,0 +1,412 @@
+use crate::{
+    error::ParseError,
+    geometry::{add, cross, distance, dot, rotate, scale, Point},
+};
+use rand::Rng;
+use ratatui::style::Color;
+use std::{f64::consts::PI, fmt::Display, str::FromStr, sync::OnceLock};
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Face {
+    Up,
+    Front,
+    Left,
+    BackLeft,
+    BackRight,
+    Right,
+    Back,
+    DownBackRight,
+    DownRight,
+    DownLeft,
+    DownBackLeft,
+    Down,
+}
+
+impl From<Face> for Color {
+    fn from(value: Face) -> Self {
+        match value {
+            Face::Up => Color::Rgb(255, 255, 255),
+            Face::Front => Color::Rgb(2, 208, 64),
+            Face::Left => Color::Rgb(142, 68, 173),
+            Face::BackLeft => Color::Rgb(253, 216, 53),
+            Face::BackRight => Color::Rgb(48, 79, 254),
+            Face::Right => Color::Rgb(236, 0, 0),
+            Face::Back => Color::Rgb(160, 230, 120),
+            Face::DownBackRight => Color::Rgb(255, 140, 200),
+            Face::DownRight => Color::Rgb(255, 240, 170),
+            Face::DownLeft => Color::Rgb(120, 200, 255),
+            Face::DownBackLeft => Color::Rgb(255, 139, 36),
+            Face::Down => Color::Rgb(160, 160, 160),
+        }
+    }
+}
+
+/// Faces in the order they are stored: U, the ring of faces around it
+/// clockwise from F, the faces opposite to that ring in the same order and D.
+pub const FACES: [Face; 12] = [
+    Face::Up,
+    Face::Front,
+    Face::Left,
+    Face::BackLeft,
+    Face::BackRight,
+    Face::Right,
+    Face::Back,
+    Face::DownBackRight,
+    Face::DownRight,
+    Face::DownLeft,
+    Face::DownBackLeft,
+    Face::Down,
+];
+
+/// Stickers per face: the center followed by each corner and the edge
+/// clockwise after it.
+pub const STICKERS: usize = 11;
+
+/// Lines of a WCA scramble, each one ending with a turn of U.
+const SCRAMBLE_LINES: usize = 7;
+const LINE_LENGTH: usize = 10;
+
+// tolerance when comparing points of the puzzle
+const EPSILON: f64 = 1e-6;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Turn {
+    /// Everything but the L face, around the axis of the face opposite to it.
+    Right,
+    /// Everything but the U face, around the axis of D.
+    Down,
+    Up,
+}
+
+/// Move in Pochmann notation, where `R` and `D` turn by two fifths and `U`
+/// by one.
+#[derive(PartialEq, Clone)]
+pub struct Move {
+    pub turn: Turn,
+    pub clockwise: bool,
+}
+
+impl FromStr for Move {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<Self, ParseError> {
+        let mut chars = value.chars();
+
+        let turn = match chars.next().ok_or(ParseError::EmptyMove)? {
+            'R' => Turn::Right,
+            'D' => Turn::Down,
+            'U' => Turn::Up,
+            ch => return Err(ParseError::Face(ch)),
+        };
+
+        let rest = chars.as_str();
+
+        let (clockwise, rest) = match turn {
+            Turn::Up => match rest.strip_prefix('\'') {
+                Some(rest) => (false, rest),
+                None => (true, rest),
+            },
+            _ => match (rest.strip_prefix("++"), rest.strip_prefix("--")) {
+                (Some(rest), _) => (true, rest),
+                (_, Some(rest)) => (false, rest),
+                _ => {
+                    return Err(rest
+                        .chars()
+                        .find(|&ch| ch != '+' && ch != '-')
+                        .map_or(ParseError::MissingRotation, ParseError::Rotation))
+                }
+            },
+        };
+
+        if !rest.is_empty() {
+            return Err(ParseError::Trailing(rest.to_string()));
+        }
+
+        Ok(Self { turn, clockwise })
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.turn, self.clockwise) {
+            (Turn::Right, true) => write!(f, "R++"),
+            (Turn::Right, false) => write!(f, "R--"),
+            (Turn::Down, true) => write!(f, "D++"),
+            (Turn::Down, false) => write!(f, "D--"),
+            (Turn::Up, true) => write!(f, "U"),
+            (Turn::Up, false) => write!(f, "U'"),
+        }
+    }
+}
+
+/// Shape of the dodecahedron and the sticker permutation of every move.
+struct Geometry {
+    /// Corners of each face as indices into the corners of the puzzle,
+    /// clockwise as seen from outside.
+    faces: [[usize; 5]; 12],
+    /// Destination of every sticker for each turn, clockwise and then
+    /// counter-clockwise.
+    moves: [Vec<usize>; 6],
+}
+
+impl Geometry {
+    fn get() -> &'static Self {
+        static GEOMETRY: OnceLock<Geometry> = OnceLock::new();
+        GEOMETRY.get_or_init(Self::generate)
+    }
+
+    fn generate() -> Self {
+        let normals = normals();
+        let mut corners: Vec<Point> = Vec::new();
+
+        let faces = normals.map(|normal| {
+            let neighbors = normals
+                .iter()
+                .filter(|&&other| (dot(normal, other) - 5.0_f64.sqrt().recip()).abs() < EPSILON)
+                .collect::<Vec<&Point>>();
+
+            let mut face_corners = Vec::new();
+
+            for (i, &&a) in neighbors.iter().enumerate() {
+                for &&b in &neighbors[i + 1..] {
+                    if dot(a, b) > 0.0 {
+                        let corner = add(add(normal, a), b);
+                        face_corners.push(scale(corner, dot(corner, normal).recip()));
+                    }
+                }
+            }
+
+            // order the corners clockwise, that is by decreasing angle in a
+            // basis of the face's plane that turns counter-clockwise
+            let e1 = cross(
+                normal,
+                if normal[1].abs() < 0.9 {
+                    [0.0, 1.0, 0.0]
+                } else {
+                    [0.0, 0.0, 1.0]
+                },
+            );
+            let e2 = cross(normal, e1);
+            let angle = |p: &Point| dot(*p, e2).atan2(dot(*p, e1));
+            face_corners.sort_by(|a, b| angle(b).total_cmp(&angle(a)));
+
+            let indices = face_corners.iter().map(|&corner| {
+                match corners.iter().position(|&c| distance(c, corner) < EPSILON) {
+                    Some(idx) => idx,
+                    None => {
+                        corners.push(corner);
+                        corners.len() - 1
+                    }
+                }
+            });
+
+            let mut result = [0; 5];
+            result.iter_mut().zip(indices).for_each(|(r, idx)| *r = idx);
+            result
+        });
+
+        // position of each sticker and of the piece it belongs to, with the
+        // sticker slightly raised off its piece towards the outside of its face
+        let stickers = (0..12 * STICKERS)
+            .map(|idx| {
+                let (face, sticker) = (idx / STICKERS, idx % STICKERS);
+                let normal = normals[face];
+                let corner = |k: usize| corners[faces[face][k % 5]];
+
+                let piece = match sticker {
+                    0 => normal,
+                    s if s % 2 == 1 => corner(s / 2),
+                    s => scale(add(corner(s / 2 - 1), corner(s / 2)), 0.5),
+                };
+
+                (add(piece, scale(normal, 0.3)), piece)
+            })
+            .collect::<Vec<(Point, Point)>>();
+
+        let turn = |axis: Point, angle: f64, is_turned: &dyn Fn(Point) -> bool| {
+            let axis = scale(axis, dot(axis, axis).sqrt().recip());
+
+            stickers
+                .iter()
+                .enumerate()
+                .map(|(idx, &(position, piece))| {
+                    if !is_turned(piece) {
+                        return idx;
+                    }
+
+                    let position = rotate(position, axis, angle);
+
+                    stickers
+                        .iter()
+                        .position(|&(other, _)| distance(other, position) < EPSILON)
+                        .unwrap()
+                })
+                .collect::<Vec<usize>>()
+        };
+
+        let [up, left, down] = [0, 2, 11].map(|face| normals[face]);
+        let on_face = |normal: Point| move |piece: Point| dot(piece, normal) > 1.0 - EPSILON;
+        let off_face = |normal: Point| move |piece: Point| dot(piece, normal) < 1.0 - EPSILON;
+        let fifth = 2.0 * PI / 5.0;
+
+        let moves = [
+            turn(scale(left, -1.0), -2.0 * fifth, &off_face(left)),
+            turn(down, -2.0 * fifth, &off_face(up)),
+            turn(up, -fifth, &on_face(up)),
+            turn(scale(left, -1.0), 2.0 * fifth, &off_face(left)),
+            turn(down, 2.0 * fifth, &off_face(up)),
+            turn(up, fifth, &on_face(up)),
+        ];
+
+        Self { faces, moves }
+    }
+}
+
+/// Outward normals of the faces, at unit distance from the center.
+fn normals() -> [Point; 12] {
+    let (y, r) = (5.0_f64.sqrt().recip(), 2.0 / 5.0_f64.sqrt());
+
+    std::array::from_fn(|idx| match idx {
+        0 => [0.0, 1.0, 0.0],
+        11 => [0.0, -1.0, 0.0],
+        // going clockwise around U from F, which faces the viewer
+        1..=5 => {
+            let angle = -2.0 * PI / 5.0 * (idx - 1) as f64;
+            [r * angle.sin(), y, r * angle.cos()]
+        }
+        _ => {
+            let angle = -2.0 * PI / 5.0 * (idx - 6) as f64;
+            [-r * angle.sin(), -y, -r * angle.cos()]
+        }
+    })
+}
+
+/// Corners of every face in a flattened net made of two stars, the faces
+/// around U on the left with F below it and the faces around D on the right
+/// with B below it. Corners are listed clockwise in the order of the
+/// stickers and faces are one unit wide.
+pub fn net_layout() -> [[(f64, f64); 5]; 12] {
+    let geometry = Geometry::get();
+    let mut layout = [[(0.0, 0.0); 5]; 12];
+
+    for (center, below) in [(0, 1), (11, 6)] {
+        let face = geometry.faces[center];
+        let shared = (0..5)
+            .find(|&k| {
+                let next = face[(k + 1) % 5];
+                geometry.faces[below].contains(&face[k]) && geometry.faces[below].contains(&next)
+            })
+            .unwrap();
+
+        place_face(&mut layout[center], shared, (0.5, 0.0), (-0.5, 0.0));
+
+        for k in 0..5 {
+            let (a, b) = (face[k], face[(k + 1) % 5]);
+            let neighbor = (0..12)
+                .find(|&f| {
+                    f != center && geometry.faces[f].contains(&a) && geometry.faces[f].contains(&b)
+                })
+                .unwrap();
+            let start = geometry.faces[neighbor]
+                .iter()
+                .position(|&c| c == b)
+                .unwrap();
+
+            let corners = layout[center];
+            place_face(
+                &mut layout[neighbor],
+                start,
+                corners[(k + 1) % 5],
+                corners[k],
+            );
+        }
+    }
+
+    // move the star around D to the right of the one around U
+    let max_x =
+        |faces: &[[(f64, f64); 5]]| faces.iter().flatten().map(|p| p.0).fold(f64::MIN, f64::max);
+    let min_x =
+        |faces: &[[(f64, f64); 5]]| faces.iter().flatten().map(|p| p.0).fold(f64::MAX, f64::min);
+    let (up_star, down_star) = layout.split_at_mut(6);
+    let shift = max_x(up_star) - min_x(down_star) + 0.4;
+
+    for (x, _) in down_star.iter_mut().flatten() {
+        *x += shift;
+    }
+
+    layout
+}
+
+/// Places the corners of a regular pentagon given two of them that come one
+/// after the other going clockwise.
+fn place_face(corners: &mut [(f64, f64); 5], start: usize, first: (f64, f64), second: (f64, f64)) {
+    let (dx, dy) = (second.0 - first.0, second.1 - first.1);
+    let side = dx.hypot(dy);
+    let inradius = side / 2.0 / (PI / 5.0).tan();
+    // going clockwise, the center is on the right of each side
+    let center = (
+        (first.0 + second.0) / 2.0 + dy / side * inradius,
+        (first.1 + second.1) / 2.0 - dx / side * inradius,
+    );
+
+    for m in 0..5 {
+        let (sin, cos) = (-2.0 * PI / 5.0 * m as f64).sin_cos();
+        let (x, y) = (first.0 - center.0, first.1 - center.1);
+        corners[(start + m) % 5] = (center.0 + x * cos - y * sin, center.1 + x * sin + y * cos);
+    }
+}
+
+#[derive(Clone)]
+pub struct Megaminx {
+    pub facelets: Vec<Face>,
+}
+
+impl Megaminx {
+    pub fn new() -> Self {
+        Self {
+            facelets: FACES
+                .iter()
+                .flat_map(|&face| std::iter::repeat_n(face, STICKERS))
+                .collect(),
+        }
+    }
+
+    pub fn apply_move(&mut self, r#move: &Move) {
+        let turn = match r#move.turn {
+            Turn::Right => 0,
+            Turn::Down => 1,
+            Turn::Up => 2,
+        };
+        let permutation = &Geometry::get().moves[turn + if r#move.clockwise { 0 } else { 3 }];
+        let mut facelets = self.facelets.clone();
+
+        for (idx, &face) in self.facelets.iter().enumerate() {
+            facelets[permutation[idx]] = face;
+        }
+
+        self.facelets = facelets;
+    }
+}
+
+/// Generates a scramble of random `R` and `D` moves in the WCA format, with
+/// every line ending in a turn of U in the direction of the last `D` move.
+pub fn random_scramble() -> Vec<Move> {
+    let mut rng = rand::rng();
+    let mut moves = Vec::with_capacity(SCRAMBLE_LINES * (LINE_LENGTH + 1));
+
+    for _ in 0..SCRAMBLE_LINES {
+        for i in 0..LINE_LENGTH {
+            moves.push(Move {
+                turn: if i % 2 == 0 { Turn::Right } else { Turn::Down },
+                clockwise: rng.random(),
+            });
+        }
+
+        moves.push(Move {
+            turn: Turn::Up,
+            clockwise: moves.last().unwrap().clockwise,
+        });
+    }
+
+    moves
+}
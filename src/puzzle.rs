@@ -0,0 +1,149 @@
+use crate::{
+    clock::Clock, cube::Cube, error::ParseError, megaminx::Megaminx, pyraminx::Pyraminx,
+    scramble::Scramble, skewb::Skewb, square1::Square1,
+};
+use std::{fmt::Display, str::FromStr};
+
+/// Puzzle that scrambles are generated for and that a session's solves were
+/// done on.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum Puzzle {
+    /// NxN cube of the given size.
+    Cube(usize),
+    Pyraminx,
+    Megaminx,
+    Skewb,
+    Square1,
+    Clock,
+}
+
+// puzzles in the order they are cycled through
+const PUZZLES: [Puzzle; 11] = [
+    Puzzle::Cube(2),
+    Puzzle::Cube(3),
+    Puzzle::Cube(4),
+    Puzzle::Cube(5),
+    Puzzle::Cube(6),
+    Puzzle::Cube(7),
+    Puzzle::Pyraminx,
+    Puzzle::Megaminx,
+    Puzzle::Skewb,
+    Puzzle::Square1,
+    Puzzle::Clock,
+];
+
+impl Puzzle {
+    /// Identifier of the puzzle in history files, following the WCA event
+    /// ids.
+    pub fn id(self) -> &'static str {
+        match self {
+            Puzzle::Cube(2) => "222",
+            Puzzle::Cube(3) => "333",
+            Puzzle::Cube(4) => "444",
+            Puzzle::Cube(5) => "555",
+            Puzzle::Cube(6) => "666",
+            Puzzle::Cube(7) => "777",
+            Puzzle::Cube(_) => unreachable!(),
+            Puzzle::Pyraminx => "pyram",
+            Puzzle::Megaminx => "minx",
+            Puzzle::Skewb => "skewb",
+            Puzzle::Square1 => "sq1",
+            Puzzle::Clock => "clock",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let idx = PUZZLES.iter().position(|p| *p == self).unwrap();
+        PUZZLES[(idx + 1).min(PUZZLES.len() - 1)]
+    }
+
+    pub fn previous(self) -> Self {
+        let idx = PUZZLES.iter().position(|p| *p == self).unwrap();
+        PUZZLES[idx.saturating_sub(1)]
+    }
+}
+
+impl Default for Puzzle {
+    fn default() -> Self {
+        Puzzle::Cube(3)
+    }
+}
+
+impl Display for Puzzle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Puzzle::Cube(size) => write!(f, "{size}x{size}"),
+            Puzzle::Pyraminx => write!(f, "Pyraminx"),
+            Puzzle::Megaminx => write!(f, "Megaminx"),
+            Puzzle::Skewb => write!(f, "Skewb"),
+            Puzzle::Square1 => write!(f, "Square-1"),
+            Puzzle::Clock => write!(f, "Clock"),
+        }
+    }
+}
+
+impl FromStr for Puzzle {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<Self, ParseError> {
+        PUZZLES
+            .into_iter()
+            .find(|puzzle| puzzle.id() == value)
+            .ok_or_else(|| ParseError::Puzzle(value.to_string()))
+    }
+}
+
+/// State of the puzzle after the current scramble, shown in the preview pane.
+pub enum Preview {
+    Cube(Cube),
+    Pyraminx(Pyraminx),
+    Megaminx(Megaminx),
+    Skewb(Skewb),
+    Square1(Square1),
+    Clock(Clock),
+}
+
+impl Preview {
+    /// Preview of the puzzle after the scramble, or of the solved puzzle
+    /// while there is none.
+    pub fn new(puzzle: Puzzle, scramble: Option<&Scramble>) -> Self {
+        match (puzzle, scramble) {
+            (Puzzle::Cube(size), Some(Scramble::Cube(moves))) => {
+                let mut cube = Cube::new(size);
+                moves.iter().for_each(|r#move| cube.apply_move(r#move));
+                Preview::Cube(cube)
+            }
+            (Puzzle::Pyraminx, Some(Scramble::Pyraminx(moves))) => {
+                let mut pyraminx = Pyraminx::new();
+                moves.iter().for_each(|r#move| pyraminx.apply_move(r#move));
+                Preview::Pyraminx(pyraminx)
+            }
+            (Puzzle::Megaminx, Some(Scramble::Megaminx(moves))) => {
+                let mut megaminx = Megaminx::new();
+                moves.iter().for_each(|r#move| megaminx.apply_move(r#move));
+                Preview::Megaminx(megaminx)
+            }
+            (Puzzle::Skewb, Some(Scramble::Skewb(moves))) => {
+                let mut skewb = Skewb::new();
+                moves.iter().for_each(|r#move| skewb.apply_move(r#move));
+                Preview::Skewb(skewb)
+            }
+            (Puzzle::Square1, Some(Scramble::Square1(moves))) => {
+                let mut square1 = Square1::new();
+                moves.iter().for_each(|r#move| square1.apply_move(r#move));
+                Preview::Square1(square1)
+            }
+            (Puzzle::Clock, Some(Scramble::Clock(moves))) => {
+                let mut clock = Clock::new();
+                moves.iter().for_each(|r#move| clock.apply_move(r#move));
+                Preview::Clock(clock)
+            }
+            (Puzzle::Cube(size), _) => Preview::Cube(Cube::new(size)),
+            (Puzzle::Pyraminx, _) => Preview::Pyraminx(Pyraminx::new()),
+            (Puzzle::Megaminx, _) => Preview::Megaminx(Megaminx::new()),
+            (Puzzle::Skewb, _) => Preview::Skewb(Skewb::new()),
+            (Puzzle::Square1, _) => Preview::Square1(Square1::new()),
+            (Puzzle::Clock, _) => Preview::Clock(Clock::new()),
+        }
+    }
+}
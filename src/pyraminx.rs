@@ -0,0 +1,463 @@
+use crate::{
+    error::ParseError,
+    solver::{permutation_index, set_permutation},
+};
+use rand::Rng;
+use ratatui::style::Color;
+use std::{fmt::Display, str::FromStr, sync::OnceLock};
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Face {
+    Front,
+    Left,
+    Right,
+    Down,
+}
+
+impl From<Face> for Color {
+    fn from(value: Face) -> Self {
+        match value {
+            Face::Front => Color::Rgb(2, 208, 64),
+            Face::Left => Color::Rgb(236, 0, 0),
+            Face::Right => Color::Rgb(48, 79, 254),
+            Face::Down => Color::Rgb(253, 216, 53),
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Vertex {
+    Up,
+    Left,
+    Right,
+    Back,
+}
+
+impl Vertex {
+    fn index(self) -> usize {
+        match self {
+            Vertex::Up => 0,
+            Vertex::Left => 1,
+            Vertex::Right => 2,
+            Vertex::Back => 3,
+        }
+    }
+}
+
+const VERTICES: [Vertex; 4] = [Vertex::Up, Vertex::Left, Vertex::Right, Vertex::Back];
+
+/// Vertices of each face, the face being opposite to the vertex left out.
+///
+/// Sticker positions within a face are given as weights over these
+/// vertices, in this order.
+pub const FACES: [(Face, [Vertex; 3]); 4] = [
+    (Face::Front, [Vertex::Up, Vertex::Left, Vertex::Right]),
+    (Face::Left, [Vertex::Up, Vertex::Back, Vertex::Left]),
+    (Face::Right, [Vertex::Up, Vertex::Right, Vertex::Back]),
+    (Face::Down, [Vertex::Left, Vertex::Right, Vertex::Back]),
+];
+
+// the stickers of a face as weights over its vertices, in ninths of the way
+// from the opposite edge to each vertex: tips, centers and then edges
+const STICKERS: [[u8; 3]; 9] = [
+    [7, 1, 1],
+    [1, 7, 1],
+    [1, 1, 7],
+    [5, 2, 2],
+    [2, 5, 2],
+    [2, 2, 5],
+    [4, 4, 1],
+    [1, 4, 4],
+    [4, 1, 4],
+];
+
+// other vertices in the order they move to each other in a clockwise turn
+// around the vertex, as seen from outside the puzzle
+const CYCLES: [[usize; 3]; 4] = [[1, 3, 2], [0, 2, 3], [3, 1, 0], [2, 0, 1]];
+
+// edges as the pairs of vertices they connect
+const EDGES: [[usize; 2]; 6] = [[0, 1], [0, 2], [0, 3], [1, 2], [1, 3], [2, 3]];
+
+const N_EDGES: usize = 720 * 64;
+const N_CENTERS: usize = 81;
+const MOVE_COUNT: usize = 8;
+
+// core states closer to solved than this are rejected, as in WCA scrambles
+const MIN_LENGTH: u8 = 6;
+
+fn sticker_weights(idx: usize) -> [u8; 4] {
+    let mut weights = [0; 4];
+
+    for (vertex, weight) in FACES[idx / 9].1.iter().zip(STICKERS[idx % 9]) {
+        weights[vertex.index()] = weight;
+    }
+
+    weights
+}
+
+fn sticker_index(weights: [u8; 4]) -> usize {
+    let opposite = weights.iter().position(|&w| w == 0).unwrap();
+    let face = FACES
+        .iter()
+        .position(|(_, vertices)| vertices.iter().all(|v| v.index() != opposite))
+        .unwrap();
+    let local = FACES[face].1.map(|v| weights[v.index()]);
+
+    face * 9 + STICKERS.iter().position(|s| *s == local).unwrap()
+}
+
+#[derive(PartialEq, Clone)]
+pub struct Move {
+    pub vertex: Vertex,
+    pub clockwise: bool,
+    /// Turns only the tip instead of the whole corner layer.
+    pub tip: bool,
+}
+
+impl Move {
+    fn from_index(index: usize) -> Self {
+        Self {
+            vertex: VERTICES[index / 2],
+            clockwise: index.is_multiple_of(2),
+            tip: false,
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self {
+            clockwise: !self.clockwise,
+            ..self.clone()
+        }
+    }
+}
+
+impl FromStr for Move {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<Self, ParseError> {
+        let mut chars = value.chars();
+
+        let ch = chars.next().ok_or(ParseError::EmptyMove)?;
+        let vertex = match ch.to_ascii_uppercase() {
+            'U' => Vertex::Up,
+            'L' => Vertex::Left,
+            'R' => Vertex::Right,
+            'B' => Vertex::Back,
+            _ => return Err(ParseError::Face(ch)),
+        };
+
+        let clockwise = match chars.next() {
+            None => true,
+            Some('\'') => false,
+            Some(ch) => return Err(ParseError::Rotation(ch)),
+        };
+
+        let rest = chars.collect::<String>();
+
+        if !rest.is_empty() {
+            return Err(ParseError::Trailing(rest));
+        }
+
+        Ok(Self {
+            vertex,
+            clockwise,
+            tip: ch.is_ascii_lowercase(),
+        })
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let letter = ["U", "L", "R", "B"][self.vertex.index()];
+
+        write!(
+            f,
+            "{}{}",
+            if self.tip {
+                letter.to_lowercase()
+            } else {
+                letter.to_string()
+            },
+            if self.clockwise { "" } else { "'" }
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct Pyraminx {
+    pub facelets: [Face; 36],
+}
+
+impl Pyraminx {
+    pub fn new() -> Self {
+        Self {
+            facelets: std::array::from_fn(|idx| FACES[idx / 9].0),
+        }
+    }
+
+    /// Color of the sticker at the given weights over the vertices of the
+    /// face, listed in [`FACES`].
+    pub fn sticker(&self, face: Face, weights: [u8; 3]) -> Face {
+        let face = FACES.iter().position(|(f, _)| *f == face).unwrap();
+        let local = STICKERS.iter().position(|s| *s == weights).unwrap();
+
+        self.facelets[face * 9 + local]
+    }
+
+    pub fn apply_move(&mut self, r#move: &Move) {
+        let vertex = r#move.vertex.index();
+        let depth = if r#move.tip { 7 } else { 4 };
+        let turns = if r#move.clockwise { 1 } else { 2 };
+
+        for _ in 0..turns {
+            let previous = self.facelets;
+
+            for (idx, &face) in previous.iter().enumerate() {
+                let weights = sticker_weights(idx);
+
+                if weights[vertex] < depth {
+                    continue;
+                }
+
+                let mut turned = weights;
+                let cycle = CYCLES[vertex];
+
+                for i in 0..3 {
+                    turned[cycle[(i + 1) % 3]] = weights[cycle[i]];
+                }
+
+                self.facelets[sticker_index(turned)] = face;
+            }
+        }
+    }
+
+    /// The two stickers of the edge at the given position.
+    fn edge_stickers([a, b]: [usize; 2]) -> [usize; 2] {
+        let mut others = (0..4).filter(|&v| v != a && v != b);
+        let (c, d) = (others.next().unwrap(), others.next().unwrap());
+
+        [c, d].map(|c| {
+            let mut weights = [0; 4];
+            weights[a] = 4;
+            weights[b] = 4;
+            weights[c] = 1;
+            sticker_index(weights)
+        })
+    }
+
+    /// The three stickers of the center attached to the given vertex.
+    fn center_stickers(vertex: usize) -> [usize; 3] {
+        let mut others = (0..4).filter(|&v| v != vertex);
+        let others = [(); 3].map(|_| others.next().unwrap());
+
+        others.map(|opposite| {
+            let mut weights = [2; 4];
+            weights[vertex] = 5;
+            weights[opposite] = 0;
+            sticker_index(weights)
+        })
+    }
+
+    fn edges(&self) -> usize {
+        let solved = Self::new();
+        let mut permutation = [0; 6];
+        let mut orientation = 0;
+
+        for (position, edge) in EDGES.iter().enumerate() {
+            let [first, second] = Self::edge_stickers(*edge).map(|idx| self.facelets[idx]);
+
+            for (piece, home) in EDGES.iter().enumerate() {
+                let colors = Self::edge_stickers(*home).map(|idx| solved.facelets[idx]);
+
+                if colors == [first, second] || colors == [second, first] {
+                    permutation[position] = piece as u8;
+                    orientation = orientation * 2 + usize::from(colors != [first, second]);
+                }
+            }
+        }
+
+        permutation_index(&permutation) * 64 + orientation
+    }
+
+    fn set_edges(&mut self, idx: usize) {
+        let solved = Self::new();
+        let mut permutation = [0, 1, 2, 3, 4, 5];
+        set_permutation(&mut permutation, idx / 64);
+
+        for (position, edge) in EDGES.iter().enumerate() {
+            let piece = permutation[position] as usize;
+            let flipped = (idx % 64) >> (5 - position) & 1 == 1;
+            let mut colors = Self::edge_stickers(EDGES[piece]).map(|i| solved.facelets[i]);
+
+            if flipped {
+                colors.reverse();
+            }
+
+            for (sticker, color) in Self::edge_stickers(*edge).into_iter().zip(colors) {
+                self.facelets[sticker] = color;
+            }
+        }
+    }
+
+    fn centers(&self) -> usize {
+        let solved = Self::new();
+
+        (0..4).fold(0, |acc, vertex| {
+            let stickers = Self::center_stickers(vertex);
+            let twist = (0..3)
+                .find(|&twist| {
+                    (0..3).all(|i| {
+                        self.facelets[stickers[i]] == solved.facelets[stickers[(i + twist) % 3]]
+                    })
+                })
+                .unwrap_or_default();
+
+            acc * 3 + twist
+        })
+    }
+
+    fn set_centers(&mut self, mut idx: usize) {
+        let solved = Self::new();
+
+        for vertex in (0..4).rev() {
+            let stickers = Self::center_stickers(vertex);
+            let twist = idx % 3;
+            idx /= 3;
+
+            for i in 0..3 {
+                self.facelets[stickers[i]] = solved.facelets[stickers[(i + twist) % 3]];
+            }
+        }
+    }
+}
+
+/// Distance of every state of the edges and centers to solved, tips aside.
+struct Tables {
+    edge_move: Vec<[u16; MOVE_COUNT]>,
+    center_move: Vec<[u8; MOVE_COUNT]>,
+    distance: Vec<u8>,
+}
+
+impl Tables {
+    fn get() -> &'static Self {
+        static TABLES: OnceLock<Tables> = OnceLock::new();
+        TABLES.get_or_init(Self::generate)
+    }
+
+    fn generate() -> Self {
+        let mut edge_move = vec![[0; MOVE_COUNT]; N_EDGES];
+        let mut center_move = vec![[0; MOVE_COUNT]; N_CENTERS];
+
+        for (idx, entry) in edge_move.iter_mut().enumerate() {
+            let mut puzzle = Pyraminx::new();
+            puzzle.set_edges(idx);
+
+            for (r#move, next) in entry.iter_mut().enumerate() {
+                let mut puzzle = puzzle.clone();
+                puzzle.apply_move(&Move::from_index(r#move));
+                *next = puzzle.edges() as u16;
+            }
+        }
+
+        for (idx, entry) in center_move.iter_mut().enumerate() {
+            let mut puzzle = Pyraminx::new();
+            puzzle.set_centers(idx);
+
+            for (r#move, next) in entry.iter_mut().enumerate() {
+                let mut puzzle = puzzle.clone();
+                puzzle.apply_move(&Move::from_index(r#move));
+                *next = puzzle.centers() as u8;
+            }
+        }
+
+        let mut distance = vec![u8::MAX; N_EDGES * N_CENTERS];
+        distance[0] = 0;
+        let mut depth = 0;
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for idx in 0..distance.len() {
+                if distance[idx] != depth {
+                    continue;
+                }
+
+                let (edges, centers) = (idx / N_CENTERS, idx % N_CENTERS);
+
+                for r#move in 0..MOVE_COUNT {
+                    let next = edge_move[edges][r#move] as usize * N_CENTERS
+                        + center_move[centers][r#move] as usize;
+
+                    if distance[next] == u8::MAX {
+                        distance[next] = depth + 1;
+                        changed = true;
+                    }
+                }
+            }
+
+            depth += 1;
+        }
+
+        Self {
+            edge_move,
+            center_move,
+            distance,
+        }
+    }
+}
+
+/// Picks a uniformly random state and returns an optimal sequence of corner
+/// moves that produces it, followed by the tip moves.
+pub fn random_state_scramble() -> Vec<Move> {
+    let tables = Tables::get();
+    let mut rng = rand::rng();
+
+    // only a quarter of the edge and center combinations can be reached
+    let mut idx = loop {
+        let idx = rng.random_range(0..tables.distance.len());
+
+        if (MIN_LENGTH..u8::MAX).contains(&tables.distance[idx]) {
+            break idx;
+        }
+    };
+
+    let mut solution = Vec::new();
+
+    while tables.distance[idx] > 0 {
+        let (edges, centers) = (idx / N_CENTERS, idx % N_CENTERS);
+
+        let (r#move, next) = (0..MOVE_COUNT)
+            .map(|r#move| {
+                (
+                    r#move,
+                    tables.edge_move[edges][r#move] as usize * N_CENTERS
+                        + tables.center_move[centers][r#move] as usize,
+                )
+            })
+            .find(|&(_, next)| tables.distance[next] < tables.distance[idx])
+            .unwrap();
+
+        solution.push(Move::from_index(r#move));
+        idx = next;
+    }
+
+    let mut moves = solution
+        .iter()
+        .rev()
+        .map(Move::inverse)
+        .collect::<Vec<Move>>();
+
+    for vertex in VERTICES {
+        match rng.random_range(0..3) {
+            0 => (),
+            twist => moves.push(Move {
+                vertex,
+                clockwise: twist == 1,
+                tip: true,
+            }),
+        }
+    }
+
+    moves
+}
@@ -1,7 +1,19 @@
-use crate::cube::{Face, Move, Rotation};
+use crate::{
+    clock,
+    cube::{Face, Layers, Move, Rotation},
+    error::ParseError,
+    megaminx::{self, Turn},
+    puzzle::Puzzle,
+    pyraminx, skewb, solver,
+    square1::{self, Square1},
+};
 use rand::distr::{Distribution, StandardUniform};
 use rand::Rng;
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    str::FromStr,
+    sync::mpsc::{self, Receiver},
+};
 
 impl Distribution<Face> for StandardUniform {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Face {
@@ -26,58 +38,243 @@ impl Distribution<Rotation> for StandardUniform {
     }
 }
 
+/// Default length of the random move scrambles used for cubes without a
+/// random-state scrambler, or `None` for puzzles that don't use them.
+pub fn scramble_length(puzzle: Puzzle) -> Option<usize> {
+    match puzzle {
+        Puzzle::Cube(..=3) => None,
+        Puzzle::Cube(4) => Some(40),
+        Puzzle::Cube(5) => Some(60),
+        Puzzle::Cube(6) => Some(80),
+        Puzzle::Cube(_) => Some(100),
+        _ => None,
+    }
+}
+
 #[derive(Clone)]
-pub struct Scramble {
-    pub moves: Vec<Move>,
+pub enum Scramble {
+    Cube(Vec<Move>),
+    Pyraminx(Vec<pyraminx::Move>),
+    Megaminx(Vec<megaminx::Move>),
+    Skewb(Vec<skewb::Move>),
+    Square1(Vec<square1::Move>),
+    Clock(Vec<clock::Move>),
 }
 
 impl Scramble {
-    pub fn new(scramble_length: u8) -> Self {
-        let mut moves = vec![Move::new()];
-
-        let mut previous_move = moves[0].clone();
-        let mut was_opposite = false;
-
-        for _ in 1..=scramble_length {
-            moves.push(loop {
-                let r#move = Move::new();
-
-                if r#move.face != previous_move.face
-                    && !(r#move.face == previous_move.face.opposite_face() && was_opposite)
-                {
-                    was_opposite = r#move.face == previous_move.face.opposite_face();
-                    previous_move = r#move.clone();
-                    break r#move;
-                }
+    /// Generates a scramble for the puzzle, made of `length` moves instead of
+    /// the default [`scramble_length`] if given and the puzzle uses random
+    /// move scrambles.
+    pub fn new(puzzle: Puzzle, length: Option<usize>) -> Self {
+        match puzzle {
+            Puzzle::Cube(2) => Self::Cube(solver::random_state_scramble_2x2()),
+            Puzzle::Cube(3) => Self::Cube(solver::random_state_scramble()),
+            Puzzle::Cube(size) => Self::Cube(random_moves(
+                size,
+                length.or(scramble_length(puzzle)).unwrap(),
+            )),
+            Puzzle::Pyraminx => Self::Pyraminx(pyraminx::random_state_scramble()),
+            Puzzle::Megaminx => Self::Megaminx(megaminx::random_scramble()),
+            Puzzle::Skewb => Self::Skewb(skewb::random_state_scramble()),
+            Puzzle::Square1 => Self::Square1(square1::random_state_scramble()),
+            Puzzle::Clock => Self::Clock(clock::random_state_scramble()),
+        }
+    }
+
+    /// Scramble without any move, for solves timed before one was ready.
+    pub fn empty(puzzle: Puzzle) -> Self {
+        match puzzle {
+            Puzzle::Cube(_) => Self::Cube(Vec::new()),
+            Puzzle::Pyraminx => Self::Pyraminx(Vec::new()),
+            Puzzle::Megaminx => Self::Megaminx(Vec::new()),
+            Puzzle::Skewb => Self::Skewb(Vec::new()),
+            Puzzle::Square1 => Self::Square1(Vec::new()),
+            Puzzle::Clock => Self::Clock(Vec::new()),
+        }
+    }
+
+    /// Parses a scramble written in the notation of the given puzzle.
+    pub fn parse(puzzle: Puzzle, value: &str) -> Result<Self, ParseError> {
+        Ok(match puzzle {
+            Puzzle::Cube(_) => Self::Cube(parse_moves(value)?),
+            Puzzle::Pyraminx => Self::Pyraminx(parse_moves(value)?),
+            Puzzle::Megaminx => Self::Megaminx(parse_moves(value)?),
+            Puzzle::Skewb => Self::Skewb(parse_moves(value)?),
+            Puzzle::Square1 => Self::Square1(parse_square1(value)?),
+            Puzzle::Clock => Self::Clock(parse_moves(value)?),
+        })
+    }
+}
+
+/// Scramble being generated on a worker thread, as random-state scrambles can
+/// take a while, most of all the first one while the solver builds its
+/// tables.
+pub struct PendingScramble {
+    puzzle: Puzzle,
+    length: Option<usize>,
+    receiver: Receiver<Scramble>,
+}
+
+impl PendingScramble {
+    pub fn spawn(puzzle: Puzzle, length: Option<usize>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            // the receiver is gone if the scramble isn't needed anymore
+            let _ = sender.send(Scramble::new(puzzle, length));
+        });
+
+        Self {
+            puzzle,
+            length,
+            receiver,
+        }
+    }
+
+    /// Whether the scramble is for the puzzle and the length given.
+    pub fn is_for(&self, puzzle: Puzzle, length: Option<usize>) -> bool {
+        self.puzzle == puzzle && self.length == length
+    }
+
+    /// The scramble, once it is generated.
+    pub fn try_take(&self) -> Option<Scramble> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+// square-1 scrambles are also written without spaces, as in `(1,0)/(-1,3)/`,
+// and every slice turn has to be possible for the pieces to stay whole
+fn parse_square1(value: &str) -> Result<Vec<square1::Move>, ParseError> {
+    let moves: Vec<square1::Move> = parse_moves(&value.replace(' ', "").replace('/', "/ "))?;
+    let mut square1 = Square1::new();
+
+    for (idx, r#move) in moves.iter().enumerate() {
+        if !square1.can_apply(r#move) {
+            return Err(ParseError::Move {
+                token: r#move.to_string(),
+                position: idx + 1,
+                error: Box::new(ParseError::Shape),
             });
         }
 
-        Self { moves }
+        square1.apply_move(r#move);
+    }
+
+    Ok(moves)
+}
+
+fn parse_moves<T: FromStr<Err = ParseError>>(value: &str) -> Result<Vec<T>, ParseError> {
+    let mut moves = Vec::new();
+
+    for (idx, token) in value.split_whitespace().enumerate() {
+        moves.push(token.parse().map_err(|error| ParseError::Move {
+            token: token.to_string(),
+            position: idx + 1,
+            error: Box::new(error),
+        })?);
+    }
+
+    Ok(moves)
+}
+
+fn join_moves<T: ToString>(moves: &[T]) -> String {
+    moves
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn random_moves(size: usize, length: usize) -> Vec<Move> {
+    let mut rng = rand::rng();
+    let mut moves = Vec::with_capacity(length);
+    // moves since the last change of axis, which all commute with each other
+    let mut same_axis: Vec<Move> = Vec::new();
+
+    while moves.len() < length {
+        let r#move = Move {
+            face: rng.random(),
+            rotation: rng.random(),
+            layers: Layers::Outer(rng.random_range(1..=size / 2)),
+        };
+
+        // on even cubes, turning half of the cube from either side is the
+        // same up to a rotation
+        if size.is_multiple_of(2)
+            && r#move.layers == Layers::Outer(size / 2)
+            && matches!(r#move.face, Face::Down | Face::Back | Face::Left)
+        {
+            continue;
+        }
+
+        if same_axis
+            .last()
+            .is_some_and(|last| last.face.axis().0 != r#move.face.axis().0)
+        {
+            same_axis.clear();
+        }
+
+        if same_axis
+            .iter()
+            .any(|other| other.face == r#move.face && other.layers == r#move.layers)
+        {
+            continue;
+        }
+
+        same_axis.push(r#move.clone());
+        moves.push(r#move);
     }
+
+    moves
 }
 
 impl Display for Scramble {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            self.moves
-                .iter()
-                .map(ToString::to_string)
+        let moves = match self {
+            Scramble::Cube(moves) => join_moves(moves),
+            Scramble::Pyraminx(moves) => join_moves(moves),
+            Scramble::Skewb(moves) => join_moves(moves),
+            Scramble::Square1(moves) => join_moves(moves),
+            Scramble::Clock(moves) => join_moves(moves),
+            // each line of a megaminx scramble ends with a turn of U
+            Scramble::Megaminx(moves) => moves
+                .split_inclusive(|r#move| r#move.turn == Turn::Up)
+                .map(join_moves)
                 .collect::<Vec<String>>()
-                .join(" ")
-        )
+                .join("\n"),
+        };
+
+        write!(f, "{moves}")
     }
 }
 
-impl From<&str> for Scramble {
-    fn from(value: &str) -> Self {
-        let mut moves = Vec::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        for r#move in value.split_whitespace() {
-            moves.push(r#move.into());
-        }
+    #[test]
+    fn reports_position_of_invalid_move() {
+        assert_eq!(
+            Scramble::parse(Puzzle::Cube(3), "R U Q2").err(),
+            Some(ParseError::Move {
+                token: String::from("Q2"),
+                position: 3,
+                error: Box::new(ParseError::Face('Q')),
+            })
+        );
+    }
 
-        Self { moves }
+    #[test]
+    fn rejects_blocked_square1_slice() {
+        assert_eq!(
+            // the first two slices undo each other, so the last move turns the
+            // top two twelfths from solved, where a corner is across the slice
+            Scramble::parse(Puzzle::Square1, "(1,0)/ (0,0)/ (1,0)/").err(),
+            Some(ParseError::Move {
+                token: String::from("(1,0)/"),
+                position: 3,
+                error: Box::new(ParseError::Shape),
+            })
+        );
     }
 }
@@ -2,41 +2,179 @@ use ratatui::widgets::TableState;
 
 use crate::{
     app::Solve,
-    stats::{get_avg, get_solves_from_tail, Stats},
+    puzzle::Puzzle,
+    stats::{RollingStat, StatKind, Stats, TrackedStat},
 };
 
+/// Entry of a session in the session manifest, which lists the sessions in
+/// the order they are cycled through.
+#[derive(Clone)]
+pub struct SessionInfo {
+    /// Name of the history file of the session, which only exists once a
+    /// solve has been added to it.
+    pub file: String,
+    pub name: String,
+    pub puzzle: Puzzle,
+    /// Creation date, in seconds since the epoch.
+    pub created: u64,
+    pub settings: SessionSettings,
+}
+
+/// Settings that each session keeps on its own.
+#[derive(Clone)]
+pub struct SessionSettings {
+    pub inspection: bool,
+    /// Length of random move scrambles, when the puzzle uses them, instead
+    /// of the default one for the puzzle.
+    pub scramble_length: Option<usize>,
+    pub stats: Vec<TrackedStat>,
+    /// Sub-X thresholds counted in the distribution of the times, in
+    /// milliseconds.
+    pub thresholds: Vec<u128>,
+    /// Width of the buckets of the histogram of the times, in milliseconds.
+    pub bucket_width: u128,
+}
+
+impl Default for SessionSettings {
+    fn default() -> Self {
+        Self {
+            inspection: true,
+            scramble_length: None,
+            stats: TrackedStat::defaults(),
+            thresholds: vec![10000, 15000, 20000, 30000],
+            bucket_width: 1000,
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct Session {
     pub solves: Vec<Solve>,
     pub state: TableState,
     pub available_height: u16,
     pub stats: Stats,
+    /// Set when the history file couldn't be parsed, in which case it isn't
+    /// rewritten so that the solves in it aren't lost.
+    pub is_corrupted: bool,
+    pub puzzle: Puzzle,
+    /// Means and averages computed for each solve, as set in the session
+    /// settings.
+    pub tracked: Vec<TrackedStat>,
+    /// Windows of the tracked stats over the last solves, which new solves
+    /// are pushed into.
+    pub windows: Vec<RollingStat>,
 }
 
 impl Session {
-    pub fn update_stats_on_new(&mut self) {
-        self.stats.update_on_new(&self.solves);
+    /// Computes the tracked stats ending at each solve and the stats of the
+    /// whole session from scratch.
+    pub fn recompute(&mut self) {
+        self.windows = self.new_windows();
+
+        for solve in &mut self.solves {
+            solve.averages = self
+                .windows
+                .iter_mut()
+                .map(|window| window.push(solve.time))
+                .collect();
+        }
+
+        self.stats = Stats::new(&self.solves, self.tracked.len());
+    }
+
+    /// Adds a solve to the end of the session, returning the lines of the
+    /// stats it set a new PB for.
+    pub fn push(&mut self, mut solve: Solve) -> Vec<StatKind> {
+        solve.averages = self
+            .windows
+            .iter_mut()
+            .map(|window| window.push(solve.time))
+            .collect();
+
+        let pbs = StatKind::all(self.tracked.len())
+            .filter(|&kind| self.stats.line(kind).is_beaten_by(solve.stat(kind)))
+            .collect::<Vec<StatKind>>();
+
+        self.stats.add(&solve);
+        self.solves.push(solve);
+        self.stats.refresh(self.solves.last());
+        self.stats.locate_new_bests(&pbs, self.solves.len() - 1);
         self.select_first();
+
+        pbs
     }
 
-    pub fn update_stats(&mut self) {
-        self.stats.update(&self.solves);
+    /// Changes the solve at the index.
+    pub fn edit(&mut self, idx: usize, edit: impl FnOnce(&mut Solve)) {
+        self.update_from(idx, |solves| edit(&mut solves[idx]));
     }
 
-    pub fn update_around(&mut self, idx: usize) {
+    /// Removes the solve at the index.
+    pub fn remove(&mut self, idx: usize) -> Solve {
+        let mut removed = None;
+        self.update_from(idx, |solves| removed = Some(solves.remove(idx)));
+
+        removed.unwrap()
+    }
+
+    // Only the windows that include the changed solve are computed again,
+    // which are the ones ending less than the largest stat after it.
+    fn update_from(&mut self, idx: usize, change: impl FnOnce(&mut Vec<Solve>)) {
+        let largest = self
+            .tracked
+            .iter()
+            .map(|tracked| tracked.stat.size)
+            .max()
+            .unwrap_or(1);
+
         let len = self.solves.len();
+        let end = len.min(idx + largest);
 
-        for idx in idx..len.min(idx + 5) {
-            self.solves[idx].avg_of_5 =
-                get_solves_from_tail(&self.solves[..=idx], 5).and_then(|solves| get_avg(solves, 5));
+        for solve in &self.solves[idx..end] {
+            self.stats.remove(solve);
         }
 
-        for idx in idx..len.min(idx + 12) {
-            self.solves[idx].avg_of_12 = get_solves_from_tail(&self.solves[..=idx], 12)
-                .and_then(|solves| get_avg(solves, 12));
+        change(&mut self.solves);
+
+        // a removed solve shifts the ones after it, which then don't change
+        let end = end - (len - self.solves.len());
+
+        for (stat_idx, tracked) in self.tracked.iter().enumerate() {
+            let mut window = RollingStat::new(tracked.stat);
+
+            for solve in &self.solves[(idx + 1).saturating_sub(tracked.stat.size)..idx] {
+                window.push(solve.time);
+            }
+
+            for solve in &mut self.solves[idx..end] {
+                solve.averages[stat_idx] = window.push(solve.time);
+            }
+        }
+
+        for solve in &self.solves[idx..end] {
+            self.stats.add(solve);
         }
 
-        self.update_stats();
+        self.windows = self.new_windows();
+
+        for window in &mut self.windows {
+            let start = self.solves.len().saturating_sub(window.stat().size);
+
+            for solve in &self.solves[start..] {
+                window.push(solve.time);
+            }
+        }
+
+        self.stats.refresh(self.solves.last());
+        self.stats
+            .relocate_bests(&self.solves, idx..end, len - self.solves.len());
+    }
+
+    fn new_windows(&self) -> Vec<RollingStat> {
+        self.tracked
+            .iter()
+            .map(|tracked| RollingStat::new(tracked.stat))
+            .collect()
     }
 
     pub fn selected_idx(&self) -> Option<usize> {
@@ -84,7 +222,7 @@ impl Session {
     }
 
     pub fn select_last(&mut self) {
-        self.select_with_index(self.solves.len().checked_sub(1).unwrap_or_default());
+        self.select_with_index(self.solves.len().saturating_sub(1));
     }
 
     pub fn scroll_up(&mut self, by: usize, move_cursor: bool) {
@@ -138,3 +276,174 @@ impl Session {
         self.scroll_down(self.available_height as usize, true);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{app::Penalty, stats::StatEntry};
+    use rand::Rng;
+
+    // tracked stats with the number of solves they trim from each end, which
+    // is 5% of the solves rounded up for averages
+    const TRACKED: [(&str, usize); 9] = [
+        ("mo1", 0),
+        ("mo3", 0),
+        ("ao3", 1),
+        ("ao5", 1),
+        ("ao12", 1),
+        ("ao20", 1),
+        ("ao21", 2),
+        ("ao40", 2),
+        ("ao41", 3),
+    ];
+
+    /// Time and penalty of the entry, which is compared as they are.
+    fn value(entry: Option<StatEntry>) -> Option<(u128, u8)> {
+        entry.map(|entry| (entry.time, entry.penalty as u8))
+    }
+
+    /// Close times, so that the bests are often tied, with DNFs rare enough
+    /// for windows to often have as many as they trim.
+    fn random_solve(rng: &mut impl Rng) -> Solve {
+        let penalty = match rng.random_range(0..12) {
+            0 => Penalty::Dnf,
+            1 => Penalty::PlusTwo,
+            _ => Penalty::Ok,
+        };
+
+        Solve::from_history_file(
+            rng.random_range(1000..1020),
+            penalty as u8,
+            "",
+            0,
+            "",
+            Puzzle::default(),
+        )
+        .unwrap()
+    }
+
+    /// Stat of the solves, computed from the sorted times.
+    fn naive_stat(trim: usize, solves: &[Solve]) -> Option<(u128, u8)> {
+        let mut times = solves
+            .iter()
+            .map(|solve| (matches!(solve.time.penalty, Penalty::Dnf), solve.time.time))
+            .collect::<Vec<(bool, u128)>>();
+        times.sort();
+
+        let counted = &times[trim..times.len() - trim];
+
+        if counted.iter().any(|(dnf, _)| *dnf) {
+            return Some((0, Penalty::Dnf as u8));
+        }
+
+        let sum = counted.iter().map(|(_, time)| time).sum::<u128>();
+
+        Some((sum / counted.len() as u128, Penalty::Ok as u8))
+    }
+
+    /// Checks the tracked stats of every solve, the windows of the last
+    /// solves and the stats of the session against the ones computed again
+    /// from the times alone.
+    fn check(session: &Session) {
+        let solves = &session.solves;
+
+        for (idx, solve) in solves.iter().enumerate() {
+            for (stat_idx, (tracked, (_, trim))) in session.tracked.iter().zip(TRACKED).enumerate()
+            {
+                let expected = (idx + 1)
+                    .checked_sub(tracked.stat.size)
+                    .and_then(|start| naive_stat(trim, &solves[start..=idx]));
+
+                assert_eq!(
+                    value(solve.averages[stat_idx]),
+                    expected,
+                    "{tracked} at {idx}"
+                );
+            }
+        }
+
+        for (stat_idx, window) in session.windows.iter().enumerate() {
+            assert_eq!(
+                value(window.value()),
+                value(solves.last().and_then(|solve| solve.averages[stat_idx]))
+            );
+        }
+
+        for kind in StatKind::all(session.tracked.len()) {
+            let values = solves
+                .iter()
+                .map(|solve| value(solve.stat(kind)))
+                .collect::<Vec<Option<(u128, u8)>>>();
+            let best = values
+                .iter()
+                .flatten()
+                .min_by_key(|(time, penalty)| (*penalty == Penalty::Dnf as u8, *time, *penalty))
+                .copied();
+            let line = session.stats.line(kind);
+
+            assert_eq!(value(line.current()), values.last().copied().flatten());
+            assert_eq!(value(line.best()), best);
+            assert_eq!(
+                line.best_solve(),
+                best.and_then(|best| values.iter().position(|value| *value == Some(best)))
+            );
+        }
+
+        let sum = solves.iter().map(|solve| solve.time.time).sum::<u128>();
+        let valid = solves
+            .iter()
+            .filter(|solve| !matches!(solve.time.penalty, Penalty::Dnf))
+            .count();
+
+        assert_eq!(session.stats.solve_count, solves.len() as u128);
+        assert_eq!(session.stats.valid_solve_count, valid as u128);
+        assert_eq!(
+            session.stats.global_mean,
+            sum.checked_div(solves.len() as u128).unwrap_or_default()
+        );
+    }
+
+    #[test]
+    fn incremental_stats_match_naive_ones() {
+        let mut rng = rand::rng();
+        let mut session = Session {
+            tracked: TRACKED
+                .iter()
+                .map(|(stat, _)| stat.parse().unwrap())
+                .collect(),
+            ..Session::default()
+        };
+        session.recompute();
+
+        for _ in 0..150 {
+            session.push(random_solve(&mut rng));
+        }
+        check(&session);
+
+        for _ in 0..300 {
+            let len = session.solves.len();
+
+            match rng.random_range(0..4) {
+                0 if len > 0 => {
+                    session.remove(rng.random_range(0..len));
+                }
+                1 if len > 0 => {
+                    let solve = random_solve(&mut rng);
+                    session.edit(rng.random_range(0..len), |edited| edited.time = solve.time);
+                }
+                // edits near the end, whose windows are cut short
+                2 if len > 0 => {
+                    let solve = random_solve(&mut rng);
+                    session.edit(len - 1 - rng.random_range(0..len.min(45)), |edited| {
+                        edited.time = solve.time
+                    });
+                }
+                _ => {
+                    session.push(random_solve(&mut rng));
+                }
+            }
+
+            check(&session);
+        }
+    }
+}
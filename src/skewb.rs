@@ -0,0 +1,468 @@
+use crate::{
+    cube::Face,
+    cubie::has_odd_parity,
+    error::ParseError,
+    geometry::{add, distance, dot, rotate, scale, Point},
+    solver::{permutation_index, set_permutation},
+};
+use rand::Rng;
+use std::{f64::consts::PI, fmt::Display, str::FromStr, sync::OnceLock};
+
+// faces in the order they are stored, as in the cube net
+const FACES: [Face; 6] = [
+    Face::Up,
+    Face::Left,
+    Face::Front,
+    Face::Right,
+    Face::Back,
+    Face::Down,
+];
+
+/// Stickers of a face: the center followed by the top-left, top-right,
+/// bottom-left and bottom-right corners as laid out in the net.
+pub const STICKERS: usize = 5;
+
+// normal of each face followed by the directions of the right and the bottom
+// of the face in the net
+const AXES: [[Point; 3]; 6] = [
+    [[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+    [[-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, -1.0, 0.0]],
+    [[0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, -1.0, 0.0]],
+    [[1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, -1.0, 0.0]],
+    [[0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, -1.0, 0.0]],
+    [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]],
+];
+
+// corner turned by each move: UBL, DFL, DBR and DBL, leaving UFR in place
+const AXIS_CORNERS: [Point; 4] = [
+    [-1.0, 1.0, -1.0],
+    [-1.0, -1.0, 1.0],
+    [1.0, -1.0, -1.0],
+    [-1.0, -1.0, -1.0],
+];
+
+// corners that only swap places with each other: UBL, DFL and DBR, the UFR
+// corner of the same group never moving
+const FIXED_CORNERS: [Point; 3] = [[-1.0, 1.0, -1.0], [-1.0, -1.0, 1.0], [1.0, -1.0, -1.0]];
+
+// corners that the four moves cycle around: DBL, UFL, UBR and DFR
+const FREE_CORNERS: [Point; 4] = [
+    [-1.0, -1.0, -1.0],
+    [-1.0, 1.0, 1.0],
+    [1.0, 1.0, -1.0],
+    [1.0, -1.0, 1.0],
+];
+
+// tolerance when comparing points of the puzzle
+const EPSILON: f64 = 1e-6;
+
+// every piece only ever takes even permutations, so each coordinate keeps
+// half of the permutation indices
+const N_CENTERS: usize = 360;
+const N_FIXED: usize = 3 * 27;
+const N_FREE: usize = 12 * 81;
+const MOVE_COUNT: usize = 8;
+
+// states closer to solved than this are rejected, as in WCA scrambles
+const MIN_LENGTH: u8 = 7;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Corner {
+    Up,
+    Left,
+    Right,
+    Back,
+}
+
+const CORNERS: [Corner; 4] = [Corner::Up, Corner::Left, Corner::Right, Corner::Back];
+
+impl Corner {
+    fn index(self) -> usize {
+        CORNERS.iter().position(|c| *c == self).unwrap()
+    }
+}
+
+#[derive(PartialEq, Clone)]
+pub struct Move {
+    pub corner: Corner,
+    pub clockwise: bool,
+}
+
+impl Move {
+    fn from_index(index: usize) -> Self {
+        Self {
+            corner: CORNERS[index / 2],
+            clockwise: index.is_multiple_of(2),
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self {
+            corner: self.corner,
+            clockwise: !self.clockwise,
+        }
+    }
+}
+
+impl FromStr for Move {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<Self, ParseError> {
+        let mut chars = value.chars();
+
+        let corner = match chars.next().ok_or(ParseError::EmptyMove)? {
+            'U' => Corner::Up,
+            'L' => Corner::Left,
+            'R' => Corner::Right,
+            'B' => Corner::Back,
+            ch => return Err(ParseError::Face(ch)),
+        };
+
+        let clockwise = match chars.next() {
+            None => true,
+            Some('\'') => false,
+            Some(ch) => return Err(ParseError::Rotation(ch)),
+        };
+
+        let rest = chars.collect::<String>();
+
+        if !rest.is_empty() {
+            return Err(ParseError::Trailing(rest));
+        }
+
+        Ok(Self { corner, clockwise })
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            ["U", "L", "R", "B"][self.corner.index()],
+            if self.clockwise { "" } else { "'" }
+        )
+    }
+}
+
+/// Sticker permutation of every clockwise move and the stickers of every
+/// corner position.
+struct Geometry {
+    moves: [Vec<usize>; 4],
+    /// Stickers of each corner, clockwise as seen from outside the corner.
+    corners: Vec<(Point, [usize; 3])>,
+}
+
+impl Geometry {
+    fn get() -> &'static Self {
+        static GEOMETRY: OnceLock<Geometry> = OnceLock::new();
+        GEOMETRY.get_or_init(Self::generate)
+    }
+
+    fn generate() -> Self {
+        // position of each sticker and of the piece it belongs to, with the
+        // sticker slightly raised off its piece towards the outside of its face
+        let stickers = (0..6 * STICKERS)
+            .map(|idx| {
+                let [normal, right, down] = AXES[idx / STICKERS];
+
+                let piece = match idx % STICKERS {
+                    0 => normal,
+                    s => {
+                        let (x, y) = ([-1.0, 1.0][(s - 1) % 2], [-1.0, 1.0][(s - 1) / 2]);
+                        add(normal, add(scale(right, x), scale(down, y)))
+                    }
+                };
+
+                (add(piece, scale(normal, 0.3)), piece)
+            })
+            .collect::<Vec<(Point, Point)>>();
+
+        let find = |position: Point| {
+            stickers
+                .iter()
+                .position(|&(other, _)| distance(other, position) < EPSILON)
+                .unwrap()
+        };
+
+        let clockwise = |corner: Point| (scale(corner, 3.0_f64.sqrt().recip()), -2.0 * PI / 3.0);
+
+        let moves = AXIS_CORNERS.map(|corner| {
+            let (axis, angle) = clockwise(corner);
+
+            stickers
+                .iter()
+                .enumerate()
+                .map(|(idx, &(position, piece))| {
+                    // the half of the puzzle on the side of the corner
+                    if dot(piece, corner) > 0.0 {
+                        find(rotate(position, axis, angle))
+                    } else {
+                        idx
+                    }
+                })
+                .collect::<Vec<usize>>()
+        });
+
+        let corners = FIXED_CORNERS
+            .into_iter()
+            .chain(FREE_CORNERS)
+            .map(|corner| {
+                let (axis, angle) = clockwise(corner);
+                let first = (0..stickers.len())
+                    .find(|&idx| distance(stickers[idx].1, corner) < EPSILON)
+                    .unwrap();
+                let second = find(rotate(stickers[first].0, axis, angle));
+                let third = find(rotate(stickers[second].0, axis, angle));
+
+                (corner, [first, second, third])
+            })
+            .collect();
+
+        Self { moves, corners }
+    }
+}
+
+/// Sticker colors of a Skewb, using the colors of the cube.
+///
+/// Faces are stored in `U L F R B D` order, each one as listed in
+/// [`STICKERS`].
+#[derive(Clone)]
+pub struct Skewb {
+    pub facelets: [Face; 6 * STICKERS],
+}
+
+impl Skewb {
+    pub fn new() -> Self {
+        Self {
+            facelets: std::array::from_fn(|idx| FACES[idx / STICKERS]),
+        }
+    }
+
+    pub fn face(&self, face: Face) -> &[Face] {
+        let offset = FACES.iter().position(|f| *f == face).unwrap() * STICKERS;
+
+        &self.facelets[offset..offset + STICKERS]
+    }
+
+    pub fn apply_move(&mut self, r#move: &Move) {
+        let permutation = &Geometry::get().moves[r#move.corner.index()];
+        let turns = if r#move.clockwise { 1 } else { 2 };
+
+        for _ in 0..turns {
+            let previous = self.facelets;
+
+            for (idx, &face) in previous.iter().enumerate() {
+                self.facelets[permutation[idx]] = face;
+            }
+        }
+    }
+
+    fn centers(&self) -> usize {
+        let permutation = FACES.map(|face| {
+            let color = self.face(face)[0];
+            FACES.iter().position(|f| *f == color).unwrap() as u8
+        });
+
+        permutation_index(&permutation) / 2
+    }
+
+    fn set_centers(&mut self, idx: usize) {
+        let mut permutation = [0, 1, 2, 3, 4, 5];
+        set_even_permutation(&mut permutation, idx);
+
+        for (face, piece) in permutation.into_iter().enumerate() {
+            self.facelets[face * STICKERS] = FACES[piece as usize];
+        }
+    }
+
+    /// Permutation and twist of the corners at the given positions, which
+    /// only ever swap places with each other.
+    fn corners(&self, positions: &[Point]) -> usize {
+        let solved = Self::new();
+        let stickers = Self::corner_stickers(positions);
+        let mut permutation = vec![0; positions.len()];
+        let mut twist = 0;
+
+        for (position, at) in stickers.iter().enumerate() {
+            for (piece, home) in stickers.iter().enumerate() {
+                let found = (0..3).find(|&t| {
+                    (0..3).all(|i| self.facelets[at[i]] == solved.facelets[home[(i + t) % 3]])
+                });
+
+                if let Some(t) = found {
+                    permutation[position] = piece as u8;
+                    twist = twist * 3 + t;
+                }
+            }
+        }
+
+        permutation_index(&permutation) / 2 * 3_usize.pow(positions.len() as u32) + twist
+    }
+
+    fn set_corners(&mut self, positions: &[Point], idx: usize) {
+        let solved = Self::new();
+        let stickers = Self::corner_stickers(positions);
+        let twists = 3_usize.pow(positions.len() as u32);
+        let mut permutation = (0..positions.len() as u8).collect::<Vec<u8>>();
+        set_even_permutation(&mut permutation, idx / twists);
+
+        for (position, at) in stickers.iter().enumerate() {
+            let home = stickers[permutation[position] as usize];
+            let t = idx % twists / 3_usize.pow((positions.len() - 1 - position) as u32) % 3;
+
+            for i in 0..3 {
+                self.facelets[at[i]] = solved.facelets[home[(i + t) % 3]];
+            }
+        }
+    }
+
+    fn corner_stickers(positions: &[Point]) -> Vec<[usize; 3]> {
+        let corners = &Geometry::get().corners;
+
+        positions
+            .iter()
+            .map(|&position| {
+                corners
+                    .iter()
+                    .find(|(corner, _)| distance(*corner, position) < EPSILON)
+                    .unwrap()
+                    .1
+            })
+            .collect()
+    }
+}
+
+/// Sets the even permutation with the given index, out of the two sharing
+/// it once the index is halved.
+fn set_even_permutation(perm: &mut [u8], idx: usize) {
+    set_permutation(perm, 2 * idx);
+
+    if has_odd_parity(perm) {
+        perm.sort_unstable();
+        set_permutation(perm, 2 * idx + 1);
+    }
+}
+
+/// Distance of every state to solved.
+struct Tables {
+    center_move: Vec<[u16; MOVE_COUNT]>,
+    fixed_move: Vec<[u16; MOVE_COUNT]>,
+    free_move: Vec<[u16; MOVE_COUNT]>,
+    distance: Vec<u8>,
+}
+
+impl Tables {
+    fn get() -> &'static Self {
+        static TABLES: OnceLock<Tables> = OnceLock::new();
+        TABLES.get_or_init(Self::generate)
+    }
+
+    fn generate() -> Self {
+        let center_move = move_table(N_CENTERS, Skewb::set_centers, Skewb::centers);
+        let fixed_move = move_table(
+            N_FIXED,
+            |skewb, idx| skewb.set_corners(&FIXED_CORNERS, idx),
+            |skewb| skewb.corners(&FIXED_CORNERS),
+        );
+        let free_move = move_table(
+            N_FREE,
+            |skewb, idx| skewb.set_corners(&FREE_CORNERS, idx),
+            |skewb| skewb.corners(&FREE_CORNERS),
+        );
+
+        let mut tables = Self {
+            center_move,
+            fixed_move,
+            free_move,
+            distance: vec![u8::MAX; N_CENTERS * N_FIXED * N_FREE],
+        };
+
+        // only one in nine indices is a reachable state, so the search walks
+        // a queue of states rather than sweeping the whole table at each depth
+        let mut queue = vec![0];
+        let mut head = 0;
+        tables.distance[0] = 0;
+
+        while head < queue.len() {
+            let idx = queue[head] as usize;
+            head += 1;
+
+            for r#move in 0..MOVE_COUNT {
+                let next = tables.next(idx, r#move);
+
+                if tables.distance[next] == u8::MAX {
+                    tables.distance[next] = tables.distance[idx] + 1;
+                    queue.push(next as u32);
+                }
+            }
+        }
+
+        tables
+    }
+
+    fn next(&self, idx: usize, r#move: usize) -> usize {
+        let (centers, fixed, free) = (
+            idx / (N_FIXED * N_FREE),
+            idx / N_FREE % N_FIXED,
+            idx % N_FREE,
+        );
+
+        (self.center_move[centers][r#move] as usize * N_FIXED
+            + self.fixed_move[fixed][r#move] as usize)
+            * N_FREE
+            + self.free_move[free][r#move] as usize
+    }
+}
+
+fn move_table(
+    size: usize,
+    set: impl Fn(&mut Skewb, usize),
+    get: impl Fn(&Skewb) -> usize,
+) -> Vec<[u16; MOVE_COUNT]> {
+    (0..size)
+        .map(|idx| {
+            let mut skewb = Skewb::new();
+            set(&mut skewb, idx);
+
+            std::array::from_fn(|r#move| {
+                let mut skewb = skewb.clone();
+                skewb.apply_move(&Move::from_index(r#move));
+                get(&skewb) as u16
+            })
+        })
+        .collect()
+}
+
+/// Picks a uniformly random state and returns an optimal sequence of moves
+/// that produces it.
+pub fn random_state_scramble() -> Vec<Move> {
+    let tables = Tables::get();
+    let mut rng = rand::rng();
+
+    let mut idx = loop {
+        let idx = rng.random_range(0..tables.distance.len());
+
+        if (MIN_LENGTH..u8::MAX).contains(&tables.distance[idx]) {
+            break idx;
+        }
+    };
+
+    let mut solution = Vec::new();
+
+    while tables.distance[idx] > 0 {
+        let (r#move, next) = (0..MOVE_COUNT)
+            .map(|r#move| (r#move, tables.next(idx, r#move)))
+            .find(|&(_, next)| tables.distance[next] < tables.distance[idx])
+            .unwrap();
+
+        solution.push(r#move);
+        idx = next;
+    }
+
+    solution
+        .into_iter()
+        .rev()
+        .map(|r#move| Move::from_index(r#move).inverse())
+        .collect()
+}
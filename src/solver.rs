@@ -0,0 +1,645 @@
+use crate::{
+    cube::Move,
+    cubie::{has_odd_parity, CubieCube, MOVE_COUNT, SOLVED},
+};
+use rand::Rng;
+use std::sync::OnceLock;
+
+const N_TWIST: usize = 2187;
+const N_FLIP: usize = 2048;
+const N_SLICE: usize = 495;
+const N_PERM_8: usize = 40320;
+const N_SLICE_PERM: usize = 24;
+
+// solutions longer than this are rejected and the search continues
+const MAX_LENGTH: usize = 21;
+
+// U, U2, U', R2, F2, D, D2, D', L2, B2
+const PHASE2_MOVES: [usize; 10] = [0, 1, 2, 4, 7, 9, 10, 11, 13, 16];
+
+const N_POCKET_PERM: usize = 5040;
+const N_POCKET_TWIST: usize = 729;
+
+// 2x2 states closer to solved than this are rejected, as in WCA scrambles
+const POCKET_MIN_LENGTH: u8 = 4;
+
+// U, R and F turns, which keep the DBL corner in place
+const POCKET_MOVES: [usize; 9] = [0, 1, 2, 3, 4, 5, 6, 7, 8];
+
+// corner positions other than DBL
+const POCKET_CORNERS: [usize; 7] = [0, 1, 2, 3, 4, 5, 7];
+
+impl CubieCube {
+    fn random() -> Self {
+        let mut rng = rand::rng();
+        let mut cube = CubieCube::new();
+
+        cube.set_twist(rng.random_range(0..N_TWIST));
+        cube.set_flip(rng.random_range(0..N_FLIP));
+        set_permutation(&mut cube.cp, rng.random_range(0..N_PERM_8));
+        set_permutation(&mut cube.ep, rng.random_range(0..479_001_600));
+
+        if has_odd_parity(&cube.cp) != has_odd_parity(&cube.ep) {
+            cube.ep.swap(0, 1);
+        }
+
+        cube
+    }
+
+    fn twist(&self) -> usize {
+        self.co[..7]
+            .iter()
+            .fold(0, |acc, &co| acc * 3 + co as usize)
+    }
+
+    fn set_twist(&mut self, mut twist: usize) {
+        let mut sum = 0;
+
+        for i in (0..7).rev() {
+            self.co[i] = (twist % 3) as u8;
+            sum += self.co[i];
+            twist /= 3;
+        }
+
+        self.co[7] = (3 - sum % 3) % 3;
+    }
+
+    fn flip(&self) -> usize {
+        self.eo[..11]
+            .iter()
+            .fold(0, |acc, &eo| acc * 2 + eo as usize)
+    }
+
+    fn set_flip(&mut self, mut flip: usize) {
+        let mut sum = 0;
+
+        for i in (0..11).rev() {
+            self.eo[i] = (flip % 2) as u8;
+            sum += self.eo[i];
+            flip /= 2;
+        }
+
+        self.eo[11] = sum % 2;
+    }
+
+    /// Positions of the FR, FL, BL and BR edges, ignoring their order.
+    fn slice(&self) -> usize {
+        let mut slice = 0;
+        let mut found = 0;
+
+        for j in (0..12).rev() {
+            if self.ep[j] >= 8 {
+                found += 1;
+                slice += binomial(11 - j, found);
+            }
+        }
+
+        slice
+    }
+
+    fn set_slice(&mut self, mut slice: usize) {
+        let mut is_slice = [false; 12];
+
+        for count in (1..=4).rev() {
+            let mut k = count - 1;
+            while binomial(k + 1, count) <= slice {
+                k += 1;
+            }
+            slice -= binomial(k, count);
+            is_slice[11 - k] = true;
+        }
+
+        let mut slice_edge = 8;
+        let mut other_edge = 0;
+
+        for (edge, is_slice) in self.ep.iter_mut().zip(is_slice) {
+            if is_slice {
+                *edge = slice_edge;
+                slice_edge += 1;
+            } else {
+                *edge = other_edge;
+                other_edge += 1;
+            }
+        }
+    }
+
+    fn pocket_permutation(&self) -> usize {
+        permutation_index(&POCKET_CORNERS.map(|i| self.cp[i]))
+    }
+
+    fn set_pocket_permutation(&mut self, idx: usize) {
+        let mut corners = POCKET_CORNERS.map(|i| self.cp[i]);
+        set_permutation(&mut corners, idx);
+
+        for (i, corner) in POCKET_CORNERS.into_iter().zip(corners) {
+            self.cp[i] = corner;
+        }
+    }
+
+    /// Twist of the URF to DLF corners, the DBL corner never being twisted.
+    fn pocket_twist(&self) -> usize {
+        self.co[..6]
+            .iter()
+            .fold(0, |acc, &co| acc * 3 + co as usize)
+    }
+
+    fn set_pocket_twist(&mut self, mut twist: usize) {
+        let mut sum = 0;
+
+        for i in (0..6).rev() {
+            self.co[i] = (twist % 3) as u8;
+            sum += self.co[i];
+            twist /= 3;
+        }
+
+        self.co[6] = 0;
+        self.co[7] = (3 - sum % 3) % 3;
+    }
+
+    fn corner_permutation(&self) -> usize {
+        permutation_index(&self.cp)
+    }
+
+    /// Permutation of the U and D layer edges, only meaningful in phase 2.
+    fn edge_permutation(&self) -> usize {
+        permutation_index(&self.ep[..8])
+    }
+
+    /// Permutation of the slice edges, only meaningful in phase 2.
+    fn slice_permutation(&self) -> usize {
+        permutation_index(&self.ep[8..])
+    }
+}
+
+fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+pub fn permutation_index(perm: &[u8]) -> usize {
+    let mut idx = 0;
+
+    for (i, &p) in perm.iter().enumerate() {
+        let smaller = perm[i + 1..].iter().filter(|&&q| q < p).count();
+        idx = idx * (perm.len() - i) + smaller;
+    }
+
+    idx
+}
+
+/// Rearranges the values already in `perm` into the permutation with the given index.
+pub fn set_permutation(perm: &mut [u8], mut idx: usize) {
+    let len = perm.len();
+    let mut digits = vec![0; len];
+
+    for i in (0..len).rev() {
+        digits[i] = idx % (len - i);
+        idx /= len - i;
+    }
+
+    let mut available = perm.to_vec();
+    available.sort_unstable();
+
+    for (p, digit) in perm.iter_mut().zip(digits) {
+        *p = available.remove(digit);
+    }
+}
+
+struct Tables {
+    twist_move: Vec<[u16; MOVE_COUNT]>,
+    flip_move: Vec<[u16; MOVE_COUNT]>,
+    slice_move: Vec<[u16; MOVE_COUNT]>,
+    corner_perm_move: Vec<[u16; MOVE_COUNT]>,
+    edge_perm_move: Vec<[u16; MOVE_COUNT]>,
+    slice_perm_move: Vec<[u16; MOVE_COUNT]>,
+    twist_slice_prune: Vec<u8>,
+    flip_slice_prune: Vec<u8>,
+    corner_slice_prune: Vec<u8>,
+    edge_slice_prune: Vec<u8>,
+}
+
+impl Tables {
+    fn get() -> &'static Self {
+        static TABLES: OnceLock<Tables> = OnceLock::new();
+        TABLES.get_or_init(Self::generate)
+    }
+
+    fn generate() -> Self {
+        let all_moves = (0..MOVE_COUNT).collect::<Vec<usize>>();
+
+        let twist_move = move_table(N_TWIST, &all_moves, CubieCube::set_twist, CubieCube::twist);
+        let flip_move = move_table(N_FLIP, &all_moves, CubieCube::set_flip, CubieCube::flip);
+        let slice_move = move_table(N_SLICE, &all_moves, CubieCube::set_slice, CubieCube::slice);
+        let corner_perm_move = move_table(
+            N_PERM_8,
+            &PHASE2_MOVES,
+            |cube, idx| set_permutation(&mut cube.cp, idx),
+            CubieCube::corner_permutation,
+        );
+        let edge_perm_move = move_table(
+            N_PERM_8,
+            &PHASE2_MOVES,
+            |cube, idx| set_permutation(&mut cube.ep[..8], idx),
+            CubieCube::edge_permutation,
+        );
+        let slice_perm_move = move_table(
+            N_SLICE_PERM,
+            &PHASE2_MOVES,
+            |cube, idx| set_permutation(&mut cube.ep[8..], idx),
+            CubieCube::slice_permutation,
+        );
+
+        let twist_slice_prune = pruning_table(&all_moves, &twist_move, &slice_move);
+        let flip_slice_prune = pruning_table(&all_moves, &flip_move, &slice_move);
+        let corner_slice_prune = pruning_table(&PHASE2_MOVES, &corner_perm_move, &slice_perm_move);
+        let edge_slice_prune = pruning_table(&PHASE2_MOVES, &edge_perm_move, &slice_perm_move);
+
+        Self {
+            twist_move,
+            flip_move,
+            slice_move,
+            corner_perm_move,
+            edge_perm_move,
+            slice_perm_move,
+            twist_slice_prune,
+            flip_slice_prune,
+            corner_slice_prune,
+            edge_slice_prune,
+        }
+    }
+}
+
+/// Distance of every 2x2 state to solved, indexed by permutation and twist.
+struct PocketTables {
+    permutation_move: Vec<[u16; MOVE_COUNT]>,
+    twist_move: Vec<[u16; MOVE_COUNT]>,
+    distance: Vec<u8>,
+}
+
+impl PocketTables {
+    fn get() -> &'static Self {
+        static TABLES: OnceLock<PocketTables> = OnceLock::new();
+        TABLES.get_or_init(Self::generate)
+    }
+
+    fn generate() -> Self {
+        let permutation_move = move_table(
+            N_POCKET_PERM,
+            &POCKET_MOVES,
+            CubieCube::set_pocket_permutation,
+            CubieCube::pocket_permutation,
+        );
+        let twist_move = move_table(
+            N_POCKET_TWIST,
+            &POCKET_MOVES,
+            CubieCube::set_pocket_twist,
+            CubieCube::pocket_twist,
+        );
+        let distance = pruning_table(&POCKET_MOVES, &permutation_move, &twist_move);
+
+        Self {
+            permutation_move,
+            twist_move,
+            distance,
+        }
+    }
+
+    fn distance(&self, permutation: usize, twist: usize) -> u8 {
+        self.distance[permutation * N_POCKET_TWIST + twist]
+    }
+
+    /// Optimal solution of a 2x2 state. Every state has a neighbour one move
+    /// closer to solved, so following those neighbours gives one.
+    fn solve(&self, mut permutation: usize, mut twist: usize) -> Vec<usize> {
+        let mut solution = Vec::new();
+
+        while self.distance(permutation, twist) > 0 {
+            let distance = self.distance(permutation, twist);
+
+            let (r#move, next_permutation, next_twist) = POCKET_MOVES
+                .into_iter()
+                .map(|r#move| {
+                    (
+                        r#move,
+                        self.permutation_move[permutation][r#move] as usize,
+                        self.twist_move[twist][r#move] as usize,
+                    )
+                })
+                .find(|&(_, permutation, twist)| self.distance(permutation, twist) < distance)
+                .unwrap();
+
+            solution.push(r#move);
+            permutation = next_permutation;
+            twist = next_twist;
+        }
+
+        solution
+    }
+}
+
+fn move_table(
+    size: usize,
+    moves: &[usize],
+    set: impl Fn(&mut CubieCube, usize),
+    get: impl Fn(&CubieCube) -> usize,
+) -> Vec<[u16; MOVE_COUNT]> {
+    let mut table = vec![[0; MOVE_COUNT]; size];
+
+    for (idx, entry) in table.iter_mut().enumerate() {
+        let mut cube = SOLVED;
+        set(&mut cube, idx);
+
+        for &r#move in moves {
+            let mut cube = cube;
+            cube.apply_move_index(r#move);
+            entry[r#move] = get(&cube) as u16;
+        }
+    }
+
+    table
+}
+
+/// Breadth-first search over the product of two coordinates, storing the
+/// distance of every pair to the solved pair.
+fn pruning_table(
+    moves: &[usize],
+    first: &[[u16; MOVE_COUNT]],
+    second: &[[u16; MOVE_COUNT]],
+) -> Vec<u8> {
+    let size = first.len() * second.len();
+    let mut table = vec![u8::MAX; size];
+    table[0] = 0;
+
+    let mut filled = 1;
+    let mut depth = 0;
+
+    while filled < size {
+        for idx in 0..size {
+            if table[idx] != depth {
+                continue;
+            }
+
+            let (a, b) = (idx / second.len(), idx % second.len());
+
+            for &r#move in moves {
+                let next = first[a][r#move] as usize * second.len() + second[b][r#move] as usize;
+
+                if table[next] == u8::MAX {
+                    table[next] = depth + 1;
+                    filled += 1;
+                }
+            }
+        }
+
+        depth += 1;
+    }
+
+    table
+}
+
+fn is_redundant(r#move: usize, previous: Option<&usize>) -> bool {
+    let Some(previous) = previous else {
+        return false;
+    };
+
+    let (face, previous_face) = (r#move / 3, previous / 3);
+
+    // opposite faces commute, so only one of their orders is searched
+    face == previous_face || face + 3 == previous_face
+}
+
+struct Search<'a> {
+    tables: &'a Tables,
+    cube: CubieCube,
+    solution: Vec<usize>,
+}
+
+impl Search<'_> {
+    fn phase1(&mut self, twist: usize, flip: usize, slice: usize, togo: usize) -> bool {
+        if togo == 0 {
+            let is_phase2_move = self
+                .solution
+                .last()
+                .is_some_and(|r#move| PHASE2_MOVES.contains(r#move));
+
+            return twist == 0 && flip == 0 && slice == 0 && !is_phase2_move && self.start_phase2();
+        }
+
+        let t = self.tables;
+        let distance = t.twist_slice_prune[twist * N_SLICE + slice]
+            .max(t.flip_slice_prune[flip * N_SLICE + slice]);
+
+        if distance as usize > togo {
+            return false;
+        }
+
+        for r#move in 0..MOVE_COUNT {
+            if is_redundant(r#move, self.solution.last()) {
+                continue;
+            }
+
+            self.solution.push(r#move);
+
+            if self.phase1(
+                t.twist_move[twist][r#move] as usize,
+                t.flip_move[flip][r#move] as usize,
+                t.slice_move[slice][r#move] as usize,
+                togo - 1,
+            ) {
+                return true;
+            }
+
+            self.solution.pop();
+        }
+
+        false
+    }
+
+    fn start_phase2(&mut self) -> bool {
+        let mut cube = self.cube;
+
+        for &r#move in &self.solution {
+            cube.apply_move_index(r#move);
+        }
+
+        let (corners, edges, slice) = (
+            cube.corner_permutation(),
+            cube.edge_permutation(),
+            cube.slice_permutation(),
+        );
+
+        (0..=MAX_LENGTH - self.solution.len())
+            .any(|depth| self.phase2(corners, edges, slice, depth))
+    }
+
+    fn phase2(&mut self, corners: usize, edges: usize, slice: usize, togo: usize) -> bool {
+        if togo == 0 {
+            return corners == 0 && edges == 0 && slice == 0;
+        }
+
+        let t = self.tables;
+        let distance = t.corner_slice_prune[corners * N_SLICE_PERM + slice]
+            .max(t.edge_slice_prune[edges * N_SLICE_PERM + slice]);
+
+        if distance as usize > togo {
+            return false;
+        }
+
+        for r#move in PHASE2_MOVES {
+            if is_redundant(r#move, self.solution.last()) {
+                continue;
+            }
+
+            self.solution.push(r#move);
+
+            if self.phase2(
+                t.corner_perm_move[corners][r#move] as usize,
+                t.edge_perm_move[edges][r#move] as usize,
+                t.slice_perm_move[slice][r#move] as usize,
+                togo - 1,
+            ) {
+                return true;
+            }
+
+            self.solution.pop();
+        }
+
+        false
+    }
+}
+
+fn solve(cube: CubieCube) -> Vec<usize> {
+    let mut search = Search {
+        tables: Tables::get(),
+        cube,
+        solution: Vec::new(),
+    };
+
+    let (twist, flip, slice) = (cube.twist(), cube.flip(), cube.slice());
+
+    for depth in 0..=MAX_LENGTH {
+        if search.phase1(twist, flip, slice, depth) {
+            break;
+        }
+    }
+
+    search.solution
+}
+
+/// Picks a uniformly random cube state and returns a sequence of moves that
+/// produces it from the solved cube.
+pub fn random_state_scramble() -> Vec<Move> {
+    solve(CubieCube::random())
+        .into_iter()
+        .rev()
+        .map(|r#move| Move::from_index(r#move).inverse())
+        .collect()
+}
+
+/// Picks a uniformly random 2x2 state at least a few moves away from solved
+/// and returns an optimal sequence of moves that produces it.
+pub fn random_state_scramble_2x2() -> Vec<Move> {
+    let tables = PocketTables::get();
+    let mut rng = rand::rng();
+
+    let (permutation, twist) = loop {
+        let permutation = rng.random_range(0..N_POCKET_PERM);
+        let twist = rng.random_range(0..N_POCKET_TWIST);
+
+        if tables.distance(permutation, twist) >= POCKET_MIN_LENGTH {
+            break (permutation, twist);
+        }
+    };
+
+    tables
+        .solve(permutation, twist)
+        .into_iter()
+        .rev()
+        .map(|r#move| Move::from_index(r#move).inverse())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // most moves any 2x2 state needs, counting half turns as one move
+    const POCKET_MAX_LENGTH: usize = 11;
+
+    fn move_index(r#move: &Move) -> usize {
+        (0..MOVE_COUNT)
+            .find(|&idx| Move::from_index(idx) == *r#move)
+            .unwrap()
+    }
+
+    fn apply(cube: &mut CubieCube, moves: &[usize]) {
+        moves
+            .iter()
+            .for_each(|&r#move| cube.apply_move_index(r#move));
+    }
+
+    #[test]
+    fn solves_random_states() {
+        for _ in 0..20 {
+            let cube = CubieCube::random();
+            let solution = solve(cube);
+
+            let mut solved = cube;
+            apply(&mut solved, &solution);
+
+            assert!(solution.len() <= MAX_LENGTH);
+            assert!(solved == SOLVED);
+        }
+    }
+
+    #[test]
+    fn scrambles_are_solvable_within_max_length() {
+        for _ in 0..20 {
+            let scramble = random_state_scramble();
+            assert!(scramble.len() <= MAX_LENGTH);
+
+            let mut cube = SOLVED;
+            apply(
+                &mut cube,
+                &scramble.iter().map(move_index).collect::<Vec<usize>>(),
+            );
+            let solution = solve(cube);
+            apply(&mut cube, &solution);
+
+            assert!(cube == SOLVED);
+        }
+    }
+
+    #[test]
+    fn pocket_solutions_are_optimal() {
+        let tables = PocketTables::get();
+        let mut rng = rand::rng();
+
+        for _ in 0..1000 {
+            let permutation = rng.random_range(0..N_POCKET_PERM);
+            let twist = rng.random_range(0..N_POCKET_TWIST);
+            let solution = tables.solve(permutation, twist);
+
+            let mut cube = SOLVED;
+            cube.set_pocket_permutation(permutation);
+            cube.set_pocket_twist(twist);
+            apply(&mut cube, &solution);
+
+            assert_eq!(solution.len(), tables.distance(permutation, twist) as usize);
+            assert!(solution.len() <= POCKET_MAX_LENGTH);
+            assert!(cube.cp == SOLVED.cp && cube.co == SOLVED.co);
+        }
+    }
+
+    #[test]
+    fn pocket_scrambles_are_long_enough() {
+        for _ in 0..100 {
+            let length = random_state_scramble_2x2().len();
+
+            assert!((POCKET_MIN_LENGTH as usize..=POCKET_MAX_LENGTH).contains(&length));
+        }
+    }
+}
@@ -0,0 +1,770 @@
+use crate::{
+    cube::Face,
+    cubie::has_odd_parity,
+    error::ParseError,
+    solver::{permutation_index, set_permutation},
+};
+use rand::{seq::SliceRandom, Rng};
+use std::{fmt::Display, str::FromStr, sync::OnceLock};
+
+/// Twelfths of a turn in each layer.
+pub const SLOTS: usize = 12;
+
+// piece in each twelfth of the layers of the solved puzzle, clockwise from
+// the back end of the slice as seen from above. Corners take two twelfths,
+// pieces 0 to 7 are corners and pieces 0 to 3 and 8 to 11 belong on top.
+const SOLVED_TOP: [u8; SLOTS] = [0, 0, 8, 1, 1, 9, 2, 2, 10, 3, 3, 11];
+const SOLVED_BOTTOM: [u8; SLOTS] = [4, 4, 12, 5, 5, 13, 6, 6, 14, 7, 7, 15];
+
+// side faces in the order they come clockwise from the slice as seen from
+// above, the slice running from the back to the front
+const SIDES: [Face; 4] = [Face::Right, Face::Front, Face::Left, Face::Back];
+
+/// Turn of the top and bottom layers in twelfths of a turn, each clockwise as
+/// seen from its own face, followed by a turn of the right half of the puzzle
+/// if `slash` is set.
+#[derive(PartialEq, Clone)]
+pub struct Move {
+    pub top: i8,
+    pub bottom: i8,
+    pub slash: bool,
+}
+
+impl FromStr for Move {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<Self, ParseError> {
+        if value.is_empty() {
+            return Err(ParseError::EmptyMove);
+        }
+
+        if value == "/" {
+            return Ok(Self {
+                top: 0,
+                bottom: 0,
+                slash: true,
+            });
+        }
+
+        let (turn, rest) = value
+            .strip_prefix('(')
+            .and_then(|value| value.split_once(')'))
+            .ok_or_else(|| ParseError::Turn(value.to_string()))?;
+
+        let amounts = turn
+            .split_once(',')
+            .and_then(|(top, bottom)| Some((top.parse().ok()?, bottom.parse().ok()?)))
+            .filter(|(top, bottom): &(i8, i8)| top.abs() < 12 && bottom.abs() < 12);
+
+        let Some((top, bottom)) = amounts else {
+            return Err(ParseError::Turn(turn.to_string()));
+        };
+
+        let slash = match rest {
+            "" => false,
+            "/" => true,
+            rest => return Err(ParseError::Trailing(rest.to_string())),
+        };
+
+        Ok(Self { top, bottom, slash })
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.top != 0 || self.bottom != 0 || !self.slash {
+            write!(f, "({},{})", self.top, self.bottom)?;
+        }
+
+        if self.slash {
+            write!(f, "/")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Pieces of a Square-1 by the twelfths of the layers they take up.
+///
+/// Both layers are indexed clockwise as seen from above, starting at the back
+/// end of the slice, so the right half of the puzzle is the first six
+/// twelfths of each layer.
+#[derive(PartialEq, Clone)]
+pub struct Square1 {
+    pub top: [u8; SLOTS],
+    pub bottom: [u8; SLOTS],
+    /// Whether the right half of the middle layer is turned over.
+    pub middle_flipped: bool,
+}
+
+impl Square1 {
+    pub fn new() -> Self {
+        Self {
+            top: SOLVED_TOP,
+            bottom: SOLVED_BOTTOM,
+            middle_flipped: false,
+        }
+    }
+
+    /// Whether the move can be made, that is whether no piece is in the way
+    /// of the slice after turning the layers.
+    pub fn can_apply(&self, r#move: &Move) -> bool {
+        let mut square1 = self.clone();
+        square1.turn(r#move.top, r#move.bottom);
+
+        !r#move.slash || square1.is_twistable()
+    }
+
+    /// Applies the move, whose slice turn must be possible as checked by
+    /// [`Self::can_apply`].
+    pub fn apply_move(&mut self, r#move: &Move) {
+        self.turn(r#move.top, r#move.bottom);
+
+        if r#move.slash {
+            self.slash();
+        }
+    }
+
+    fn turn(&mut self, top: i8, bottom: i8) {
+        let top = top.rem_euclid(SLOTS as i8) as usize;
+        let bottom = bottom.rem_euclid(SLOTS as i8) as usize;
+
+        self.top.rotate_right(top);
+        // clockwise as seen from below is counter-clockwise from above
+        self.bottom.rotate_left(bottom);
+    }
+
+    fn slash(&mut self) {
+        let (top, bottom) = (self.top, self.bottom);
+
+        for k in 0..SLOTS / 2 {
+            self.top[k] = bottom[SLOTS / 2 - 1 - k];
+            self.bottom[k] = top[SLOTS / 2 - 1 - k];
+        }
+
+        self.middle_flipped = !self.middle_flipped;
+    }
+
+    fn is_twistable(&self) -> bool {
+        [self.top, self.bottom]
+            .iter()
+            .all(|layer| layer[0] != layer[SLOTS - 1] && layer[5] != layer[6])
+    }
+
+    /// Twelfth each piece of the layer starts at, clockwise, and how many
+    /// twelfths it takes up.
+    pub fn pieces(&self, top: bool) -> Vec<(usize, usize)> {
+        let layer = if top { self.top } else { self.bottom };
+
+        (0..SLOTS)
+            .filter(|&k| layer[k] != layer[(k + SLOTS - 1) % SLOTS])
+            .map(|k| {
+                (
+                    k,
+                    if layer[(k + 1) % SLOTS] == layer[k] {
+                        2
+                    } else {
+                        1
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Color of the top or bottom sticker of the piece at the given twelfth
+    /// of a layer, followed by the color of its side sticker there.
+    pub fn sticker(&self, top: bool, slot: usize) -> (Face, Face) {
+        let layer = if top { self.top } else { self.bottom };
+        let piece = layer[slot];
+        let home_top = SOLVED_TOP.contains(&piece);
+        let home = if home_top { SOLVED_TOP } else { SOLVED_BOTTOM };
+
+        // twelfths of the piece at home, in the order they come clockwise
+        let slots = (0..SLOTS)
+            .filter(|&k| home[k] == piece)
+            .collect::<Vec<usize>>();
+
+        // moving to the other layer turns a piece over, reversing the order
+        // of its stickers
+        let part = if slots.len() == 1 {
+            0
+        } else {
+            let first = layer[(slot + SLOTS - 1) % SLOTS] != piece;
+            usize::from(!first) ^ usize::from(home_top != top)
+        };
+        let side = SIDES[(slots[part].div_ceil(3) + 3) % 4];
+
+        (if home_top { Face::Up } else { Face::Down }, side)
+    }
+
+    /// Shape of the layers and middle of the puzzle, along with the parity of
+    /// the order of its pieces.
+    fn shape(&self) -> Shape {
+        let order = [true, false]
+            .into_iter()
+            .flat_map(|top| {
+                let layer = if top { self.top } else { self.bottom };
+                self.pieces(top).into_iter().map(move |(k, _)| layer[k])
+            })
+            .collect::<Vec<u8>>();
+
+        Shape {
+            top: layer_mask(&self.top),
+            bottom: layer_mask(&self.bottom),
+            middle_flipped: self.middle_flipped,
+            parity: has_odd_parity(&order),
+        }
+    }
+}
+
+// twelfths that a piece starts at, as bits
+fn layer_mask(layer: &[u8; SLOTS]) -> u16 {
+    (0..SLOTS)
+        .filter(|&k| layer[k] != layer[(k + SLOTS - 1) % SLOTS])
+        .fold(0, |mask, k| mask | 1 << k)
+}
+
+// widths of the pieces of a layer, in the order of the twelfths they start at
+fn widths(mask: u16) -> Vec<usize> {
+    let starts = (0..SLOTS)
+        .filter(|&k| mask >> k & 1 == 1)
+        .collect::<Vec<usize>>();
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, &k)| (starts[(i + 1) % starts.len()] + SLOTS - k - 1) % SLOTS + 1)
+        .collect()
+}
+
+/// Shape of a Square-1, with the parity of the order its pieces come in when
+/// read clockwise from the slice on top and then on the bottom.
+#[derive(PartialEq, Clone, Copy)]
+struct Shape {
+    top: u16,
+    bottom: u16,
+    middle_flipped: bool,
+    parity: bool,
+}
+
+impl Shape {
+    /// Turns the layers, which moves the pieces crossing the back end of the
+    /// slice from one end of the order of their layer to the other.
+    fn turn(self, top: usize, bottom: usize) -> Self {
+        let rotation_parity = |mask: u16, crossing: u16| {
+            let (moved, count) = ((mask & crossing).count_ones(), mask.count_ones());
+            moved * (count - moved) % 2 == 1
+        };
+        let all = (1 << SLOTS) - 1;
+
+        Self {
+            top: (self.top << top | self.top >> (SLOTS - top)) & all,
+            bottom: (self.bottom >> bottom | self.bottom << (SLOTS - bottom)) & all,
+            middle_flipped: self.middle_flipped,
+            parity: self.parity
+                ^ rotation_parity(self.top, all & !(all >> top))
+                ^ rotation_parity(self.bottom, (1 << bottom) - 1),
+        }
+    }
+
+    fn is_twistable(self) -> bool {
+        let ends = 1 | 1 << (SLOTS / 2);
+        self.top & ends == ends && self.bottom & ends == ends
+    }
+
+    fn slash(self) -> Self {
+        let half = (1 << (SLOTS / 2)) - 1;
+        let right = |mask: u16| widths(mask)[..(mask & half).count_ones() as usize].to_vec();
+        let (top_right, bottom_right) = (right(self.top), right(self.bottom));
+
+        // the right half turns over, reversing the order of its pieces
+        let turned = |widths: &[usize]| {
+            let mut mask = 0;
+            let mut k = 0;
+
+            for width in widths.iter().rev() {
+                mask |= 1 << k;
+                k += width;
+            }
+
+            mask
+        };
+
+        let (a, c) = (top_right.len(), bottom_right.len());
+        let (b, d) = (
+            self.top.count_ones() as usize - a,
+            self.bottom.count_ones() as usize - c,
+        );
+
+        // pieces in the order top right, top left, bottom right and bottom
+        // left, moved to bottom right reversed, top left, top right reversed
+        // and bottom left
+        let order = (a + b..a + b + c)
+            .rev()
+            .chain(a..a + b)
+            .chain((0..a).rev())
+            .chain(a + b + c..a + b + c + d)
+            .map(|piece| piece as u8)
+            .collect::<Vec<u8>>();
+
+        Self {
+            top: turned(&bottom_right) | self.top & !half,
+            bottom: turned(&top_right) | self.bottom & !half,
+            middle_flipped: !self.middle_flipped,
+            parity: self.parity ^ has_odd_parity(&order),
+        }
+    }
+}
+
+const N_PERM: usize = 40320;
+
+// phase 2 steps: layer turns that keep the square shape followed by a slice
+// turn. The first sixteen turn the layers by quarter turns, the others by a
+// twelfth less each, after which the layers are turned a twelfth further to
+// bring them back in line with the slice.
+const STEPS: usize = 32;
+
+// pieces starting at these twelfths once the puzzle is in the square shape
+// that phase 2 solves from, the top one twelfth ahead of the solved puzzle
+const TOP_CORNERS: [usize; 4] = [1, 4, 7, 10];
+const TOP_EDGES: [usize; 4] = [3, 6, 9, 0];
+const BOTTOM_CORNERS: [usize; 4] = [0, 3, 6, 9];
+const BOTTOM_EDGES: [usize; 4] = [2, 5, 8, 11];
+
+fn step_turn(step: usize) -> (usize, usize) {
+    let (top, bottom) = (step / 4 % 4 * 3, step % 4 * 3);
+
+    if step < 16 {
+        (top, bottom)
+    } else {
+        (top + 2, bottom + 2)
+    }
+}
+
+// layer turns by quarter turns, in twelfths
+fn quarter_turns() -> impl Iterator<Item = (usize, usize)> {
+    (0..16).map(|i| (i / 4 * 3, i % 4 * 3))
+}
+
+impl Square1 {
+    /// Solved puzzle with its top turned into the square shape that the
+    /// slice turns keep.
+    fn square() -> Self {
+        let mut square1 = Self::new();
+        square1.turn(1, 0);
+        square1
+    }
+
+    fn corners(&self) -> usize {
+        permutation_index(&self.square_pieces(TOP_CORNERS, BOTTOM_CORNERS))
+    }
+
+    fn edges(&self) -> usize {
+        permutation_index(&self.square_pieces(TOP_EDGES, BOTTOM_EDGES).map(|p| p - 8))
+    }
+
+    fn square_pieces(&self, top: [usize; 4], bottom: [usize; 4]) -> [u8; 8] {
+        let mut pieces = [0; 8];
+
+        for i in 0..4 {
+            pieces[i] = self.top[top[i]];
+            pieces[i + 4] = self.bottom[bottom[i]];
+        }
+
+        pieces
+    }
+
+    fn set_corners(&mut self, idx: usize) {
+        let mut pieces = [0, 1, 2, 3, 4, 5, 6, 7];
+        set_permutation(&mut pieces, idx);
+
+        for i in 0..4 {
+            self.top[TOP_CORNERS[i]..TOP_CORNERS[i] + 2].fill(pieces[i]);
+            self.bottom[BOTTOM_CORNERS[i]..BOTTOM_CORNERS[i] + 2].fill(pieces[i + 4]);
+        }
+    }
+
+    fn set_edges(&mut self, idx: usize) {
+        let mut pieces = [8, 9, 10, 11, 12, 13, 14, 15];
+        set_permutation(&mut pieces, idx);
+
+        for i in 0..4 {
+            self.top[TOP_EDGES[i]] = pieces[i];
+            self.bottom[BOTTOM_EDGES[i]] = pieces[i + 4];
+        }
+    }
+
+    fn apply_step(&mut self, step: usize) {
+        let (top, bottom) = step_turn(step);
+        self.turn(top as i8, bottom as i8);
+        self.slash();
+
+        if step >= 16 {
+            self.turn(1, 1);
+        }
+    }
+}
+
+/// Moves of the permutation of one kind of piece in the square shape, and
+/// the slice turns needed to solve it along with the middle layer.
+struct PieceTables {
+    /// Quarter turn of the top and of the bottom.
+    turn: Vec<[u16; 2]>,
+    step: Vec<[u16; STEPS]>,
+    distance: Vec<u8>,
+    solved: usize,
+}
+
+impl PieceTables {
+    fn generate(set: fn(&mut Square1, usize), get: fn(&Square1) -> usize) -> Self {
+        let mut turn = vec![[0; 2]; N_PERM];
+        let mut step = vec![[0; STEPS]; N_PERM];
+
+        for idx in 0..N_PERM {
+            let mut square1 = Square1::square();
+            set(&mut square1, idx);
+
+            let after = |apply: &dyn Fn(&mut Square1)| {
+                let mut square1 = square1.clone();
+                apply(&mut square1);
+                get(&square1) as u16
+            };
+
+            turn[idx] = [after(&|s| s.turn(3, 0)), after(&|s| s.turn(0, 3))];
+            step[idx] = std::array::from_fn(|r#move| after(&|s| s.apply_step(r#move)));
+        }
+
+        let solved = get(&Square1::square());
+        let mut distance = vec![u8::MAX; N_PERM * 2];
+        let mut frontier = vec![solved * 2];
+        let mut depth = 0;
+
+        while !frontier.is_empty() {
+            let mut next = Vec::new();
+            let mut queue = frontier;
+
+            // layer turns don't count, so spread each depth over them first
+            while let Some(idx) = queue.pop() {
+                if distance[idx] != u8::MAX {
+                    continue;
+                }
+
+                distance[idx] = depth;
+                let (perm, middle) = (idx / 2, idx % 2);
+
+                queue.extend(turn[perm].map(|next| next as usize * 2 + middle));
+                next.extend(step[perm].map(|next| next as usize * 2 + (middle ^ 1)));
+            }
+
+            frontier = next;
+            depth += 1;
+        }
+
+        Self {
+            turn,
+            step,
+            distance,
+            solved,
+        }
+    }
+
+    fn turn(&self, perm: usize, (top, bottom): (usize, usize)) -> usize {
+        let perm = (0..top / 3).fold(perm, |perm, _| self.turn[perm][0] as usize);
+        (0..bottom / 3).fold(perm, |perm, _| self.turn[perm][1] as usize)
+    }
+}
+
+/// Slice turns needed to reach the square shape, and the piece tables of the
+/// square shape.
+struct Tables {
+    layers: Vec<u16>,
+    layer_index: Vec<u16>,
+    shape_distance: Vec<u8>,
+    corners: PieceTables,
+    edges: PieceTables,
+}
+
+impl Tables {
+    fn get() -> &'static Self {
+        static TABLES: OnceLock<Tables> = OnceLock::new();
+        TABLES.get_or_init(Self::generate)
+    }
+
+    fn generate() -> Self {
+        // every way of filling a layer with pieces one or two twelfths wide
+        let layers = (0..1 << SLOTS)
+            .filter(|&mask: &u16| mask != 0 && widths(mask).iter().all(|&w| w <= 2))
+            .collect::<Vec<u16>>();
+        let mut layer_index = vec![u16::MAX; 1 << SLOTS];
+
+        for (idx, &mask) in layers.iter().enumerate() {
+            layer_index[mask as usize] = idx as u16;
+        }
+
+        let mut tables = Self {
+            shape_distance: vec![u8::MAX; layers.len() * layers.len() * 4],
+            layers,
+            layer_index,
+            corners: PieceTables::generate(Square1::set_corners, Square1::corners),
+            edges: PieceTables::generate(Square1::set_edges, Square1::edges),
+        };
+
+        // shapes one slice turn further away are the turns of the layers of
+        // the shapes after a slice turn from the current ones
+        let mut frontier = vec![Square1::square().shape()];
+        let mut depth = 0;
+
+        while !frontier.is_empty() {
+            let mut next = Vec::new();
+
+            for shape in frontier {
+                for (top, bottom) in (0..SLOTS).flat_map(|t| (0..SLOTS).map(move |b| (t, b))) {
+                    let turned = shape.turn(top, bottom);
+                    let idx = tables.shape_index(turned);
+
+                    if tables.shape_distance[idx] == u8::MAX {
+                        tables.shape_distance[idx] = depth;
+
+                        if turned.is_twistable() {
+                            next.push(turned.slash());
+                        }
+                    }
+                }
+            }
+
+            frontier = next;
+            depth += 1;
+        }
+
+        tables
+    }
+
+    fn shape_index(&self, shape: Shape) -> usize {
+        let layer = |mask: u16| self.layer_index[mask as usize] as usize;
+
+        ((layer(shape.top) * self.layers.len() + layer(shape.bottom)) * 2
+            + usize::from(shape.middle_flipped))
+            * 2
+            + usize::from(shape.parity)
+    }
+
+    fn shape_distance(&self, shape: Shape) -> u8 {
+        self.shape_distance[self.shape_index(shape)]
+    }
+
+    fn square_distance(&self, corners: usize, edges: usize, middle: usize) -> usize {
+        let corners = self.corners.distance[corners * 2 + middle];
+        let edges = self.edges.distance[edges * 2 + middle];
+
+        corners.max(edges) as usize
+    }
+}
+
+struct Search<'a> {
+    tables: &'a Tables,
+    /// Whether phase 1 ends with a slice turn and no layer turns after it.
+    after_slash: bool,
+    steps: Vec<usize>,
+    last_turn: (usize, usize),
+}
+
+impl Search<'_> {
+    fn search(&mut self, corners: usize, edges: usize, middle: usize, depth: usize) -> bool {
+        let tables = self.tables;
+
+        if depth == 0 {
+            let solved = quarter_turns().find(|&turn| {
+                middle == 0
+                    && tables.corners.turn(corners, turn) == tables.corners.solved
+                    && tables.edges.turn(edges, turn) == tables.edges.solved
+            });
+
+            if let Some(turn) = solved {
+                self.last_turn = turn;
+            }
+
+            return solved.is_some();
+        }
+
+        for step in 0..STEPS {
+            // two slice turns in a row cancel out
+            let after_slash = match self.steps.last() {
+                Some(&last) => last < 16,
+                None => self.after_slash,
+            };
+
+            if step == 0 && after_slash {
+                continue;
+            }
+
+            let corners = tables.corners.step[corners][step] as usize;
+            let edges = tables.edges.step[edges][step] as usize;
+            let middle = middle ^ 1;
+
+            if tables.square_distance(corners, edges, middle) >= depth {
+                continue;
+            }
+
+            self.steps.push(step);
+
+            if self.search(corners, edges, middle, depth - 1) {
+                return true;
+            }
+
+            self.steps.pop();
+        }
+
+        false
+    }
+}
+
+/// Picks a uniformly random state and returns a sequence of moves that
+/// produces it.
+pub fn random_state_scramble() -> Vec<Move> {
+    let tables = Tables::get();
+    let mut rng = rand::rng();
+
+    loop {
+        let top = tables.layers[rng.random_range(0..tables.layers.len())];
+        let bottom = tables.layers[rng.random_range(0..tables.layers.len())];
+
+        // both layers share the eight corners, which take two twelfths each
+        if top.count_ones() + bottom.count_ones() != 16 {
+            continue;
+        }
+
+        let square1 = random_pieces(top, bottom, rng.random());
+
+        if tables.shape_distance(square1.shape()) != u8::MAX {
+            return scramble_to(square1);
+        }
+    }
+}
+
+/// Sequence of moves that produces the given state, found by first bringing
+/// it to the square shape and then solving the pieces in that shape.
+fn scramble_to(mut square1: Square1) -> Vec<Move> {
+    let tables = Tables::get();
+    let mut shape = square1.shape();
+    let all_turns = || (0..SLOTS).flat_map(|top| (0..SLOTS).map(move |bottom| (top, bottom)));
+
+    // layer turns, each but the last followed by a slice turn
+    let mut turns = Vec::new();
+
+    while tables.shape_distance(shape) > 0 {
+        let distance = tables.shape_distance(shape);
+
+        let turn = all_turns()
+            .find(|&(top, bottom)| {
+                let turned = shape.turn(top, bottom);
+                turned.is_twistable() && tables.shape_distance(turned.slash()) < distance
+            })
+            .unwrap();
+
+        shape = shape.turn(turn.0, turn.1).slash();
+        turns.push(turn);
+    }
+
+    let square = Square1::square().shape();
+    let (top, bottom) = all_turns()
+        .find(|&(top, bottom)| shape.turn(top, bottom) == square)
+        .unwrap();
+
+    for &(top, bottom) in &turns {
+        square1.turn(top as i8, bottom as i8);
+        square1.slash();
+    }
+
+    square1.turn(top as i8, bottom as i8);
+
+    let mut search = Search {
+        tables,
+        after_slash: !turns.is_empty() && (top, bottom) == (0, 0),
+        steps: Vec::new(),
+        last_turn: (0, 0),
+    };
+    let (corners, edges, middle) = (
+        square1.corners(),
+        square1.edges(),
+        usize::from(square1.middle_flipped),
+    );
+    let mut depth = tables.square_distance(corners, edges, middle);
+
+    while !search.search(corners, edges, middle, depth) {
+        depth += 1;
+    }
+
+    // the last turn of phase 1 is made along with the first one of phase 2,
+    // and the top is turned back from the square shape at the end
+    let mut pending = (top, bottom);
+
+    for step in search.steps {
+        let (top, bottom) = step_turn(step);
+        turns.push((pending.0 + top, pending.1 + bottom));
+        pending = if step < 16 { (0, 0) } else { (1, 1) };
+    }
+
+    let (top, bottom) = search.last_turn;
+    turns.push((pending.0 + top + SLOTS - 1, pending.1 + bottom));
+
+    // the scramble undoes the solution, so it makes the opposite turns in
+    // reverse order
+    let amount = |turn: usize| {
+        let turn = (SLOTS - turn % SLOTS) % SLOTS;
+
+        if turn > SLOTS / 2 {
+            turn as i8 - SLOTS as i8
+        } else {
+            turn as i8
+        }
+    };
+
+    turns
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &(top, bottom))| Move {
+            top: amount(top),
+            bottom: amount(bottom),
+            slash: i < turns.len() - 1,
+        })
+        .filter(|r#move| r#move.top != 0 || r#move.bottom != 0 || r#move.slash)
+        .collect()
+}
+
+/// Random arrangement of the pieces into layers of the given shapes.
+fn random_pieces(top: u16, bottom: u16, middle_flipped: bool) -> Square1 {
+    let mut rng = rand::rng();
+    let mut corners = (0..8).collect::<Vec<u8>>();
+    let mut edges = (8..16).collect::<Vec<u8>>();
+    corners.shuffle(&mut rng);
+    edges.shuffle(&mut rng);
+
+    let mut layer = |mask: u16| {
+        let mut layer = [0; SLOTS];
+        let start = mask.trailing_zeros() as usize;
+        let mut k = start;
+
+        for width in widths(mask) {
+            let piece = if width == 2 {
+                corners.pop().unwrap()
+            } else {
+                edges.pop().unwrap()
+            };
+
+            for i in 0..width {
+                layer[(k + i) % SLOTS] = piece;
+            }
+
+            k += width;
+        }
+
+        layer
+    };
+
+    Square1 {
+        top: layer(top),
+        bottom: layer(bottom),
+        middle_flipped,
+    }
+}
@@ -1,9 +1,19 @@
-use std::{cmp::Ordering, fmt::Display};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, VecDeque},
+    fmt::Display,
+    iter,
+    ops::Range,
+    str::FromStr,
+};
 
 use crate::{
     app::{Penalty, Solve},
-    timer::millis_to_string_not_running,
+    error::ParseError,
+    session::Session,
+    timer::{self, millis_to_string_not_running},
 };
+use chrono::{Local, NaiveDate, TimeZone, Weekday};
 use ratatui::{
     style::{Color, Style},
     text::Span,
@@ -24,6 +34,14 @@ impl StatEntry {
 
         Self { time, penalty }
     }
+
+    /// Time before the penalty was added to it.
+    pub fn raw_time(&self) -> u128 {
+        match self.penalty {
+            Penalty::PlusTwo => self.time - 2000,
+            _ => self.time,
+        }
+    }
 }
 
 impl Display for StatEntry {
@@ -72,142 +90,1095 @@ impl PartialOrd for StatEntry {
 pub struct StatLine {
     current: Option<StatEntry>,
     best: Option<StatEntry>,
+    // index of the solve the best ends at, the first one when tied
+    best_solve: Option<usize>,
 }
 
 impl StatLine {
-    fn new(current: Option<StatEntry>, best: Option<StatEntry>) -> Self {
-        Self { current, best }
+    pub fn current(&self) -> Option<StatEntry> {
+        self.current
     }
 
-    pub fn update(&mut self, new: Option<StatEntry>) {
-        if let Some(new) = new {
-            self.current = Some(new);
-            self.best = if let Some(best) = self.best {
-                Some(best.min(new))
-            } else {
-                Some(new)
-            };
+    pub fn best(&self) -> Option<StatEntry> {
+        self.best
+    }
+
+    /// Index of the solve that the best single is, or that the best of a
+    /// tracked stat ends at.
+    pub fn best_solve(&self) -> Option<usize> {
+        self.best_solve
+    }
+
+    /// Whether the entry would be a new PB, which needs a best to beat.
+    pub fn is_beaten_by(&self, entry: Option<StatEntry>) -> bool {
+        match (entry, self.best) {
+            (Some(entry), Some(best)) => {
+                !matches!(entry.penalty, Penalty::Dnf) && entry.cmp(&best) == Ordering::Less
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Line of the stats of a session: the single or one of the tracked stats,
+/// by its index.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum StatKind {
+    Single,
+    Tracked(usize),
+}
+
+impl StatKind {
+    /// Every line of a session with the number of tracked stats.
+    pub fn all(stat_count: usize) -> impl Iterator<Item = StatKind> {
+        std::iter::once(StatKind::Single).chain((0..stat_count).map(StatKind::Tracked))
+    }
+}
+
+/// Solve that set a PB for a line of the stats, beating the previous one.
+pub struct PbEvent {
+    /// Index of the solve, which for a tracked stat is the one ending it.
+    pub solve: usize,
+    pub date: u64,
+    pub old: Option<StatEntry>,
+    pub new: StatEntry,
+}
+
+/// Every PB set over the solves for the line, from the first one.
+pub fn pb_history(solves: &[Solve], kind: StatKind) -> Vec<PbEvent> {
+    let mut events = Vec::new();
+    let mut best: Option<StatEntry> = None;
+
+    for (idx, solve) in solves.iter().enumerate() {
+        let Some(value) = solve
+            .stat(kind)
+            .filter(|value| !matches!(value.penalty, Penalty::Dnf))
+        else {
+            continue;
+        };
+
+        if best.is_none_or(|best| value < best) {
+            events.push(PbEvent {
+                solve: idx,
+                date: solve.date,
+                old: best,
+                new: value,
+            });
+            best = Some(value);
+        }
+    }
+
+    events
+}
+
+/// Mean or average of a number of consecutive solves, written as `mo3` or
+/// `ao5`.
+///
+/// Averages leave out the best and worst 5% of the solves, rounded up, so
+/// that an ao5 or ao12 drops one solve from each end and an ao100 five.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Stat {
+    pub trimmed: bool,
+    pub size: usize,
+}
+
+impl Stat {
+    /// Number of solves left out from each end of the window.
+    pub fn trim(self) -> usize {
+        if self.trimmed {
+            self.size.div_ceil(20)
+        } else {
+            0
+        }
+    }
+
+    /// Name of the stat in full, like "Average of 5".
+    pub fn long_name(self) -> String {
+        let kind = if self.trimmed { "Average" } else { "Mean" };
+
+        format!("{kind} of {}", self.size)
+    }
+}
+
+impl Display for Stat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}o{}", if self.trimmed { 'a' } else { 'm' }, self.size)
+    }
+}
+
+impl FromStr for Stat {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<Self, ParseError> {
+        let stat = value
+            .strip_prefix("ao")
+            .map(|size| (true, size))
+            .or_else(|| value.strip_prefix("mo").map(|size| (false, size)))
+            .and_then(|(trimmed, size)| Some((trimmed, size.parse::<usize>().ok()?)))
+            .map(|(trimmed, size)| Self { trimmed, size })
+            // an average needs a solve left over after trimming both ends
+            .filter(|stat| stat.size > 2 * stat.trim());
+
+        stat.ok_or_else(|| ParseError::Stat(value.to_string()))
+    }
+}
+
+/// Stat tracked in a session, which is also shown as a column of the solves
+/// table if written with a trailing `*`, as in `ao5*`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct TrackedStat {
+    pub stat: Stat,
+    pub column: bool,
+}
+
+impl TrackedStat {
+    /// Stats tracked by default: mo3, ao5 and ao12, the averages being shown
+    /// in the solves table.
+    pub fn defaults() -> Vec<Self> {
+        [(false, 3, false), (true, 5, true), (true, 12, true)]
+            .into_iter()
+            .map(|(trimmed, size, column)| Self {
+                stat: Stat { trimmed, size },
+                column,
+            })
+            .collect()
+    }
+}
+
+impl Display for TrackedStat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.stat, if self.column { "*" } else { "" })
+    }
+}
+
+impl FromStr for TrackedStat {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<Self, ParseError> {
+        let (stat, column) = match value.strip_suffix('*') {
+            Some(stat) => (stat, true),
+            None => (value, false),
+        };
+
+        Ok(Self {
+            stat: stat.parse()?,
+            column,
+        })
+    }
+}
+
+/// Parses a list of tracked stats separated by whitespace.
+pub fn parse_tracked_stats(value: &str) -> Result<Vec<TrackedStat>, ParseError> {
+    value.split_whitespace().map(str::parse).collect()
+}
+
+pub fn join_tracked_stats(tracked: &[TrackedStat]) -> String {
+    tracked
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Indices of the solves in the window of the stat ending at the solve at
+/// the index, each with whether it is trimmed from the stat.
+pub fn window_solves(stat: Stat, solves: &[Solve], end: usize) -> Option<Vec<(usize, bool)>> {
+    let start = (end + 1).checked_sub(stat.size)?;
+    let mut order = (start..=end).collect::<Vec<usize>>();
+    order.sort_by_key(|&idx| key(solves[idx].time));
+
+    let trim = stat.trim();
+    let trimmed = [&order[..trim], &order[stat.size - trim..]].concat();
+
+    Some(
+        (start..=end)
+            .map(|idx| (idx, trimmed.contains(&idx)))
+            .collect(),
+    )
+}
+
+/// Text listing the solves of the window of the stat ending at the solve at
+/// the index the way csTimer shares them, with the trimmed ones in
+/// parentheses.
+pub fn window_report(stat: Stat, value: StatEntry, solves: &[Solve], end: usize) -> Option<String> {
+    let window = window_solves(stat, solves, end)?;
+
+    let mut lines = vec![
+        format!(
+            "Generated By {} on {}",
+            env!("CARGO_PKG_NAME"),
+            Local::now().format("%Y-%m-%d")
+        ),
+        format!("{}: {value}", stat.long_name()),
+        String::new(),
+        String::from("Time List:"),
+    ];
+
+    for (number, (idx, trimmed)) in window.into_iter().enumerate() {
+        let time = report_time(&solves[idx]);
+        let time = if trimmed { format!("({time})") } else { time };
+
+        lines.push(report_line(number, &time, &solves[idx]));
+    }
+
+    Some(lines.join("\n"))
+}
+
+/// Time of a solve in a report, with the time of DNFs kept in parentheses.
+fn report_time(solve: &Solve) -> String {
+    match solve.time.penalty {
+        Penalty::Dnf => format!("DNF({})", millis_to_string_not_running(solve.time.time)),
+        _ => solve.time.to_string(),
+    }
+}
+
+fn report_line(idx: usize, time: &str, solve: &Solve) -> String {
+    let mut line = format!(
+        "{}. {time}   {}",
+        idx + 1,
+        solve.scramble.to_string().replace('\n', " ")
+    );
+
+    if !solve.comment.is_empty() {
+        line += &format!("   [{}]", solve.comment);
+    }
+
+    line
+}
+
+/// Text report of a session the way csTimer copies its statistics: the
+/// mean and average of the session, the current and best ao5 and ao12, and
+/// the times with their scrambles.
+pub fn session_report(name: &str, session: &Session) -> String {
+    let solves = &session.solves;
+    let entry_to_string =
+        |entry: Option<StatEntry>| entry.map_or(String::from("-"), |entry| entry.to_string());
+
+    let valid = solves
+        .iter()
+        .filter(|solve| !matches!(solve.time.penalty, Penalty::Dnf))
+        .map(|solve| solve.time.time)
+        .collect::<Vec<u128>>();
+    let mean = valid
+        .iter()
+        .sum::<u128>()
+        .checked_div(valid.len() as u128)
+        .map_or(String::from("-"), millis_to_string_not_running);
+
+    // the average of the whole session trims 5% of the solves from each end
+    let average = if solves.len() >= 3 {
+        let mut average = RollingStat::new(Stat {
+            trimmed: true,
+            size: solves.len(),
+        });
+        for solve in solves {
+            average.push(solve.time);
+        }
+
+        average.value()
+    } else {
+        None
+    };
+
+    let mut lines = vec![
+        format!(
+            "Statistics for {}",
+            Local::now().format("%Y-%m-%d %H:%M:%S")
+        ),
+        format!("Session: {name} ({})", session.puzzle),
+        String::new(),
+        format!("Solves: {}/{}", valid.len(), solves.len()),
+        format!("Mean: {mean}"),
+        format!("Average: {}", entry_to_string(average)),
+        format!(
+            "Best single: {}",
+            entry_to_string(solves.iter().map(|solve| solve.time).min())
+        ),
+        format!(
+            "Worst single: {}",
+            entry_to_string(solves.iter().map(|solve| solve.time).max())
+        ),
+    ];
+
+    for size in [5, 12] {
+        let stat = Stat {
+            trimmed: true,
+            size,
+        };
+        let mut window = RollingStat::new(stat);
+        let mut best: Option<StatEntry> = None;
+
+        for solve in solves {
+            if let Some(value) = window.push(solve.time) {
+                best = Some(best.map_or(value, |best| best.min(value)));
+            }
+        }
+
+        lines.extend([
+            String::new(),
+            stat.to_string(),
+            format!("current: {}", entry_to_string(window.value())),
+            format!("best: {}", entry_to_string(best)),
+        ]);
+    }
+
+    lines.extend([String::new(), String::from("Time List:")]);
+    lines.extend(
+        solves
+            .iter()
+            .enumerate()
+            .map(|(idx, solve)| report_line(idx, &report_time(solve), solve)),
+    );
+
+    lines.join("\n")
+}
+
+/// Parses a list of sub-X thresholds in seconds separated by whitespace,
+/// like `10 15 20`, into milliseconds in increasing order.
+pub fn parse_thresholds(value: &str) -> Result<Vec<u128>, ParseError> {
+    let mut thresholds = value
+        .split_whitespace()
+        .map(timer::parse_time)
+        .collect::<Result<Vec<u128>, ParseError>>()?;
+
+    thresholds.sort_unstable();
+    thresholds.dedup();
+
+    Ok(thresholds)
+}
+
+pub fn join_thresholds(thresholds: &[u128]) -> String {
+    thresholds
+        .iter()
+        .map(|&threshold| timer::millis_to_seconds_string(threshold))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Spread of the times of a session, leaving out DNFs.
+pub struct Distribution {
+    pub count: usize,
+    pub mean: u128,
+    /// Standard deviation of the times from their mean.
+    pub std_dev: u128,
+    pub median: u128,
+    /// 10th and 90th percentiles.
+    pub p10: u128,
+    pub p90: u128,
+    /// Number of solves under each sub-X threshold.
+    pub subs: Vec<(u128, usize)>,
+    /// Start of each bucket of the histogram from the fastest to the slowest
+    /// time, with the number of solves in it.
+    pub buckets: Vec<(u128, u64)>,
+}
+
+impl Distribution {
+    pub fn new(solves: &[Solve], thresholds: &[u128], bucket_width: u128) -> Option<Self> {
+        let mut times = solves
+            .iter()
+            .filter(|solve| !matches!(solve.time.penalty, Penalty::Dnf))
+            .map(|solve| solve.time.time)
+            .collect::<Vec<u128>>();
+
+        if times.is_empty() {
+            return None;
+        }
+
+        times.sort_unstable();
+
+        let count = times.len();
+        let mean = times.iter().sum::<u128>() / count as u128;
+        let variance = times
+            .iter()
+            .map(|time| time.abs_diff(mean).pow(2))
+            .sum::<u128>()
+            / count as u128;
+
+        let subs = thresholds
+            .iter()
+            .map(|&threshold| (threshold, times.partition_point(|&time| time < threshold)))
+            .collect();
+
+        let first = times[0] / bucket_width;
+        let mut counts = vec![0; (times[count - 1] / bucket_width - first) as usize + 1];
+
+        for time in &times {
+            counts[(time / bucket_width - first) as usize] += 1;
+        }
+
+        Some(Self {
+            count,
+            mean,
+            std_dev: (variance as f64).sqrt().round() as u128,
+            median: percentile(&times, 50),
+            p10: percentile(&times, 10),
+            p90: percentile(&times, 90),
+            subs,
+            buckets: (first..)
+                .map(|bucket| bucket * bucket_width)
+                .zip(counts)
+                .collect(),
+        })
+    }
+}
+
+/// Time at the percentile of the sorted times, interpolating between the two
+/// closest ones.
+fn percentile(times: &[u128], percent: u128) -> u128 {
+    let rank = (times.len() as u128 - 1) * percent;
+    let (idx, rest) = ((rank / 100) as usize, rank % 100);
+
+    if rest == 0 {
+        times[idx]
+    } else {
+        times[idx] + (times[idx + 1] - times[idx]) * rest / 100
+    }
+}
+
+// key that orders stat entries like `StatEntry` does, DNFs last, while
+// telling apart the penalties of equal times
+type Key = (bool, u128, u8);
+
+fn key(entry: StatEntry) -> Key {
+    (
+        matches!(entry.penalty, Penalty::Dnf),
+        entry.time,
+        entry.penalty as u8,
+    )
+}
+
+fn entry((_, time, penalty): Key) -> StatEntry {
+    StatEntry {
+        time,
+        penalty: Penalty::try_from(penalty).unwrap(),
+    }
+}
+
+/// Ordered multiset of stat entries.
+#[derive(Default, Clone)]
+struct Multiset {
+    counts: BTreeMap<Key, usize>,
+    len: usize,
+}
+
+impl Multiset {
+    fn insert(&mut self, key: Key) {
+        *self.counts.entry(key).or_default() += 1;
+        self.len += 1;
+    }
+
+    fn remove(&mut self, key: Key) -> bool {
+        let Some(count) = self.counts.get_mut(&key) else {
+            return false;
+        };
+
+        *count -= 1;
+
+        if *count == 0 {
+            self.counts.remove(&key);
+        }
+
+        self.len -= 1;
+        true
+    }
+
+    fn first(&self) -> Option<Key> {
+        self.counts.keys().next().copied()
+    }
+
+    fn last(&self) -> Option<Key> {
+        self.counts.keys().next_back().copied()
+    }
+
+    fn pop_first(&mut self) -> Option<Key> {
+        let key = self.first()?;
+        self.remove(key);
+        Some(key)
+    }
+
+    fn pop_last(&mut self) -> Option<Key> {
+        let key = self.last()?;
+        self.remove(key);
+        Some(key)
+    }
+}
+
+/// Stat over a window of consecutive solves that slides along a session.
+///
+/// The solves of the window are split into the ones trimmed from the bottom,
+/// the counted ones and the ones trimmed from the top, each kept ordered, so
+/// that adding a solve and dropping the oldest one takes logarithmic time.
+#[derive(Clone)]
+pub struct RollingStat {
+    stat: Stat,
+    entries: VecDeque<StatEntry>,
+    low: Multiset,
+    mid: Multiset,
+    high: Multiset,
+    // sum of the counted times that aren't DNFs and number of counted DNFs
+    sum: u128,
+    dnfs: usize,
+}
+
+impl RollingStat {
+    pub fn new(stat: Stat) -> Self {
+        Self {
+            stat,
+            entries: VecDeque::with_capacity(stat.size + 1),
+            low: Multiset::default(),
+            mid: Multiset::default(),
+            high: Multiset::default(),
+            sum: 0,
+            dnfs: 0,
+        }
+    }
+
+    pub fn stat(&self) -> Stat {
+        self.stat
+    }
+
+    /// Adds a solve to the window, dropping the oldest one if it is full, and
+    /// returns the stat of the window.
+    pub fn push(&mut self, entry: StatEntry) -> Option<StatEntry> {
+        if self.entries.len() == self.stat.size {
+            let oldest = self.entries.pop_front().unwrap();
+            self.remove(key(oldest));
+        }
+
+        self.entries.push_back(entry);
+        self.insert(key(entry));
+
+        self.value()
+    }
+
+    /// Stat of the window, once it holds enough solves.
+    pub fn value(&self) -> Option<StatEntry> {
+        if self.entries.len() < self.stat.size {
+            return None;
+        }
+
+        // with more DNFs than are trimmed, one of them counts
+        if self.dnfs > 0 {
+            return Some(StatEntry::new(0, Penalty::Dnf));
+        }
+
+        Some(StatEntry::new(self.sum / self.mid.len as u128, Penalty::Ok))
+    }
+
+    fn insert(&mut self, key: Key) {
+        let trim = self.stat.trim();
+
+        self.low.insert(key);
+
+        if self.low.len > trim {
+            let key = self.low.pop_last().unwrap();
+            self.insert_mid(key);
+        }
+
+        if self.high.len < trim {
+            if let Some(key) = self.mid.last() {
+                self.remove_mid(key);
+                self.high.insert(key);
+            }
+        } else if let (Some(lower), Some(higher)) = (self.high.first(), self.mid.last()) {
+            if higher > lower {
+                self.high.remove(lower);
+                self.remove_mid(higher);
+                self.high.insert(higher);
+                self.insert_mid(lower);
+            }
         }
     }
+
+    fn remove(&mut self, key: Key) {
+        let trim = self.stat.trim();
+
+        if !self.low.remove(key) && !self.high.remove(key) {
+            self.remove_mid(key);
+        }
+
+        if self.low.len < trim {
+            if let Some(key) = self.mid.first() {
+                self.remove_mid(key);
+                self.low.insert(key);
+            } else if let Some(key) = self.high.pop_first() {
+                self.low.insert(key);
+            }
+        }
+
+        if self.high.len < trim {
+            if let Some(key) = self.mid.last() {
+                self.remove_mid(key);
+                self.high.insert(key);
+            }
+        }
+    }
+
+    fn insert_mid(&mut self, key: Key) {
+        self.mid.insert(key);
+        self.count(key, true);
+    }
+
+    fn remove_mid(&mut self, key: Key) {
+        self.mid.remove(key);
+        self.count(key, false);
+    }
+
+    fn count(&mut self, key: Key, added: bool) {
+        let entry = entry(key);
+
+        match (entry.penalty, added) {
+            (Penalty::Dnf, true) => self.dnfs += 1,
+            (Penalty::Dnf, false) => self.dnfs -= 1,
+            (_, true) => self.sum += entry.time,
+            (_, false) => self.sum -= entry.time,
+        }
+    }
+}
+
+/// What the next solve can make of a stat, given the solves before it.
+pub struct NextSolve {
+    /// Best possible value, if the next solve is the fastest one.
+    pub best: StatEntry,
+    /// Worst possible value, if the next solve is a DNF.
+    pub worst: StatEntry,
+    /// What the next solve needs to set a PB, once there is one to beat.
+    pub target: Option<PbTarget>,
 }
 
+pub enum PbTarget {
+    /// Even a DNF sets a PB.
+    Any,
+    /// Times under this one set a PB.
+    Under(u128),
+    /// No time sets a PB.
+    Unreachable,
+}
+
+impl NextSolve {
+    /// Possible values of the stat ending at the next solve, once the solves
+    /// miss only that one to fill it.
+    pub fn new(stat: Stat, solves: &[Solve], pb: Option<StatEntry>) -> Option<Self> {
+        let start = (solves.len() + 1).checked_sub(stat.size)?;
+        let mut window = RollingStat::new(stat);
+
+        for solve in &solves[start..] {
+            window.push(solve.time);
+        }
+
+        let value = |time| window.clone().push(time).unwrap();
+        let best = value(StatEntry::new(0, Penalty::Ok));
+        let worst = value(StatEntry::new(0, Penalty::Dnf));
+
+        let target = pb.map(|pb| {
+            if worst < pb {
+                return PbTarget::Any;
+            } else if best >= pb {
+                return PbTarget::Unreachable;
+            }
+
+            // the stat only grows with the time, so the slowest time that
+            // still sets a PB is searched for between one that does and one
+            // that doesn't
+            let (mut low, mut high) = (0, pb.time * stat.size as u128 + 1);
+
+            while high - low > 1 {
+                let mid = (low + high) / 2;
+
+                if value(StatEntry::new(mid, Penalty::Ok)) < pb {
+                    low = mid;
+                } else {
+                    high = mid;
+                }
+            }
+
+            PbTarget::Under(high)
+        });
+
+        Some(Self {
+            best,
+            worst,
+            target,
+        })
+    }
+}
+
+/// Stats of a whole session, kept up to date as solves are added, changed
+/// and removed without going over every solve again.
 #[derive(Default)]
 pub struct Stats {
     pub time: StatLine,
-    pub mean_of_3: StatLine,
-    pub avg_of_5: StatLine,
-    pub avg_of_12: StatLine,
+    /// Lines of the tracked stats of the session, in the same order.
+    pub averages: Vec<StatLine>,
     pub valid_solve_count: u128,
     pub solve_count: u128,
     pub global_mean: u128,
+    times: Multiset,
+    // values every tracked stat took over the session
+    values: Vec<Multiset>,
+    sum: u128,
 }
 
 impl Stats {
-    pub fn update(&mut self, solves: &[Solve]) {
-        self.time.current = solves.last().map(|solve| solve.time);
-        self.time.best = solves.iter().map(|solve| solve.time).min();
+    /// Stats of the solves, which have their tracked stats computed.
+    pub fn new(solves: &[Solve], stat_count: usize) -> Self {
+        let mut stats = Self {
+            averages: (0..stat_count).map(|_| StatLine::default()).collect(),
+            values: (0..stat_count).map(|_| Multiset::default()).collect(),
+            ..Self::default()
+        };
+
+        for solve in solves {
+            stats.add(solve);
+        }
+
+        stats.refresh(solves.last());
+        stats.locate_bests(solves);
+
+        stats
+    }
+
+    /// Counts the solve and the tracked stats ending at it. [`Self::refresh`]
+    /// has to be called after the solves are added or removed.
+    pub fn add(&mut self, solve: &Solve) {
+        self.times.insert(key(solve.time));
+
+        for (values, average) in self.values.iter_mut().zip(&solve.averages) {
+            if let Some(average) = average {
+                values.insert(key(*average));
+            }
+        }
+
+        self.sum += solve.time.time;
+        self.solve_count += 1;
+
+        if !matches!(solve.time.penalty, Penalty::Dnf) {
+            self.valid_solve_count += 1;
+        }
+    }
+
+    /// Stops counting the solve and the tracked stats ending at it.
+    pub fn remove(&mut self, solve: &Solve) {
+        self.times.remove(key(solve.time));
+
+        for (values, average) in self.values.iter_mut().zip(&solve.averages) {
+            if let Some(average) = average {
+                values.remove(key(*average));
+            }
+        }
+
+        self.sum -= solve.time.time;
+        self.solve_count -= 1;
+
+        if !matches!(solve.time.penalty, Penalty::Dnf) {
+            self.valid_solve_count -= 1;
+        }
+    }
+
+    pub fn line(&self, kind: StatKind) -> &StatLine {
+        match kind {
+            StatKind::Single => &self.time,
+            StatKind::Tracked(idx) => &self.averages[idx],
+        }
+    }
+
+    fn line_mut(&mut self, kind: StatKind) -> &mut StatLine {
+        match kind {
+            StatKind::Single => &mut self.time,
+            StatKind::Tracked(idx) => &mut self.averages[idx],
+        }
+    }
 
-        self.mean_of_3 = StatLine::new(
-            get_mean(solves, 3),
-            solves.windows(3).filter_map(|w| get_mean(w, 3)).min(),
-        );
+    /// Updates the current and best lines, given the last solve.
+    pub fn refresh(&mut self, last: Option<&Solve>) {
+        for kind in StatKind::all(self.averages.len()) {
+            let values = match kind {
+                StatKind::Single => &self.times,
+                StatKind::Tracked(idx) => &self.values[idx],
+            };
+            let best = values.first().map(entry);
 
-        self.valid_solve_count = solves
+            let line = self.line_mut(kind);
+            line.current = last.and_then(|solve| solve.stat(kind));
+            line.best = best;
+        }
+
+        self.global_mean = self.sum.checked_div(self.solve_count).unwrap_or_default();
+    }
+
+    /// Finds the solves the bests end at by going over all of them.
+    pub fn locate_bests(&mut self, solves: &[Solve]) {
+        for kind in StatKind::all(self.averages.len()) {
+            let line = self.line_mut(kind);
+
+            line.best_solve = line.best.and_then(|best| {
+                solves.iter().position(|solve| {
+                    solve
+                        .stat(kind)
+                        .is_some_and(|value| key(value) == key(best))
+                })
+            });
+        }
+    }
+
+    /// Finds the solves the bests end at once the solves in the range had
+    /// their stats computed again, after the given number of solves were
+    /// removed at its start. All the solves are only gone over again for the
+    /// lines whose best ended at one of the changed solves.
+    pub fn relocate_bests(&mut self, solves: &[Solve], changed: Range<usize>, removed: usize) {
+        for kind in StatKind::all(self.averages.len()) {
+            let line = self.line_mut(kind);
+
+            let Some(best) = line.best else {
+                line.best_solve = None;
+                continue;
+            };
+            let is_best = |idx: &usize| {
+                solves[*idx]
+                    .stat(kind)
+                    .is_some_and(|value| key(value) == key(best))
+            };
+
+            // the solves after the changed ones moved back by the removed ones
+            let previous = line
+                .best_solve
+                .filter(|&idx| idx < changed.start || idx >= changed.end + removed)
+                .map(|idx| {
+                    if idx < changed.start {
+                        idx
+                    } else {
+                        idx - removed
+                    }
+                });
+
+            // the first solve with the best, as a changed solve may tie or
+            // beat the previous best
+            line.best_solve = match previous {
+                Some(previous) if previous < changed.start => {
+                    iter::once(previous).chain(changed.clone()).find(is_best)
+                }
+                Some(previous) => changed.clone().chain(iter::once(previous)).find(is_best),
+                None => None,
+            }
+            .or_else(|| (0..solves.len()).find(is_best));
+        }
+    }
+
+    /// Records the last solve as the one the bests it set end at, given the
+    /// lines it set a PB for.
+    pub fn locate_new_bests(&mut self, pbs: &[StatKind], last: usize) {
+        for kind in StatKind::all(self.averages.len()) {
+            let line = self.line_mut(kind);
+
+            // the first value of a line is its best without beating one
+            if pbs.contains(&kind) || (line.best_solve.is_none() && line.best.is_some()) {
+                line.best_solve = Some(last);
+            }
+        }
+    }
+}
+
+/// Summary of the solves of a session, for comparing it to the others.
+pub struct SessionSummary {
+    pub name: String,
+    pub solve_count: u128,
+    pub valid_solve_count: u128,
+    /// Time spent solving, DNFs included and penalties left out.
+    pub practice_time: u128,
+    /// Best single and best value of each stat compared across sessions.
+    pub bests: Vec<Option<StatEntry>>,
+}
+
+impl SessionSummary {
+    /// Summary of the session, which has the stats compared across sessions
+    /// computed.
+    pub fn new(name: &str, session: &Session) -> Self {
+        Self {
+            name: name.to_string(),
+            solve_count: session.stats.solve_count,
+            valid_solve_count: session.stats.valid_solve_count,
+            practice_time: session
+                .solves
+                .iter()
+                .map(|solve| solve.time.raw_time())
+                .sum(),
+            bests: StatKind::all(session.tracked.len())
+                .map(|kind| session.stats.line(kind).best())
+                .collect(),
+        }
+    }
+}
+
+/// Stats of all the sessions of a puzzle.
+pub struct GlobalStats {
+    /// Stats compared across sessions, after the single.
+    pub stats: Vec<Stat>,
+    pub sessions: Vec<SessionSummary>,
+    /// Names of the sessions whose history couldn't be read.
+    pub unreadable: Vec<String>,
+}
+
+impl GlobalStats {
+    pub fn solve_count(&self) -> u128 {
+        self.sessions
             .iter()
-            .filter(|solve| !matches!(solve.time.penalty, Penalty::Dnf))
-            .count() as u128;
-        self.solve_count = solves.len() as u128;
+            .map(|session| session.solve_count)
+            .sum()
+    }
 
-        self.global_mean = solves
+    pub fn valid_solve_count(&self) -> u128 {
+        self.sessions
             .iter()
-            .map(|solve| solve.time.time)
-            .sum::<u128>()
-            .checked_div(self.solve_count)
-            .unwrap_or_default();
-
-        self.avg_of_5 = StatLine::new(
-            solves
-                .last()
-                .map(|solve| solve.avg_of_5)
-                .unwrap_or_default(),
-            solves.iter().filter_map(|solve| solve.avg_of_5).min(),
-        );
-
-        self.avg_of_12 = StatLine::new(
-            solves
-                .last()
-                .map(|solve| solve.avg_of_12)
-                .unwrap_or_default(),
-            solves.iter().filter_map(|solve| solve.avg_of_12).min(),
-        );
-    }
-
-    pub fn update_on_new(&mut self, solves: &[Solve]) {
-        self.time.update(solves.last().map(|solve| solve.time));
-        self.mean_of_3.update(get_mean(solves, 3));
-        self.avg_of_5.update(get_avg(solves, 5));
-        self.avg_of_12.update(get_avg(solves, 12));
-
-        self.global_mean = (self.global_mean * self.solve_count + solves.last().unwrap().time.time)
-            / (self.solve_count + 1);
-
-        self.valid_solve_count += 1;
-        self.solve_count += 1;
+            .map(|session| session.valid_solve_count)
+            .sum()
+    }
+
+    pub fn practice_time(&self) -> u128 {
+        self.sessions
+            .iter()
+            .map(|session| session.practice_time)
+            .sum()
+    }
+
+    /// Best value of a line of the stats over all the sessions, with the
+    /// session it was set in.
+    pub fn best(&self, kind: StatKind) -> Option<(StatEntry, &str)> {
+        let idx = match kind {
+            StatKind::Single => 0,
+            StatKind::Tracked(idx) => idx + 1,
+        };
+
+        self.sessions
+            .iter()
+            .filter_map(|session| Some((session.bests[idx]?, session.name.as_str())))
+            .min_by(|(a, _), (b, _)| a.cmp(b))
     }
 }
 
-fn get_mean(solves: &[Solve], mean_of: usize) -> Option<StatEntry> {
-    let solves = get_solves_from_tail(solves, mean_of)?;
+/// Practice over a day or a week.
+#[derive(Default, Clone, Copy)]
+pub struct Practice {
+    pub solve_count: u128,
+    pub valid_solve_count: u128,
+    /// Time spent solving, DNFs included and penalties left out.
+    pub practice_time: u128,
+    pub best: Option<StatEntry>,
+    // sum of the valid times, penalties included
+    sum: u128,
+}
 
-    let mut sum = 0;
+impl Practice {
+    fn add(&mut self, solve: &Solve) {
+        self.solve_count += 1;
+        self.practice_time += solve.time.raw_time();
 
-    for solve in solves {
-        if matches!(solve.time.penalty, Penalty::Dnf) {
-            return Some(StatEntry::new(u128::MAX, Penalty::Dnf));
+        if !matches!(solve.time.penalty, Penalty::Dnf) {
+            self.valid_solve_count += 1;
+            self.sum += solve.time.time;
+            self.best = Some(self.best.map_or(solve.time, |best| best.min(solve.time)));
         }
+    }
 
-        sum += solve.time.time;
+    fn merge(&mut self, other: &Self) {
+        self.solve_count += other.solve_count;
+        self.valid_solve_count += other.valid_solve_count;
+        self.practice_time += other.practice_time;
+        self.sum += other.sum;
+        self.best = match (self.best, other.best) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
     }
 
-    Some(StatEntry::new(sum / 3, Penalty::Ok))
+    /// Mean of the valid solves.
+    pub fn mean(&self) -> Option<u128> {
+        (self.valid_solve_count > 0).then(|| self.sum / self.valid_solve_count)
+    }
 }
 
-pub fn get_avg(solves: &[Solve], avg_of: usize) -> Option<StatEntry> {
-    let solves = get_solves_from_tail(solves, avg_of)?;
+/// Solves of all the sessions of a puzzle grouped by the local day they were
+/// done on.
+#[derive(Default)]
+pub struct Calendar {
+    days: BTreeMap<NaiveDate, Practice>,
+    /// Names of the sessions whose history couldn't be read.
+    pub unreadable: Vec<String>,
+}
 
-    let mut sum = 0;
-    let mut min = u128::MAX;
-    let mut max = u128::MIN;
-    let mut dnf_count = 0;
+impl Calendar {
+    pub fn add(&mut self, solves: &[Solve]) {
+        for solve in solves {
+            let Some(date) = Local.timestamp_opt(solve.date as i64, 0).single() else {
+                continue;
+            };
 
-    for solve in solves {
-        if matches!(solve.time.penalty, Penalty::Dnf) {
-            dnf_count += 1;
-            if dnf_count == 2 {
-                return Some(StatEntry::new(0, Penalty::Dnf));
-            }
-        } else {
-            sum += solve.time.time;
-            min = min.min(solve.time.time);
-            max = max.max(solve.time.time);
+            self.days.entry(date.date_naive()).or_default().add(solve);
         }
     }
 
-    if dnf_count == 0 {
-        sum -= max;
+    pub fn day(&self, date: NaiveDate) -> Practice {
+        self.days.get(&date).copied().unwrap_or_default()
     }
-    sum -= min;
 
-    Some(StatEntry::new(sum / (avg_of - 2) as u128, Penalty::Ok))
-}
+    /// Practice over the week, starting on monday, the day is in.
+    pub fn week(&self, date: NaiveDate) -> Practice {
+        let week = date.week(Weekday::Mon);
+        let mut practice = Practice::default();
 
-pub fn get_solves_from_tail(solves: &[Solve], count: usize) -> Option<&[Solve]> {
-    let length = solves.len();
+        for (_, day) in self.days.range(week.first_day()..=week.last_day()) {
+            practice.merge(day);
+        }
 
-    if length < count {
-        None
-    } else {
-        Some(&solves[length - count..length])
+        practice
+    }
+
+    pub fn practice_days(&self) -> usize {
+        self.days.len()
+    }
+
+    /// Most solves done on a single day.
+    pub fn max_solve_count(&self) -> u128 {
+        self.days
+            .values()
+            .map(|day| day.solve_count)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Most consecutive days of practice.
+    pub fn longest_streak(&self) -> usize {
+        let mut longest = 0;
+        let mut streak = 0;
+        let mut previous: Option<NaiveDate> = None;
+
+        for &date in self.days.keys() {
+            streak = match previous {
+                Some(previous) if previous.succ_opt() == Some(date) => streak + 1,
+                _ => 1,
+            };
+            longest = longest.max(streak);
+            previous = Some(date);
+        }
+
+        longest
+    }
+
+    /// Consecutive days of practice up to today, which doesn't break the
+    /// streak before any solve is done on it.
+    pub fn current_streak(&self, today: NaiveDate) -> usize {
+        let mut date = if self.days.contains_key(&today) {
+            Some(today)
+        } else {
+            today.pred_opt()
+        };
+        let mut streak = 0;
+
+        while let Some(day) = date.filter(|date| self.days.contains_key(date)) {
+            streak += 1;
+            date = day.pred_opt();
+        }
+
+        streak
     }
 }
 
@@ -3,6 +3,8 @@ use std::{
     time::{Duration, SystemTime},
 };
 
+use crate::error::ParseError;
+
 pub struct Timer {
     starting_time: Option<SystemTime>,
     pub result: Duration,
@@ -68,9 +70,9 @@ pub fn millis_to_string(time: u128, is_running: bool) -> String {
         }
         _ => {
             if is_running {
-                format!("{}:{}.{}", minutes, seconds, millis / 100)
+                format!("{}:{:02}.{}", minutes, seconds, millis / 100)
             } else {
-                format!("{minutes}:{seconds}.{millis:03}")
+                format!("{minutes}:{seconds:02}.{millis:03}")
             }
         }
     }
@@ -79,3 +81,49 @@ pub fn millis_to_string(time: u128, is_running: bool) -> String {
 pub fn millis_to_string_not_running(time: u128) -> String {
     millis_to_string(time, false)
 }
+
+/// Time in seconds with only the digits it needs, like `10` or `7.5`.
+pub fn millis_to_seconds_string(time: u128) -> String {
+    millis_to_string_not_running(time)
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// Parses a time in seconds or in minutes and seconds, like `12.345` or
+/// `1:05.2`, into milliseconds.
+pub fn parse_time(value: &str) -> Result<u128, ParseError> {
+    let error = || ParseError::Time(value.to_string());
+    let is_number = |text: &str| !text.is_empty() && text.chars().all(|ch| ch.is_ascii_digit());
+
+    let (minutes, seconds) = match value.split_once(':') {
+        Some((minutes, seconds)) if is_number(minutes) => {
+            (minutes.parse::<u128>().map_err(|_| error())?, seconds)
+        }
+        Some(_) => return Err(error()),
+        None => (0, value),
+    };
+
+    let (whole, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+
+    if !is_number(whole)
+        || !(fraction.is_empty() || is_number(fraction))
+        || fraction.len() > 3
+        || (value.contains(':') && whole.len() != 2)
+    {
+        return Err(error());
+    }
+
+    let whole = whole.parse::<u128>().map_err(|_| error())?;
+    let millis = format!("{fraction:0<3}").parse::<u128>().unwrap();
+
+    if value.contains(':') && whole >= 60 {
+        return Err(error());
+    }
+
+    minutes
+        .checked_mul(60000)
+        .and_then(|time| time.checked_add(whole.checked_mul(1000)?))
+        .map(|time| time + millis)
+        .ok_or_else(error)
+}
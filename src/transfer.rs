@@ -0,0 +1,60 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use std::{ffi::OsStr, path::Path};
+
+use crate::{cstimer, history::Imported, puzzle::Puzzle, session::Session, stats, twisty};
+
+/// Format sessions are exported in.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// JSON file that csTimer can import
+    Cstimer,
+    /// Backup that Twisty Timer can restore, with a category per session
+    Twisty,
+    /// Times of a session in the external format of Twisty Timer
+    TwistyExternal,
+    /// Statistics and times of each session, like csTimer copies them
+    Report,
+}
+
+impl ExportFormat {
+    /// Format told by the extension of the file: csTimer for `.json`, a
+    /// Twisty Timer backup for `.csv` and a report otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(OsStr::to_str) {
+            Some("json") => Self::Cstimer,
+            Some("csv") => Self::Twisty,
+            _ => Self::Report,
+        }
+    }
+}
+
+/// Text of the sessions in the format. Formats that hold a single session
+/// have each session after the other.
+pub fn export(sessions: &[(&str, &Session)], format: ExportFormat) -> Result<String> {
+    Ok(match format {
+        ExportFormat::Cstimer => cstimer::export(sessions)?,
+        ExportFormat::Twisty => twisty::export_backup(sessions)?,
+        ExportFormat::TwistyExternal => sessions
+            .iter()
+            .map(|(_, session)| twisty::export_external(session))
+            .collect::<Result<String>>()?,
+        ExportFormat::Report => sessions
+            .iter()
+            .map(|(name, session)| stats::session_report(name, session))
+            .collect::<Vec<String>>()
+            .join("\n\n"),
+    })
+}
+
+/// Reads the sessions of a csTimer export, for a `.json` file, or of a
+/// Twisty Timer one, whose solves are of the puzzle if the file doesn't tell.
+pub fn import(path: &Path, puzzle: Puzzle) -> Result<Imported> {
+    match path.extension().and_then(OsStr::to_str) {
+        Some("json") => cstimer::read_export(path),
+        _ => Ok(Imported {
+            sessions: twisty::read_export(path, puzzle)?,
+            skipped: Vec::new(),
+        }),
+    }
+}
@@ -0,0 +1,388 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Local, SecondsFormat, TimeZone};
+use csv::StringRecord;
+use std::path::Path;
+
+use crate::{
+    app::{Penalty, Solve},
+    history::ImportedSession,
+    puzzle::Puzzle,
+    session::Session,
+    timer,
+};
+
+const BACKUP_HEADER: &str = "Puzzle,Category,Time(millis),Date(millis),Scramble,Penalty,Comment";
+// category of the solves that weren't put in one
+const DEFAULT_CATEGORY: &str = "Normal";
+
+// penalties of Twisty Timer backups, which store the time with them
+const TWISTY_OK: u8 = 0;
+const TWISTY_PLUS_TWO: u8 = 1;
+const TWISTY_DNF: u8 = 2;
+
+const PUZZLES: [(&str, Puzzle); 11] = [
+    ("222", Puzzle::Cube(2)),
+    ("333", Puzzle::Cube(3)),
+    ("444", Puzzle::Cube(4)),
+    ("555", Puzzle::Cube(5)),
+    ("666", Puzzle::Cube(6)),
+    ("777", Puzzle::Cube(7)),
+    ("clock", Puzzle::Clock),
+    ("mega", Puzzle::Megaminx),
+    ("pyra", Puzzle::Pyraminx),
+    ("skewb", Puzzle::Skewb),
+    ("sq1", Puzzle::Square1),
+];
+
+fn puzzle_from_id(id: &str) -> Option<Puzzle> {
+    PUZZLES
+        .into_iter()
+        .find(|(puzzle_id, _)| *puzzle_id == id)
+        .map(|(_, puzzle)| puzzle)
+}
+
+fn puzzle_id(puzzle: Puzzle) -> &'static str {
+    PUZZLES
+        .into_iter()
+        .find(|(_, other)| *other == puzzle)
+        .map(|(id, _)| id)
+        .unwrap()
+}
+
+/// Reads a Twisty Timer file, either a full backup with a session for each
+/// puzzle and category, or an export in the external format, which has no
+/// puzzle and is read as one session of the puzzle given.
+pub fn read_export(path: &Path, puzzle: Puzzle) -> Result<Vec<ImportedSession>> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .has_headers(false)
+        .flexible(true)
+        .from_path(path)
+        .with_context(|| format!("Couldn't read {}", path.display()))?;
+
+    let mut records = rdr.records().enumerate().peekable();
+
+    // the header of backups is separated by commas, unlike the solves
+    if let Some((_, Ok(record))) = records.peek() {
+        if record.iter().eq([BACKUP_HEADER]) {
+            records.next();
+        }
+    }
+
+    let mut sessions: Vec<ImportedSession> = Vec::new();
+
+    for (idx, result) in records {
+        let record = result?;
+        let context = || format!("Couldn't load line {} of {}", idx + 1, path.display());
+
+        match record.len() {
+            7 => {
+                let id = &record[0];
+                let puzzle = puzzle_from_id(id)
+                    .ok_or_else(|| anyhow!("unknown Twisty Timer puzzle {id}"))
+                    .with_context(context)?;
+                let category = &record[1];
+
+                let idx = match sessions
+                    .iter()
+                    .position(|session| session.puzzle == puzzle && session.name == category)
+                {
+                    Some(idx) => idx,
+                    None => {
+                        sessions.push(ImportedSession::new(category, puzzle));
+                        sessions.len() - 1
+                    }
+                };
+
+                push_backup_solve(&record, &mut sessions[idx]).with_context(context)?;
+            }
+            3 => {
+                if sessions.is_empty() {
+                    let name = path
+                        .file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .unwrap_or(DEFAULT_CATEGORY);
+                    sessions.push(ImportedSession::new(name, puzzle));
+                }
+
+                push_external_solve(&record, &mut sessions[0]).with_context(context)?;
+            }
+            len => bail!(
+                "Line {} of {} has {len} fields, which isn't a Twisty Timer export",
+                idx + 1,
+                path.display()
+            ),
+        }
+    }
+
+    Ok(sessions)
+}
+
+/// Solve of a backup, stored as
+/// `puzzle;category;time;date;scramble;penalty;comment` with the time and
+/// date in milliseconds.
+fn push_backup_solve(record: &StringRecord, session: &mut ImportedSession) -> Result<()> {
+    let time: u128 = record[2].parse()?;
+    let date: u64 = record[3].parse()?;
+
+    let (time, penalty) = match record[5].parse()? {
+        TWISTY_OK => (time, Penalty::Ok),
+        TWISTY_PLUS_TWO => (time.saturating_sub(2000), Penalty::PlusTwo),
+        TWISTY_DNF => (time, Penalty::Dnf),
+        penalty => bail!("unknown penalty {penalty}"),
+    };
+
+    session.push(time, penalty, &record[4], date / 1000, &record[6])
+}
+
+/// Solve of the external format, stored as `time;scramble;date` with the
+/// time like `12.345`, `12.345+` or `DNF(12.345)` and the date in ISO 8601.
+fn push_external_solve(record: &StringRecord, session: &mut ImportedSession) -> Result<()> {
+    let time = record[0].trim();
+
+    let (time, penalty) = if let Some(time) = time.strip_suffix('+') {
+        (
+            timer::parse_time(time)?.saturating_sub(2000),
+            Penalty::PlusTwo,
+        )
+    } else if let Some(time) = time.strip_prefix("DNF") {
+        let time = time.trim_start_matches('(').trim_end_matches(')');
+        let time = if time.is_empty() {
+            0
+        } else {
+            timer::parse_time(time)?
+        };
+
+        (time, Penalty::Dnf)
+    } else {
+        (timer::parse_time(time)?, Penalty::Ok)
+    };
+
+    let date = DateTime::parse_from_rfc3339(record[2].trim())
+        .with_context(|| format!("invalid date {}", &record[2]))?;
+
+    session.push(time, penalty, &record[1], date.timestamp() as u64, "")
+}
+
+fn local_date(solve: &Solve) -> Option<DateTime<Local>> {
+    Local.timestamp_opt(solve.date as i64, 0).single()
+}
+
+/// Full backup Twisty Timer can restore, with each session in the category
+/// of its name.
+pub fn export_backup(sessions: &[(&str, &Session)]) -> Result<String> {
+    let mut wtr = csv::WriterBuilder::new()
+        .delimiter(b';')
+        .quote_style(csv::QuoteStyle::Always)
+        .from_writer(Vec::new());
+
+    for (name, session) in sessions {
+        for solve in &session.solves {
+            let penalty = match solve.time.penalty {
+                Penalty::Ok => TWISTY_OK,
+                Penalty::PlusTwo => TWISTY_PLUS_TWO,
+                Penalty::Dnf => TWISTY_DNF,
+            };
+
+            wtr.write_record([
+                puzzle_id(session.puzzle).to_string(),
+                name.to_string(),
+                solve.time.time.to_string(),
+                (u128::from(solve.date) * 1000).to_string(),
+                solve.scramble.to_string(),
+                penalty.to_string(),
+                solve.comment.clone(),
+            ])?;
+        }
+    }
+
+    let solves = String::from_utf8(wtr.into_inner()?)?;
+
+    Ok(format!("{BACKUP_HEADER}\n{solves}"))
+}
+
+/// Solves of a session in the external format of Twisty Timer.
+pub fn export_external(session: &Session) -> Result<String> {
+    let mut wtr = csv::WriterBuilder::new()
+        .delimiter(b';')
+        .quote_style(csv::QuoteStyle::Always)
+        .from_writer(Vec::new());
+
+    for solve in &session.solves {
+        let time = match solve.time.penalty {
+            Penalty::Dnf => format!(
+                "DNF({})",
+                timer::millis_to_string_not_running(solve.time.time)
+            ),
+            _ => solve.time.to_string(),
+        };
+        let date = local_date(solve).map_or(String::new(), |date| {
+            date.to_rfc3339_opts(SecondsFormat::Millis, false)
+        });
+
+        wtr.write_record([time, solve.scramble.to_string(), date])?;
+    }
+
+    Ok(String::from_utf8(wtr.into_inner()?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // time with the penalty, raw time, penalty, scramble, comment and date
+    type SolveFields = (u128, u128, u8, String, String, u64);
+
+    fn solve(
+        time: u128,
+        raw: u128,
+        penalty: Penalty,
+        scramble: &str,
+        comment: &str,
+        date: u64,
+    ) -> SolveFields {
+        (
+            time,
+            raw,
+            penalty as u8,
+            scramble.to_string(),
+            comment.to_string(),
+            date,
+        )
+    }
+
+    fn solves(solves: &[Solve]) -> Vec<SolveFields> {
+        solves
+            .iter()
+            .map(|solve| {
+                (
+                    solve.time.time,
+                    solve.time.raw_time(),
+                    solve.time.penalty as u8,
+                    solve.scramble.to_string(),
+                    solve.comment.clone(),
+                    solve.date,
+                )
+            })
+            .collect()
+    }
+
+    /// Reads the export from a temporary file of this test run, named as
+    /// given.
+    fn read(name: &str, text: &str, puzzle: Puzzle) -> Vec<ImportedSession> {
+        let path = std::env::temp_dir().join(format!(
+            "{}-{}-{name}",
+            env!("CARGO_PKG_NAME"),
+            std::process::id()
+        ));
+        std::fs::write(&path, text).unwrap();
+
+        let sessions = read_export(&path, puzzle).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        sessions
+    }
+
+    fn session(imported: &ImportedSession) -> Session {
+        Session {
+            puzzle: imported.puzzle,
+            solves: imported.solves.clone(),
+            ..Session::default()
+        }
+    }
+
+    const BACKUP: &str = r#"Puzzle,Category,Time(millis),Date(millis),Scramble,Penalty,Comment
+"333";"Normal";"12345";"1700000000000";"R U R' U'";"0";"nice"
+"333";"Normal";"14000";"1700000100000";"F2";"1";""
+"333";"Normal";"9000";"1700000200000";"";"2";""
+"222";"OH";"65000";"1700000300000";"R U2";"1";""
+"333";"Normal";"65200";"1700000400000";"D";"2";""
+"#;
+
+    // a +2 and a DNF of over a minute, and a DNF without a time
+    const EXTERNAL: &str = r#""12.345";"R U R' U'";"2023-11-14T22:13:20.000+00:00"
+"14.000+";"F2";"2023-11-14T22:15:00.000+00:00"
+"DNF(9.000)";"";"2023-11-14T22:16:40.000+00:00"
+"1:05.000+";"R U2";"2023-11-14T22:18:20.000+00:00"
+"DNF(1:05.200)";"D";"2023-11-14T22:20:00.000+00:00"
+"DNF";"";"2023-11-14T22:21:40.000+00:00"
+"#;
+
+    #[test]
+    fn reads_backup() {
+        let sessions = read("backup.txt", BACKUP, Puzzle::Cube(3));
+
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions[0].name == "Normal" && sessions[0].puzzle == Puzzle::Cube(3));
+        assert!(sessions[1].name == "OH" && sessions[1].puzzle == Puzzle::Cube(2));
+        assert_eq!(
+            solves(&sessions[0].solves),
+            [
+                solve(12345, 12345, Penalty::Ok, "R U R' U'", "nice", 1700000000),
+                solve(14000, 12000, Penalty::PlusTwo, "F2", "", 1700000100),
+                solve(9000, 9000, Penalty::Dnf, "", "", 1700000200),
+                solve(65200, 65200, Penalty::Dnf, "D", "", 1700000400),
+            ]
+        );
+        assert_eq!(
+            solves(&sessions[1].solves),
+            [solve(
+                65000,
+                63000,
+                Penalty::PlusTwo,
+                "R U2",
+                "",
+                1700000300
+            )]
+        );
+    }
+
+    #[test]
+    fn reads_external() {
+        let sessions = read("external.txt", EXTERNAL, Puzzle::Cube(3));
+
+        assert_eq!(sessions.len(), 1);
+        assert!(sessions[0].puzzle == Puzzle::Cube(3));
+        assert_eq!(
+            solves(&sessions[0].solves),
+            [
+                solve(12345, 12345, Penalty::Ok, "R U R' U'", "", 1700000000),
+                solve(14000, 12000, Penalty::PlusTwo, "F2", "", 1700000100),
+                solve(9000, 9000, Penalty::Dnf, "", "", 1700000200),
+                solve(65000, 63000, Penalty::PlusTwo, "R U2", "", 1700000300),
+                solve(65200, 65200, Penalty::Dnf, "D", "", 1700000400),
+                solve(0, 0, Penalty::Dnf, "", "", 1700000500),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_what_it_exports() {
+        let imported = read("exported-backup.txt", BACKUP, Puzzle::Cube(3));
+        let sessions = imported.iter().map(session).collect::<Vec<Session>>();
+        let named = imported
+            .iter()
+            .zip(&sessions)
+            .map(|(imported, session)| (imported.name.as_str(), session))
+            .collect::<Vec<(&str, &Session)>>();
+
+        let reread = read(
+            "reread-backup.txt",
+            &export_backup(&named).unwrap(),
+            Puzzle::Cube(3),
+        );
+
+        assert_eq!(reread.len(), imported.len());
+        for (reread, imported) in reread.iter().zip(&imported) {
+            assert!(reread.name == imported.name && reread.puzzle == imported.puzzle);
+            assert_eq!(solves(&reread.solves), solves(&imported.solves));
+        }
+
+        // the external format has no comments
+        let imported = read("exported-external.txt", EXTERNAL, Puzzle::Cube(3));
+        let exported = export_external(&session(&imported[0])).unwrap();
+        let reread = read("reread-external.txt", &exported, Puzzle::Cube(3));
+
+        assert_eq!(solves(&reread[0].solves), solves(&imported[0].solves));
+    }
+}
@@ -1,18 +1,42 @@
 use crate::{
-    app::{App, AppState, Confirmation},
-    cube::Face,
-    inspection::INSPECTION_DURATION,
-    stats::stat_line_to_row,
-    timer::millis_to_string_not_running,
+    app::{App, AppState, Confirmation, Input, Penalty, Prompt},
+    clock::Clock,
+    cube::{Cube, Face},
+    megaminx::{self, Megaminx},
+    puzzle::Preview,
+    pyraminx::{self, Pyraminx},
+    skewb::Skewb,
+    square1::{self, Square1},
+    stats::{
+        self, stat_line_to_row, Calendar, Distribution, GlobalStats, NextSolve, PbTarget, Practice,
+        StatEntry, StatKind,
+    },
+    timer::{millis_to_seconds_string, millis_to_string_not_running},
 };
-use chrono::{Local, TimeZone};
+use chrono::{Datelike, Local, TimeDelta, TimeZone, Weekday};
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Flex, Layout, Rect},
     style::{Color, Modifier, Style},
+    symbols::Marker,
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Clear, Padding, Paragraph, Row, Table, Wrap},
+    widgets::{
+        Axis, Bar, BarChart, BarGroup, Block, Borders, Chart, Clear, Dataset, GraphType, Padding,
+        Paragraph, Row, Table, Tabs, Wrap,
+    },
     Frame,
 };
+use std::time::Duration;
+
+// how long the PBs set by a solve are shown under the time
+const PB_FLASH_DURATION: Duration = Duration::from_secs(3);
+// colors of the days of the calendar, from no solves to the most solves
+const CALENDAR_COLORS: [Color; 5] = [
+    Color::DarkGray,
+    Color::Rgb(14, 68, 41),
+    Color::Rgb(0, 109, 50),
+    Color::Rgb(38, 166, 65),
+    Color::Rgb(57, 211, 83),
+];
 
 pub fn render(f: &mut Frame, app: &mut App) {
     if app.inspection.is_running() {
@@ -21,9 +45,15 @@ pub fn render(f: &mut Frame, app: &mut App) {
     } else if matches!(app.state, AppState::Set) || app.timer.is_running() {
         render_timer(f, app, f.area());
         return;
+    } else if let (AppState::GlobalStats, Some(global)) = (&app.state, &app.global_stats) {
+        render_global_stats(f, app, global, f.area());
+        return;
+    } else if let (AppState::Calendar, Some(calendar)) = (&app.state, &app.calendar) {
+        render_calendar(f, app, calendar, f.area());
+        return;
     }
 
-    let (left_pane_area, main_area) = if f.area().width > 140 {
+    let (left_pane_area, main_area) = if f.area().width > app.config.wide_layout_width {
         let chunks = Layout::default()
             .constraints([Constraint::Length(45), Constraint::Min(1)].as_ref())
             .direction(Direction::Horizontal)
@@ -34,42 +64,63 @@ pub fn render(f: &mut Frame, app: &mut App) {
         (None, f.area())
     };
 
+    let scramble = app
+        .scramble
+        .as_ref()
+        .map_or(String::from("Generating scramble..."), ToString::to_string);
+    let scramble_lines = wrap_words(&scramble, main_area.width.saturating_sub(2) as usize);
+
     let mut chunks = Layout::default()
-        .constraints([Constraint::Max(3), Constraint::Min(3), Constraint::Max(13)].as_ref())
+        .constraints(
+            [
+                Constraint::Max(scramble_lines.len() as u16 + 2),
+                Constraint::Min(3),
+                Constraint::Max(stats_height(app).max(13)),
+            ]
+            .as_ref(),
+        )
         .direction(Direction::Vertical)
         .split(main_area);
 
-    render_scramble(f, app, chunks[0]);
+    render_scramble(f, app, scramble_lines, chunks[0]);
     render_timer(f, app, chunks[1]);
 
-    if let Some(area) = left_pane_area {
+    // the chart fills the space left next to the preview
+    let (chart_area, preview_area) = if let Some(area) = left_pane_area {
         render_left_pane(f, app, area);
         chunks = Layout::default()
-            .constraints([
-                Constraint::Min(1),
-                Constraint::Min(1),
-                Constraint::Min(1),
-                Constraint::Length(29),
-            ])
+            .constraints([Constraint::Min(1), Constraint::Length(preview_width(app))])
             .direction(Direction::Horizontal)
             .split(chunks[2]);
+
+        (chunks[0], chunks[1])
     } else {
         chunks = Layout::default()
             .constraints([
                 Constraint::Length(32),
                 Constraint::Length(40),
                 Constraint::Min(1),
-                Constraint::Length(29),
+                Constraint::Length(preview_width(app)),
             ])
             .direction(Direction::Horizontal)
             .split(chunks[2]);
         render_stats(f, app, chunks[0]);
         render_solves(f, app, chunks[1]);
-    }
-    render_cube(f, app, chunks[3]);
 
-    if let AppState::SolveInfo = app.state {
-        render_info_window(f, app);
+        (chunks[2], chunks[3])
+    };
+
+    if app.chart_enabled && chart_area.width > 0 {
+        render_chart(f, app, chart_area);
+    }
+    render_preview(f, app, preview_area);
+
+    match app.state {
+        AppState::SolveInfo => render_info_window(f, app),
+        AppState::Histogram => render_histogram_window(f, app),
+        AppState::PbHistory => render_pb_history_window(f, app),
+        AppState::AverageDetail => render_average_detail_window(f, app),
+        _ => (),
     }
 
     match app.confirmation {
@@ -83,17 +134,27 @@ pub fn render(f: &mut Frame, app: &mut App) {
         Some(Confirmation::Session) => render_confirmation_window(
             f,
             &format!(
-                "Are you sure you want to delete 'Session {}'?",
-                app.selected_session_idx + 1
+                "Are you sure you want to delete '{}'?",
+                app.session_info().name
             ),
         ),
         None => (),
     }
+
+    if let Some(input) = &app.input {
+        render_input_window(f, input);
+    }
+
+    if let Some(error) = &app.error {
+        render_message_window(f, "Error", Color::Red, error);
+    } else if let Some(notice) = &app.notice {
+        render_message_window(f, "Notice", Color::Cyan, notice);
+    }
 }
 
 fn render_left_pane(f: &mut Frame, app: &mut App, area: Rect) {
     let chunks = Layout::default()
-        .constraints([Constraint::Max(9), Constraint::Min(1)].as_ref())
+        .constraints([Constraint::Max(stats_height(app)), Constraint::Min(1)].as_ref())
         .direction(Direction::Vertical)
         .split(area);
 
@@ -101,18 +162,41 @@ fn render_left_pane(f: &mut Frame, app: &mut App, area: Rect) {
     render_solves(f, app, chunks[1]);
 }
 
+/// Height of the stats table, which has a row for each tracked stat and,
+/// below it, one for what the next solve can do to each solves column.
+fn stats_height(app: &App) -> u16 {
+    let columns = app
+        .session
+        .tracked
+        .iter()
+        .filter(|tracked| tracked.column)
+        .count() as u16;
+
+    app.session.tracked.len() as u16 + 6 + if columns > 0 { columns + 2 } else { 0 }
+}
+
 fn render_stats(f: &mut Frame, app: &App, area: Rect) {
-    let stats = vec![
-        stat_line_to_row("time:", &app.get_stats().time),
-        stat_line_to_row("mo3:", &app.get_stats().mean_of_3),
-        stat_line_to_row("avg5:", &app.get_stats().avg_of_5),
-        stat_line_to_row("avg12:", &app.get_stats().avg_of_12),
+    let labels = app
+        .session
+        .tracked
+        .iter()
+        .map(|tracked| format!("{}:", tracked.stat))
+        .collect::<Vec<String>>();
+
+    let mut stats = vec![stat_line_to_row("time:", &app.get_stats().time)];
+    stats.extend(
+        labels
+            .iter()
+            .zip(&app.get_stats().averages)
+            .map(|(label, line)| stat_line_to_row(label, line)),
+    );
+    stats.extend([
         Row::new(vec![Span::raw("")]),
         Row::new(vec![
             Span::raw("mean:"),
             Span::raw(millis_to_string_not_running(app.get_stats().global_mean)),
         ]),
-    ];
+    ]);
 
     let widths = [
         Constraint::Percentage(33),
@@ -120,58 +204,123 @@ fn render_stats(f: &mut Frame, app: &App, area: Rect) {
         Constraint::Percentage(33),
     ];
 
+    let header_style = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+
     let stats = Table::new(stats, widths)
-        .header(
-            Row::new(vec!["      ", "Current", "Best"]).style(
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            ),
-        )
-        .column_spacing(2)
-        .block(
-            Block::default().borders(Borders::ALL).title(Span::styled(
-                format!("Stats [Session {}]", app.selected_session_idx + 1),
-                Style::default()
-                    .fg(Color::Cyan)
-                    .add_modifier(Modifier::BOLD),
-            )),
-        );
+        .header(Row::new(vec!["      ", "Current", "Best"]).style(header_style))
+        .column_spacing(2);
+
+    let block = Block::default().borders(Borders::ALL).title(Span::styled(
+        format!(
+            "Stats [{} - {}]",
+            app.session_info().name,
+            app.session.puzzle
+        ),
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    ));
+    let chunks = Layout::default()
+        .constraints([
+            Constraint::Length(app.session.tracked.len() as u16 + 4),
+            Constraint::Length(1),
+            Constraint::Min(0),
+        ])
+        .direction(Direction::Vertical)
+        .split(block.inner(area));
+    f.render_widget(block, area);
+    f.render_widget(stats, chunks[0]);
+
+    // best and worst possible averages of the next solve, and what it takes
+    // to set a PB
+    let next = app
+        .session
+        .tracked
+        .iter()
+        .enumerate()
+        .filter(|(_, tracked)| tracked.column)
+        .map(|(idx, tracked)| {
+            let label = format!("{}:", tracked.stat);
+            let pb = app.get_stats().averages[idx].best();
+
+            let Some(next) = NextSolve::new(tracked.stat, app.get_solves(), pb) else {
+                return Row::new(vec![label, "-".into(), "-".into(), "-".into()]);
+            };
 
-    f.render_widget(stats, area);
+            let target = match next.target {
+                Some(PbTarget::Any) => String::from("any"),
+                Some(PbTarget::Under(time)) => format!("<{}", millis_to_string_not_running(time)),
+                Some(PbTarget::Unreachable) | None => String::from("-"),
+            };
+
+            Row::new(vec![
+                label,
+                next.best.to_string(),
+                next.worst.to_string(),
+                target,
+            ])
+        })
+        .collect::<Vec<Row>>();
+
+    if next.is_empty() {
+        return;
+    }
+
+    let next = Table::new(
+        next,
+        [
+            Constraint::Length(6),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(Row::new(vec!["next", "BPA", "WPA", "PB"]).style(header_style))
+    .column_spacing(1);
+    f.render_widget(next, chunks[2]);
 }
 
 fn render_solves(f: &mut Frame, app: &mut App, area: Rect) {
+    // indices of the tracked stats shown as columns
+    let columns = app
+        .session
+        .tracked
+        .iter()
+        .enumerate()
+        .filter(|(_, tracked)| tracked.column)
+        .map(|(idx, _)| idx)
+        .collect::<Vec<usize>>();
+
     let solves = app
         .get_solves()
         .iter()
         .enumerate()
         .rev()
         .map(|(idx, solve)| {
-            vec![
+            let mut row = vec![
                 Span::raw(format!("{}.", idx + 1)),
                 Span::raw(solve.time.to_string()),
-                Span::raw(
-                    solve
-                        .avg_of_5
-                        .map_or("-".to_string(), |stat| stat.to_string()),
-                ),
-                Span::raw(
-                    solve
-                        .avg_of_12
-                        .map_or("-".to_string(), |stat| stat.to_string()),
-                ),
-            ]
+            ];
+
+            row.extend(columns.iter().map(|&column| {
+                Span::raw(solve.averages[column].map_or("-".to_string(), |stat| stat.to_string()))
+            }));
+
+            row
         })
         .map(Row::new)
         .collect::<Vec<Row>>();
 
-    let widths = [
-        Constraint::Percentage(25),
-        Constraint::Percentage(25),
-        Constraint::Percentage(25),
-        Constraint::Percentage(25),
-    ];
+    let widths = vec![Constraint::Ratio(1, columns.len() as u32 + 2); columns.len() + 2];
+
+    let mut header = vec![String::from(" "), String::from("time")];
+    header.extend(
+        columns
+            .iter()
+            .map(|&column| app.session.tracked[column].stat.to_string()),
+    );
 
     let block = Block::default().borders(Borders::ALL).title(Span::styled(
         format!(
@@ -194,7 +343,7 @@ fn render_solves(f: &mut Frame, app: &mut App, area: Rect) {
                 .add_modifier(Modifier::BOLD),
         )
         .header(
-            Row::new(vec![" ", "time", "ao5", "ao12"]).style(
+            Row::new(header).style(
                 Style::default()
                     .fg(Color::Yellow)
                     .add_modifier(Modifier::BOLD),
@@ -205,20 +354,177 @@ fn render_solves(f: &mut Frame, app: &mut App, area: Rect) {
     f.render_stateful_widget(solves, area, &mut app.session.state);
 }
 
-fn render_scramble(f: &mut Frame, app: &App, area: Rect) {
+/// Plots the singles and the tracked stats with a column over the solves of
+/// the session, along with the best single so far. DNFs are marked along the
+/// top edge.
+fn render_chart(f: &mut Frame, app: &App, area: Rect) {
+    let solves = app.get_solves();
+    let start = app
+        .chart_zoom
+        .map_or(0, |zoom| solves.len().saturating_sub(zoom));
+
+    let x = |idx: usize| (idx + 1) as f64;
+    let y = |entry: StatEntry| entry.time as f64 / 1000.0;
+    let is_dnf = |entry: &StatEntry| matches!(entry.penalty, Penalty::Dnf);
+
+    // the best single before the chart starts is where the PB line starts
+    let mut pb = solves[..start].iter().map(|solve| solve.time).min();
+    let mut singles = Vec::new();
+    let mut dnfs = Vec::new();
+    let mut pbs = Vec::new();
+
+    for (idx, solve) in solves.iter().enumerate().skip(start) {
+        if is_dnf(&solve.time) {
+            dnfs.push(idx);
+        } else {
+            singles.push((x(idx), y(solve.time)));
+        }
+
+        pb = Some(pb.map_or(solve.time, |pb| pb.min(solve.time)));
+
+        if let Some(pb) = pb.filter(|pb| !is_dnf(pb)) {
+            pbs.push((x(idx), y(pb)));
+        }
+    }
+
+    let averages = app
+        .session
+        .tracked
+        .iter()
+        .enumerate()
+        .filter(|(_, tracked)| tracked.column)
+        .map(|(column, tracked)| {
+            let points = solves
+                .iter()
+                .enumerate()
+                .skip(start)
+                .filter_map(|(idx, solve)| solve.averages[column].map(|stat| (idx, stat)))
+                .filter(|(_, stat)| !is_dnf(stat))
+                .map(|(idx, stat)| (x(idx), y(stat)))
+                .collect::<Vec<(f64, f64)>>();
+
+            (tracked.stat.to_string(), points)
+        })
+        .collect::<Vec<(String, Vec<(f64, f64)>)>>();
+
+    let (low, high) = singles
+        .iter()
+        .chain(averages.iter().flat_map(|(_, points)| points))
+        .fold((f64::MAX, f64::MIN), |(low, high), &(_, y)| {
+            (low.min(y), high.max(y))
+        });
+    let (low, high) = if low > high {
+        (0.0, 1.0)
+    } else {
+        let margin = ((high - low) * 0.05).max(0.5);
+        ((low - margin).max(0.0), high + margin)
+    };
+
+    let dnfs = dnfs
+        .into_iter()
+        .map(|idx| (x(idx), high))
+        .collect::<Vec<(f64, f64)>>();
+
+    // name, color, whether the points are joined and the points of each
+    // series
+    let mut series = vec![(String::from("single"), Color::Gray, false, &singles)];
+    series.extend(
+        averages
+            .iter()
+            .zip(
+                [Color::Yellow, Color::Magenta, Color::Green, Color::Blue]
+                    .into_iter()
+                    .cycle(),
+            )
+            .map(|((name, points), color)| (name.clone(), color, true, points)),
+    );
+    series.extend([
+        (String::from("PB"), Color::Cyan, true, &pbs),
+        (String::from("DNF"), Color::Red, false, &dnfs),
+    ]);
+
+    let datasets = series
+        .iter()
+        .map(|(_, color, joined, points)| {
+            let (marker, graph_type) = if *joined {
+                (Marker::Braille, GraphType::Line)
+            } else {
+                (Marker::Dot, GraphType::Scatter)
+            };
+
+            Dataset::default()
+                .marker(marker)
+                .graph_type(graph_type)
+                .style(Style::default().fg(*color))
+                .data(points)
+        })
+        .collect::<Vec<Dataset>>();
+
+    let first = start + 1;
+    let last = solves.len().max(first + 1);
+    let label = |seconds: f64| millis_to_string_not_running((seconds * 1000.0) as u128);
+
+    let title = match app.chart_zoom {
+        Some(zoom) => format!("Chart [last {zoom}]"),
+        None => String::from("Chart [all]"),
+    };
+
+    // the legend goes in the title, as the chart is too short to fit it
+    let mut title = vec![Span::styled(
+        title,
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    )];
+    title.extend(series.iter().flat_map(|(name, color, _, _)| {
+        [
+            Span::raw(" "),
+            Span::styled(name.as_str(), Style::default().fg(*color)),
+        ]
+    }));
+
+    let chart = Chart::new(datasets)
+        .legend_position(None)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(title)),
+        )
+        .x_axis(
+            Axis::default()
+                .bounds([first as f64, last as f64])
+                .labels([first.to_string(), last.to_string()])
+                .style(Style::default().fg(Color::DarkGray)),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([low, high])
+                .labels([label(low), label((low + high) / 2.0), label(high)])
+                .style(Style::default().fg(Color::DarkGray)),
+        );
+
+    f.render_widget(chart, area);
+}
+
+fn render_scramble(f: &mut Frame, app: &App, lines: Vec<String>, area: Rect) {
+    let title = match app.scramble_length() {
+        Some(length) => format!("Scramble [{length} moves]"),
+        None => String::from("Scramble"),
+    };
+
     let block = Block::default().borders(Borders::ALL).title(Span::styled(
-        "Scramble",
+        title,
         Style::default()
             .fg(Color::Cyan)
             .add_modifier(Modifier::BOLD),
     ));
     f.render_widget(block, area);
 
-    let scramble = app.scramble.to_string();
+    let scramble = lines.join("\n");
 
     let area = center_vertically(&scramble, area);
 
-    let scramble = Paragraph::new(Span::styled(
+    let scramble = Paragraph::new(Text::styled(
         scramble,
         Style::default()
             .fg(Color::Blue)
@@ -233,8 +539,36 @@ fn render_timer(f: &mut Frame, app: &App, area: Rect) {
     let time = app.timer.to_string();
 
     let time = generate_font(&time);
+    let outer_area = area;
     let area = center_vertically(&time, area);
 
+    if let Some((text, since)) = &app.pb_flash {
+        let elapsed = since.elapsed();
+
+        // on the line below the time, when it fits
+        if elapsed < PB_FLASH_DURATION && area.bottom() < outer_area.bottom() {
+            let color = if (elapsed.as_millis() / 250).is_multiple_of(2) {
+                Color::Magenta
+            } else {
+                Color::Cyan
+            };
+            let flash = Paragraph::new(Span::styled(
+                text.as_str(),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ))
+            .alignment(Alignment::Center);
+
+            f.render_widget(
+                flash,
+                Rect {
+                    y: area.bottom(),
+                    height: 1,
+                    ..outer_area
+                },
+            );
+        }
+    }
+
     let time_text = Paragraph::new(Text::styled(
         time,
         Style::default().fg(match app.state {
@@ -249,11 +583,7 @@ fn render_timer(f: &mut Frame, app: &App, area: Rect) {
 }
 
 fn render_inspection(f: &mut Frame, app: &App, area: Rect) {
-    let Some(remaining) = app
-        .inspection
-        .elapsed()
-        .map(|elapsed| INSPECTION_DURATION.saturating_sub(elapsed))
-    else {
+    let Some(remaining) = app.inspection.remaining() else {
         return;
     };
 
@@ -290,12 +620,14 @@ fn info_window_row<'a>(field: &'a str, value: &'a str) -> Row<'a> {
 }
 
 fn render_info_window(f: &mut Frame, app: &App) {
-    let window = popup_window_from_dimensions(9, 100, f.area());
-    f.render_widget(Clear, window);
-
     let Some(idx) = app.session.selected_idx() else {
         return;
     };
+    let solve = &app.session.solves[idx];
+
+    let height = app.session.tracked.len() + 7 + usize::from(!solve.comment.is_empty());
+    let window = popup_window_from_dimensions(height as u16, 100, f.area());
+    f.render_widget(Clear, window);
 
     let block = Block::default()
         .title(Span::styled(
@@ -307,79 +639,521 @@ fn render_info_window(f: &mut Frame, app: &App) {
     let info_area = block.inner(window);
     f.render_widget(block, window);
 
-    let solve = &app.session.solves[idx];
     let time = solve.time.to_string();
-    let avg5 = solve.avg_of_5.map_or(String::from("-"), |t| t.to_string());
-    let avg12 = solve.avg_of_12.map_or(String::from("-"), |t| t.to_string());
-    let scramble = solve.scramble.to_string();
+    let averages = app
+        .session
+        .tracked
+        .iter()
+        .zip(&solve.averages)
+        .map(|(tracked, average)| {
+            (
+                format!("{}:", tracked.stat.long_name()),
+                average.map_or(String::from("-"), |t| t.to_string()),
+            )
+        })
+        .collect::<Vec<(String, String)>>();
+    let scramble = solve.scramble.to_string().replace('\n', " ");
     let date = Local
         .timestamp_opt(solve.date as i64, 0)
         .unwrap()
         .format("%Y-%m-%d %H:%M:%S")
         .to_string();
 
-    let lines = vec![
-        info_window_row("Time:", &time),
-        info_window_row("Average of 5:", &avg5),
-        info_window_row("Average of 12:", &avg12),
+    let mut lines = vec![info_window_row("Time:", &time)];
+    lines.extend(
+        averages
+            .iter()
+            .map(|(field, value)| info_window_row(field, value)),
+    );
+    lines.extend([
         info_window_row("Scramble:", &scramble),
         info_window_row("Date:", &date),
-    ];
+    ]);
+    if !solve.comment.is_empty() {
+        lines.push(info_window_row("Comment:", &solve.comment));
+    }
 
-    let table = Table::new(lines, &[Constraint::Length(15), Constraint::Fill(1)]);
+    let field_width = averages
+        .iter()
+        .map(|(field, _)| field.len() as u16 + 1)
+        .fold(15, u16::max);
+    let table = Table::new(
+        lines,
+        &[Constraint::Length(field_width), Constraint::Fill(1)],
+    );
     f.render_widget(table, info_area);
 }
 
-impl From<&Face> for Span<'_> {
-    fn from(face: &Face) -> Self {
-        let color: Color = (*face).into();
+// position of each face in the net, in units of faces
+const NET_LAYOUT: [(Face, usize, usize); 6] = [
+    (Face::Up, 1, 0),
+    (Face::Left, 0, 1),
+    (Face::Front, 1, 1),
+    (Face::Right, 2, 1),
+    (Face::Back, 3, 1),
+    (Face::Down, 1, 2),
+];
 
-        Span::styled("██", Style::default().fg(color))
+// sticker width and height followed by the gap between faces, in pixels,
+// from the largest scale to the smallest
+const NET_SCALES: [(usize, usize, usize, usize); 3] = [(2, 2, 1, 2), (1, 1, 1, 1), (1, 1, 0, 0)];
+
+/// Width of the scramble preview pane, wide enough for the net of the cube
+/// at its smallest scale.
+fn preview_width(app: &App) -> u16 {
+    match &app.preview {
+        Preview::Cube(cube) => (4 * cube.size as u16 + 5).max(29),
+        Preview::Pyraminx(_) => 29,
+        Preview::Megaminx(_) => 49,
+        Preview::Skewb(_) => 29,
+        Preview::Square1(_) => 41,
+        Preview::Clock(_) => 29,
     }
 }
 
-fn render_cube(f: &mut Frame, app: &mut App, area: Rect) {
-    let mut grid = (0..11)
-        .map(|_| vec![Span::raw(" "); 15])
-        .collect::<Vec<Vec<Span>>>();
+/// Lays out the net of the cube as pixels half a cell tall, using the
+/// largest scale that fits in the given size.
+fn cube_net(cube: &Cube, width: usize, height: usize) -> Vec<Vec<Option<Color>>> {
+    let n = cube.size;
 
-    for i in 0..3 {
-        grid[i][7] = Span::from(&app.cube_preview.facelets[i * 3]);
-        grid[i][8] = Span::from(&app.cube_preview.facelets[i * 3 + 1]);
-        grid[i][9] = Span::from(&app.cube_preview.facelets[i * 3 + 2]);
+    let (sticker_w, sticker_h, gap_w, gap_h) = NET_SCALES
+        .into_iter()
+        .find(|(sticker_w, sticker_h, gap_w, gap_h)| {
+            4 * n * sticker_w + 3 * gap_w <= width && 3 * n * sticker_h + 2 * gap_h <= height
+        })
+        .unwrap_or(NET_SCALES[NET_SCALES.len() - 1]);
 
-        grid[i + 4][0] = Span::from(&app.cube_preview.facelets[i * 3 + 9]);
-        grid[i + 4][1] = Span::from(&app.cube_preview.facelets[i * 3 + 10]);
-        grid[i + 4][2] = Span::from(&app.cube_preview.facelets[i * 3 + 11]);
+    let (face_w, face_h) = (n * sticker_w, n * sticker_h);
+    let mut pixels = vec![vec![None; 4 * face_w + 3 * gap_w]; 3 * face_h + 2 * gap_h];
 
-        grid[i + 4][4] = Span::from(&app.cube_preview.facelets[i * 3 + 18]);
-        grid[i + 4][5] = Span::from(&app.cube_preview.facelets[i * 3 + 19]);
-        grid[i + 4][6] = Span::from(&app.cube_preview.facelets[i * 3 + 20]);
+    for (face, col, row) in NET_LAYOUT {
+        let (x0, y0) = (col * (face_w + gap_w), row * (face_h + gap_h));
 
-        grid[i + 4][8] = Span::from(&app.cube_preview.facelets[i * 3 + 27]);
-        grid[i + 4][9] = Span::from(&app.cube_preview.facelets[i * 3 + 28]);
-        grid[i + 4][10] = Span::from(&app.cube_preview.facelets[i * 3 + 29]);
+        for (idx, sticker) in cube.face(face).iter().enumerate() {
+            let (x, y) = (x0 + idx % n * sticker_w, y0 + idx / n * sticker_h);
 
-        grid[i + 4][12] = Span::from(&app.cube_preview.facelets[i * 3 + 36]);
-        grid[i + 4][13] = Span::from(&app.cube_preview.facelets[i * 3 + 37]);
-        grid[i + 4][14] = Span::from(&app.cube_preview.facelets[i * 3 + 38]);
+            for line in &mut pixels[y..y + sticker_h] {
+                line[x..x + sticker_w].fill(Some(Color::from(*sticker)));
+            }
+        }
+    }
 
-        grid[i + 8][7] = Span::from(&app.cube_preview.facelets[i * 3 + 45]);
-        grid[i + 8][8] = Span::from(&app.cube_preview.facelets[i * 3 + 46]);
-        grid[i + 8][9] = Span::from(&app.cube_preview.facelets[i * 3 + 47]);
+    pixels
+}
+
+/// Draws two rows of pixels per line using half block characters.
+fn pixels_to_lines(pixels: &[Vec<Option<Color>>]) -> Vec<Line<'static>> {
+    pixels
+        .chunks(2)
+        .map(|rows| {
+            let bottom = rows.get(1);
+
+            rows[0]
+                .iter()
+                .enumerate()
+                .map(|(x, &top)| match (top, bottom.and_then(|row| row[x])) {
+                    (None, None) => Span::raw(" "),
+                    (Some(top), Some(bottom)) if top == bottom => {
+                        Span::styled("█", Style::default().fg(top))
+                    }
+                    (Some(top), Some(bottom)) => {
+                        Span::styled("▀", Style::default().fg(top).bg(bottom))
+                    }
+                    (Some(top), None) => Span::styled("▀", Style::default().fg(top)),
+                    (None, Some(bottom)) => Span::styled("▄", Style::default().fg(bottom)),
+                })
+                .collect::<Vec<Span>>()
+                .into()
+        })
+        .collect()
+}
+
+// corners of each face in the net, in the vertex order of `pyraminx::FACES`,
+// where faces are two units wide and the net is centered on the origin
+const PYRAMINX_NET_LAYOUT: [[(f64, f64); 3]; 4] = {
+    const H: f64 = 1.732_050_807_568_877_2;
+
+    [
+        [(0.0, H), (-1.0, 0.0), (1.0, 0.0)],
+        [(0.0, H), (-2.0, H), (-1.0, 0.0)],
+        [(0.0, H), (1.0, 0.0), (2.0, H)],
+        [(-1.0, 0.0), (1.0, 0.0), (0.0, -H)],
+    ]
+};
+
+// gap between the faces of the pyraminx net, as a fraction of their height
+const PYRAMINX_NET_GAP: f64 = 0.06;
+
+/// Rasterizes the net of the pyraminx with faces as large as fit in the given
+/// size, in pixels half a cell tall.
+fn pyraminx_net(pyraminx: &Pyraminx, width: usize, height: usize) -> Vec<Vec<Option<Color>>> {
+    let edge = (width as f64 / 2.0)
+        .min(height as f64 / 3.0_f64.sqrt())
+        .floor();
+    let unit = edge / 2.0;
+    let (net_w, net_h) = (2.0 * edge, (3.0_f64.sqrt() * edge).ceil());
+
+    let mut pixels = vec![vec![None; net_w as usize]; net_h as usize];
+
+    for (y, line) in pixels.iter_mut().enumerate() {
+        for (x, pixel) in line.iter_mut().enumerate() {
+            let point = (
+                (x as f64 + 0.5) / unit - 2.0,
+                net_h / 2.0 / unit - (y as f64 + 0.5) / unit,
+            );
+
+            *pixel =
+                pyraminx::FACES
+                    .iter()
+                    .zip(PYRAMINX_NET_LAYOUT)
+                    .find_map(|((face, _), corners)| {
+                        let sticker = pyraminx_sticker(barycentric(point, corners))?;
+                        Some(Color::from(pyraminx.sticker(*face, sticker)))
+                    });
+        }
     }
 
-    let grid = grid.into_iter().map(Line::from).collect::<Vec<Line>>();
-    let text = Paragraph::new(grid).block(
-        Block::default().borders(Borders::ALL).title(Span::styled(
-            "Scramble Preview",
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
-        )),
+    pixels
+}
+
+/// Sticker of a face at the point with the given weights over the corners of
+/// the face, if the point isn't in the gap around it.
+fn pyraminx_sticker(weights: [f64; 3]) -> Option<[u8; 3]> {
+    if weights.iter().any(|&w| w < PYRAMINX_NET_GAP) {
+        return None;
+    }
+
+    let cells = weights.map(|w| {
+        let w = (w - PYRAMINX_NET_GAP) / (1.0 - 3.0 * PYRAMINX_NET_GAP);
+        ((3.0 * w).floor() as u8).min(2)
+    });
+
+    // upward triangles of the grid have cells summing to two and downward
+    // ones to one
+    match cells.iter().sum::<u8>() {
+        2 => Some(cells.map(|c| 3 * c + 1)),
+        1 => Some(cells.map(|c| 3 * c + 2)),
+        _ => None,
+    }
+}
+
+// depth of the corner and edge stickers of the megaminx net and gap between
+// its faces, as fractions of the inradius of a face
+const MEGAMINX_NET_DEPTH: f64 = 0.4;
+const MEGAMINX_NET_GAP: f64 = 0.06;
+
+/// Rasterizes the two stars of the megaminx net as large as they fit in the
+/// given size, in pixels half a cell tall.
+fn megaminx_net(megaminx: &Megaminx, width: usize, height: usize) -> Vec<Vec<Option<Color>>> {
+    let layout = megaminx::net_layout();
+    let points = layout.iter().flatten();
+    let (min_x, max_x) = points.clone().fold((f64::MAX, f64::MIN), |(min, max), p| {
+        (min.min(p.0), max.max(p.0))
+    });
+    let (min_y, max_y) = points.fold((f64::MAX, f64::MIN), |(min, max), p| {
+        (min.min(p.1), max.max(p.1))
+    });
+
+    let unit = (width as f64 / (max_x - min_x)).min(height as f64 / (max_y - min_y));
+    let (net_w, net_h) = (
+        ((max_x - min_x) * unit).ceil() as usize,
+        ((max_y - min_y) * unit).ceil() as usize,
     );
 
-    f.render_widget(text, area);
+    let mut pixels = vec![vec![None; net_w]; net_h];
+
+    for (y, line) in pixels.iter_mut().enumerate() {
+        for (x, pixel) in line.iter_mut().enumerate() {
+            let point = (
+                min_x + (x as f64 + 0.5) / unit,
+                max_y - (y as f64 + 0.5) / unit,
+            );
+
+            *pixel = layout.iter().enumerate().find_map(|(face, corners)| {
+                let sticker = megaminx_sticker(point, corners)?;
+                Some(Color::from(
+                    megaminx.facelets[face * megaminx::STICKERS + sticker],
+                ))
+            });
+        }
+    }
+
+    pixels
+}
+
+/// Sticker of the face at the point, if it is inside the face and not in the
+/// gap around it.
+fn megaminx_sticker((x, y): (f64, f64), corners: &[(f64, f64); 5]) -> Option<usize> {
+    let center = corners
+        .iter()
+        .fold((0.0, 0.0), |acc, p| (acc.0 + p.0 / 5.0, acc.1 + p.1 / 5.0));
+
+    // distance to each side, positive on the inside
+    let distances: [f64; 5] = std::array::from_fn(|k| {
+        let (a, b) = (corners[k], corners[(k + 1) % 5]);
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let side = |(px, py): (f64, f64)| (dx * (py - a.1) - dy * (px - a.0)) / dx.hypot(dy);
+        side((x, y)) * side(center).signum()
+    });
+
+    let inradius = distances.iter().sum::<f64>() / 5.0;
+    let gap = MEGAMINX_NET_GAP * inradius;
+
+    if distances.iter().any(|&d| d < gap) {
+        return None;
+    }
+
+    let near = distances.map(|d| d < gap + MEGAMINX_NET_DEPTH * inradius);
+
+    Some(match (0..5).find(|&k| near[k]) {
+        None => 0,
+        // corners are near both sides around them and edges near one
+        Some(0) if near[4] => 1,
+        Some(k) if near[(k + 1) % 5] => 2 * k + 3,
+        Some(k) => 2 * k + 2,
+    })
+}
+
+/// Weights of the corners of the triangle that give the point, all of them
+/// positive if it is inside.
+fn barycentric((x, y): (f64, f64), [a, b, c]: [(f64, f64); 3]) -> [f64; 3] {
+    let det = (b.1 - c.1) * (a.0 - c.0) + (c.0 - b.0) * (a.1 - c.1);
+    let wa = ((b.1 - c.1) * (x - c.0) + (c.0 - b.0) * (y - c.1)) / det;
+    let wb = ((c.1 - a.1) * (x - c.0) + (a.0 - c.0) * (y - c.1)) / det;
+
+    [wa, wb, 1.0 - wa - wb]
+}
+
+/// Lays out the net of the skewb like the net of the cube, with faces as
+/// large as fit in the given size, in pixels half a cell tall.
+fn skewb_net(skewb: &Skewb, width: usize, height: usize) -> Vec<Vec<Option<Color>>> {
+    let size = (width.saturating_sub(3) / 4)
+        .min(height.saturating_sub(2) / 3)
+        .max(1);
+    let mut pixels = vec![vec![None; 4 * size + 3]; 3 * size + 2];
+
+    for (face, col, row) in NET_LAYOUT {
+        let (x0, y0) = (col * (size + 1), row * (size + 1));
+        let stickers = skewb.face(face);
+
+        for y in 0..size {
+            for x in 0..size {
+                let (u, v) = (
+                    (x as f64 + 0.5) / size as f64,
+                    (y as f64 + 0.5) / size as f64,
+                );
+                let distance = (u - 0.5).abs() + (v - 0.5).abs();
+
+                // leave a line around the center so that it stands out from
+                // the corners on larger faces
+                let sticker = if size > 4 && (distance - 0.5).abs() < 0.5 / size as f64 {
+                    None
+                } else if distance < 0.5 {
+                    Some(stickers[0])
+                } else {
+                    Some(stickers[1 + usize::from(u > 0.5) + 2 * usize::from(v > 0.5)])
+                };
+
+                pixels[y0 + y][x0 + x] = sticker.map(Color::from);
+            }
+        }
+    }
+
+    pixels
+}
+
+// half the width of the box each layer of the square-1 is drawn in, the depth
+// of the side stickers and the gap between pieces, in units of the distance
+// from the center to the side of a layer
+const SQUARE1_EXTENT: f64 = 1.4;
+const SQUARE1_SIDE_DEPTH: f64 = 0.25;
+const SQUARE1_GAP: f64 = 0.06;
+
+/// Rasterizes the top layer of the square-1 as seen from above next to the
+/// bottom layer as seen from below, with the middle layer underneath, as
+/// large as they fit in the given size, in pixels half a cell tall.
+fn square1_layers(square1: &Square1, width: usize, height: usize) -> Vec<Vec<Option<Color>>> {
+    let unit = (width.saturating_sub(2) as f64 / (4.0 * SQUARE1_EXTENT))
+        .min(height.saturating_sub(3) as f64 / (2.0 * SQUARE1_EXTENT));
+    let side = ((2.0 * SQUARE1_EXTENT * unit).floor() as usize).max(1);
+    let (net_w, net_h) = (2 * side + 2, side + 3);
+    // widen the gap to a pixel where it would otherwise not show, short of
+    // hiding the side stickers
+    let gap = SQUARE1_GAP.max(1.0 / unit).min(SQUARE1_SIDE_DEPTH);
+
+    let mut pixels = vec![vec![None; net_w]; net_h];
+
+    for (y, line) in pixels.iter_mut().take(side).enumerate() {
+        for (x, pixel) in line.iter_mut().enumerate() {
+            let top = x < side;
+            let x = if top { x } else { x.saturating_sub(side + 2) };
+            let point = (
+                (x as f64 + 0.5) / unit - SQUARE1_EXTENT,
+                SQUARE1_EXTENT - (y as f64 + 0.5) / unit,
+            );
+
+            // the bottom layer is turned over sideways, swapping its halves
+            let point = if top { point } else { (-point.0, point.1) };
+
+            *pixel = square1_sticker(square1, top, point, gap).map(Color::from);
+        }
+    }
+
+    // the left half of the middle layer never moves, while the right half
+    // shows its back when turned over
+    let right = if square1.middle_flipped {
+        Face::Back
+    } else {
+        Face::Front
+    };
+
+    for line in &mut pixels[side + 1..] {
+        line[..net_w / 2].fill(Some(Color::from(Face::Front)));
+        line[net_w / 2 + 1..].fill(Some(Color::from(right)));
+    }
+
+    pixels
+}
+
+/// Sticker of the layer at the point, seen from above with the slice running
+/// up and down the middle, if it is inside a piece and not in the gap around
+/// it.
+fn square1_sticker(square1: &Square1, top: bool, (x, y): (f64, f64), gap: f64) -> Option<Face> {
+    let radius = x.hypot(y);
+    // angle clockwise from the back end of the slice, which is tilted so
+    // that the sides of a layer in its square shape are upright
+    let angle = (x.atan2(y).to_degrees() - 15.0).rem_euclid(360.0);
+    let slot = (angle / 30.0) as usize % square1::SLOTS;
+
+    let (start, twelfths) = square1
+        .pieces(top)
+        .into_iter()
+        .find(|&(start, twelfths)| (slot + square1::SLOTS - start) % square1::SLOTS < twelfths)?;
+
+    // edges have a single side and corners one for each twelfth
+    let normal = match (twelfths, slot == start) {
+        (1, _) => start as f64 * 30.0 + 15.0,
+        (_, true) => start as f64 * 30.0 - 15.0,
+        (_, false) => start as f64 * 30.0 + 75.0,
+    };
+    let depth = radius * (angle - normal).to_radians().cos();
+
+    let from_start = (angle - start as f64 * 30.0).rem_euclid(360.0);
+    let to_end = twelfths as f64 * 30.0 - from_start;
+    let border = radius * from_start.min(to_end).to_radians().sin();
+
+    if depth > 1.0 - gap / 2.0 || border < gap / 2.0 {
+        return None;
+    }
+
+    let (face, side) = square1.sticker(top, slot);
+
+    Some(if depth > 1.0 - SQUARE1_SIDE_DEPTH {
+        side
+    } else {
+        face
+    })
+}
+
+// width of the hours of a clock dial and of the gap between dials, in cells
+const CLOCK_DIAL_WIDTH: usize = 2;
+const CLOCK_GAP_WIDTH: usize = 3;
+
+/// Lays out the dials of the front of the clock next to those of the back,
+/// each side as seen from its own face, with the pins between the dials.
+fn clock_faces(clock: &Clock) -> Vec<Line<'static>> {
+    let sides = [
+        ("Front", clock.front, clock.pins),
+        ("Back", clock.back, clock.back_pins()),
+    ];
+    let side_width = 3 * CLOCK_DIAL_WIDTH + 2 * CLOCK_GAP_WIDTH;
+
+    let mut lines = vec![Line::from(
+        sides
+            .iter()
+            .map(|(name, _, _)| {
+                Span::styled(
+                    format!("{name:<side_width$}{:CLOCK_GAP_WIDTH$}", ""),
+                    Style::default().add_modifier(Modifier::BOLD),
+                )
+            })
+            .collect::<Vec<Span>>(),
+    )];
+
+    for row in 0..5 {
+        let mut spans = Vec::new();
+
+        for (_, dials, pins) in &sides {
+            if row % 2 == 0 {
+                for col in 0..3 {
+                    let hours = dials[row / 2 * 3 + col];
+                    let style = if hours == 0 {
+                        Style::default().fg(Color::DarkGray)
+                    } else {
+                        Style::default().add_modifier(Modifier::BOLD)
+                    };
+
+                    spans.push(Span::styled(
+                        format!("{:>CLOCK_DIAL_WIDTH$}", if hours == 0 { 12 } else { hours }),
+                        style,
+                    ));
+                    spans.push(Span::raw(" ".repeat(CLOCK_GAP_WIDTH)));
+                }
+            } else {
+                spans.push(Span::raw(" ".repeat(CLOCK_DIAL_WIDTH)));
+
+                for col in 0..2 {
+                    let up = pins[row / 2 * 2 + col];
+                    let (symbol, color) = if up {
+                        ("●", Color::Yellow)
+                    } else {
+                        ("○", Color::DarkGray)
+                    };
+
+                    spans.push(Span::raw(" ".repeat(CLOCK_GAP_WIDTH / 2)));
+                    spans.push(Span::styled(symbol, Style::default().fg(color)));
+                    spans.push(Span::raw(" ".repeat(
+                        CLOCK_GAP_WIDTH - CLOCK_GAP_WIDTH / 2 - 1 + CLOCK_DIAL_WIDTH,
+                    )));
+                }
+
+                spans.push(Span::raw(" ".repeat(CLOCK_GAP_WIDTH)));
+            }
+        }
+
+        lines.push(Line::from(spans));
+    }
+
+    lines
+}
+
+fn render_preview(f: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title(Span::styled(
+        "Scramble Preview",
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    ));
+    let net_area = block.inner(area);
+    f.render_widget(block, area);
+
+    let (width, height) = (net_area.width as usize, net_area.height as usize * 2);
+
+    let net = match &app.preview {
+        Preview::Cube(cube) => cube_net(cube, width, height),
+        Preview::Pyraminx(pyraminx) => pyraminx_net(pyraminx, width, height),
+        Preview::Megaminx(megaminx) => megaminx_net(megaminx, width, height),
+        Preview::Skewb(skewb) => skewb_net(skewb, width, height),
+        Preview::Square1(square1) => square1_layers(square1, width, height),
+        // dials are drawn as their hours, which read better than any hand
+        // drawn in a few pixels
+        Preview::Clock(clock) => {
+            f.render_widget(Paragraph::new(clock_faces(clock)), net_area);
+            return;
+        }
+    };
+
+    f.render_widget(Paragraph::new(pixels_to_lines(&net)), net_area);
 }
 
 fn render_confirmation_window(f: &mut Frame, text: &str) {
@@ -423,6 +1197,766 @@ fn render_confirmation_window(f: &mut Frame, text: &str) {
     f.render_widget(no, no_area);
 }
 
+/// Shows the spread of the times of the session next to a histogram of them.
+fn render_histogram_window(f: &mut Frame, app: &App) {
+    let window = popup_window_from_percentage(80, 80, f.area());
+    f.render_widget(Clear, window);
+
+    let settings = app.settings();
+    let distribution = Distribution::new(
+        app.get_solves(),
+        &settings.thresholds,
+        settings.bucket_width,
+    );
+
+    let block = Block::default()
+        .title(Span::styled(
+            format!(
+                "Distribution [{}s buckets]",
+                millis_to_seconds_string(settings.bucket_width)
+            ),
+            Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        ))
+        .padding(Padding::symmetric(2, 1))
+        .borders(Borders::ALL);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(block.inner(window));
+    f.render_widget(block, window);
+
+    let hint = Paragraph::new(Span::styled(
+        "+/- to change the bucket width, x to set the sub-X thresholds",
+        Style::default().fg(Color::DarkGray),
+    ))
+    .alignment(Alignment::Center);
+    f.render_widget(hint, chunks[1]);
+
+    let Some(distribution) = distribution else {
+        let text = Paragraph::new("No solves yet").alignment(Alignment::Center);
+        f.render_widget(text, center_vertically("No solves yet", chunks[0]));
+        return;
+    };
+
+    let mut fields = vec![
+        (String::from("Solves:"), distribution.count.to_string()),
+        (
+            String::from("Mean:"),
+            millis_to_string_not_running(distribution.mean),
+        ),
+        (
+            String::from("Std dev:"),
+            millis_to_string_not_running(distribution.std_dev),
+        ),
+        (
+            String::from("Median:"),
+            millis_to_string_not_running(distribution.median),
+        ),
+        (
+            String::from("p10:"),
+            millis_to_string_not_running(distribution.p10),
+        ),
+        (
+            String::from("p90:"),
+            millis_to_string_not_running(distribution.p90),
+        ),
+    ];
+    fields.extend(distribution.subs.iter().map(|(threshold, count)| {
+        (
+            format!("Sub-{}:", millis_to_seconds_string(*threshold)),
+            format!(
+                "{count} ({:.1}%)",
+                *count as f64 * 100.0 / distribution.count as f64
+            ),
+        )
+    }));
+
+    let width = |column: fn(&(String, String)) -> &String| {
+        fields
+            .iter()
+            .map(|field| column(field).len())
+            .max()
+            .unwrap_or(0) as u16
+    };
+    let widths = [width(|(field, _)| field), width(|(_, value)| value)];
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(widths[0] + widths[1] + 4),
+            Constraint::Min(1),
+        ])
+        .split(chunks[0]);
+
+    let rows = fields
+        .iter()
+        .map(|(field, value)| info_window_row(field, value))
+        .collect::<Vec<Row>>();
+    let table = Table::new(rows, widths.map(Constraint::Length));
+    f.render_widget(table, chunks[0]);
+
+    let labels = distribution
+        .buckets
+        .iter()
+        .map(|(start, _)| millis_to_seconds_string(*start))
+        .collect::<Vec<String>>();
+    let bars = distribution
+        .buckets
+        .iter()
+        .zip(&labels)
+        .map(|((_, count), label)| {
+            Bar::default()
+                .value(*count)
+                .label(Line::from(label.as_str()))
+                .style(Style::default().fg(Color::Yellow))
+                .value_style(Style::default().fg(Color::Black).bg(Color::Yellow))
+        })
+        .collect::<Vec<Bar>>();
+
+    // the slowest buckets are left out when they don't all fit
+    let histogram = BarChart::default()
+        .direction(Direction::Horizontal)
+        .bar_width(1)
+        .bar_gap(0)
+        .data(BarGroup::default().bars(&bars));
+    f.render_widget(histogram, chunks[1]);
+}
+
+/// Shows the stats of all the sessions of the puzzle together, then those of
+/// each session.
+fn render_global_stats(f: &mut Frame, app: &App, global: &GlobalStats, area: Rect) {
+    let title_style = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let header_style = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+
+    let kinds = StatKind::all(global.stats.len()).collect::<Vec<StatKind>>();
+    let names = kinds
+        .iter()
+        .map(|&kind| match kind {
+            StatKind::Single => String::from("single"),
+            StatKind::Tracked(idx) => global.stats[idx].to_string(),
+        })
+        .collect::<Vec<String>>();
+
+    let chunks = Layout::default()
+        .constraints([
+            Constraint::Length(kinds.len() as u16 + 6),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .direction(Direction::Vertical)
+        .split(area);
+
+    let mut totals = vec![
+        (
+            String::from("Sessions:"),
+            global.sessions.len().to_string(),
+            String::new(),
+        ),
+        (
+            String::from("Solves:"),
+            format!("{}/{}", global.valid_solve_count(), global.solve_count()),
+            String::new(),
+        ),
+        (
+            String::from("Practice time:"),
+            duration_to_string(global.practice_time()),
+            String::new(),
+        ),
+        (String::new(), String::new(), String::new()),
+    ];
+    totals.extend(kinds.iter().zip(&names).map(|(&kind, name)| {
+        let (best, session) = global
+            .best(kind)
+            .map_or((String::from("-"), ""), |(best, session)| {
+                (best.to_string(), session)
+            });
+
+        (format!("Best {name}:"), best, session.to_string())
+    }));
+
+    let totals = Table::new(
+        totals
+            .iter()
+            .map(|(field, value, session)| {
+                Row::new(vec![
+                    Text::styled(field.as_str(), header_style),
+                    Text::from(value.as_str()),
+                    Text::styled(session.as_str(), Style::default().fg(Color::DarkGray)),
+                ])
+            })
+            .collect::<Vec<Row>>(),
+        [
+            Constraint::Length(16),
+            Constraint::Length(14),
+            Constraint::Fill(1),
+        ],
+    )
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .padding(Padding::horizontal(1))
+            .title(Span::styled(
+                format!("Global Stats [{}]", app.session.puzzle),
+                title_style,
+            )),
+    );
+    f.render_widget(totals, chunks[0]);
+
+    let rows = global
+        .sessions
+        .iter()
+        .map(|session| {
+            let mut row = vec![
+                session.name.clone(),
+                format!("{}/{}", session.valid_solve_count, session.solve_count),
+                duration_to_string(session.practice_time),
+            ];
+            row.extend(
+                session
+                    .bests
+                    .iter()
+                    .map(|best| best.map_or(String::from("-"), |best| best.to_string())),
+            );
+
+            Row::new(row)
+        })
+        .collect::<Vec<Row>>();
+
+    let mut header = vec![
+        String::from("Session"),
+        String::from("Solves"),
+        String::from("Practice"),
+    ];
+    header.extend(names.iter().map(|name| format!("Best {name}")));
+
+    let mut widths = vec![
+        Constraint::Fill(2),
+        Constraint::Fill(1),
+        Constraint::Fill(1),
+    ];
+    widths.extend(kinds.iter().map(|_| Constraint::Fill(1)));
+
+    let sessions = Table::new(rows, widths)
+        .header(Row::new(header).style(header_style))
+        .column_spacing(2)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .padding(Padding::horizontal(1))
+                .title(Span::styled("Sessions", title_style)),
+        );
+    f.render_widget(sessions, chunks[1]);
+
+    let footer = if global.unreadable.is_empty() {
+        Span::styled("o or Esc to go back", Style::default().fg(Color::DarkGray))
+    } else {
+        Span::styled(
+            format!(
+                "Left out as their history couldn't be read: {}",
+                global.unreadable.join(", ")
+            ),
+            Style::default().fg(Color::Red),
+        )
+    };
+    f.render_widget(
+        Paragraph::new(footer).alignment(Alignment::Center),
+        chunks[2],
+    );
+}
+
+/// Heatmap of the solves done each day, one column per week, with the
+/// practice of the selected day and of its week.
+fn render_calendar(f: &mut Frame, app: &App, calendar: &Calendar, area: Rect) {
+    let title_style = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let label_style = Style::default().fg(Color::DarkGray);
+    let today = Local::now().date_naive();
+
+    let chunks = Layout::default()
+        .constraints([
+            Constraint::Length(12),
+            Constraint::Length(7),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .direction(Direction::Vertical)
+        .split(area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .padding(Padding::horizontal(1))
+        .title(Span::styled(
+            format!("Calendar [{}]", app.session.puzzle),
+            title_style,
+        ));
+    let inner = block.inner(chunks[0]);
+    f.render_widget(block, chunks[0]);
+
+    // the last week shown is the current one, unless the selected day is
+    // further back than the heatmap is wide
+    let weeks = (inner.width.saturating_sub(4) / 2).max(1) as i64;
+    let selected_week = app.calendar_day.week(Weekday::Mon).first_day();
+    let last_week = today
+        .week(Weekday::Mon)
+        .first_day()
+        .min(selected_week + TimeDelta::weeks(weeks - 1));
+    let first_week = last_week - TimeDelta::weeks(weeks - 1);
+    let max_solve_count = calendar.max_solve_count();
+
+    let mut months = vec![' '; 4 + 2 * weeks as usize];
+    let mut months_end = 0;
+    for week in 0..weeks {
+        let monday = first_week + TimeDelta::weeks(week);
+        let sunday = monday + TimeDelta::days(6);
+        let x = 4 + 2 * week as usize;
+
+        // the first week is labelled unless the next month starts too soon
+        let starts_month = sunday.day() <= 7
+            || week == 0 && (sunday + TimeDelta::weeks(2)).month() == sunday.month();
+
+        if starts_month && x >= months_end {
+            let name = sunday.format("%b").to_string();
+            for (idx, c) in name.chars().enumerate() {
+                if let Some(slot) = months.get_mut(x + idx) {
+                    *slot = c;
+                }
+            }
+            months_end = x + name.len() + 1;
+        }
+    }
+
+    let mut lines = vec![Line::styled(
+        months.into_iter().collect::<String>(),
+        label_style,
+    )];
+    for weekday in 0..7 {
+        let label = match weekday {
+            0 => "Mon",
+            2 => "Wed",
+            4 => "Fri",
+            _ => "",
+        };
+        let mut spans = vec![Span::styled(format!("{label:<4}"), label_style)];
+
+        for week in 0..weeks {
+            let date = first_week + TimeDelta::weeks(week) + TimeDelta::days(weekday);
+            if date > today {
+                break;
+            }
+
+            let level = calendar_level(calendar.day(date).solve_count, max_solve_count);
+            let mut style = Style::default().fg(CALENDAR_COLORS[level]);
+            if date == app.calendar_day {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+
+            spans.extend([Span::styled("■", style), Span::raw(" ")]);
+        }
+
+        lines.push(Line::from(spans));
+    }
+
+    let mut legend = vec![Span::styled("Less ", label_style)];
+    legend.extend(
+        CALENDAR_COLORS
+            .iter()
+            .map(|&color| Span::styled("■ ", Style::default().fg(color))),
+    );
+    legend.push(Span::styled("More", label_style));
+    lines.extend([
+        Line::default(),
+        Line::from(legend).alignment(Alignment::Right),
+    ]);
+
+    f.render_widget(Paragraph::new(lines), inner);
+
+    let summary_chunks = Layout::default()
+        .constraints([
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+        ])
+        .direction(Direction::Horizontal)
+        .split(chunks[1]);
+
+    let week = app.calendar_day.week(Weekday::Mon);
+    let week_days = week
+        .first_day()
+        .iter_days()
+        .take(7)
+        .filter(|&date| calendar.day(date).solve_count > 0)
+        .count();
+
+    render_calendar_fields(
+        f,
+        &app.calendar_day.format("%a %Y-%m-%d").to_string(),
+        &practice_fields(&calendar.day(app.calendar_day)),
+        summary_chunks[0],
+    );
+
+    let mut week_fields = practice_fields(&calendar.week(app.calendar_day));
+    week_fields.push((String::from("Practice days:"), format!("{week_days}/7")));
+    render_calendar_fields(
+        f,
+        &format!("Week of {}", week.first_day().format("%Y-%m-%d")),
+        &week_fields,
+        summary_chunks[1],
+    );
+
+    render_calendar_fields(
+        f,
+        "Streaks",
+        &[
+            (
+                String::from("Current streak:"),
+                days_to_string(calendar.current_streak(today)),
+            ),
+            (
+                String::from("Longest streak:"),
+                days_to_string(calendar.longest_streak()),
+            ),
+            (
+                String::from("Practice days:"),
+                calendar.practice_days().to_string(),
+            ),
+        ],
+        summary_chunks[2],
+    );
+
+    let footer = if calendar.unreadable.is_empty() {
+        Span::styled(
+            "h/l week  j/k day  g today  m or Esc to go back",
+            Style::default().fg(Color::DarkGray),
+        )
+    } else {
+        Span::styled(
+            format!(
+                "Left out as their history couldn't be read: {}",
+                calendar.unreadable.join(", ")
+            ),
+            Style::default().fg(Color::Red),
+        )
+    };
+    f.render_widget(
+        Paragraph::new(footer).alignment(Alignment::Center),
+        chunks[3],
+    );
+}
+
+/// Index in `CALENDAR_COLORS` of a day with `count` solves, where no day has
+/// more than `max` of them.
+fn calendar_level(count: u128, max: u128) -> usize {
+    if count == 0 {
+        return 0;
+    }
+
+    (count * 4).div_ceil(max).clamp(1, 4) as usize
+}
+
+fn practice_fields(practice: &Practice) -> Vec<(String, String)> {
+    vec![
+        (
+            String::from("Solves:"),
+            format!("{}/{}", practice.valid_solve_count, practice.solve_count),
+        ),
+        (
+            String::from("Practice time:"),
+            duration_to_string(practice.practice_time),
+        ),
+        (
+            String::from("Mean:"),
+            practice
+                .mean()
+                .map_or(String::from("-"), millis_to_string_not_running),
+        ),
+        (
+            String::from("Best:"),
+            practice
+                .best
+                .map_or(String::from("-"), |best| best.to_string()),
+        ),
+    ]
+}
+
+fn render_calendar_fields(f: &mut Frame, title: &str, fields: &[(String, String)], area: Rect) {
+    let header_style = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+
+    let table = Table::new(
+        fields
+            .iter()
+            .map(|(field, value)| {
+                Row::new(vec![
+                    Text::styled(field.as_str(), header_style),
+                    Text::from(value.as_str()),
+                ])
+            })
+            .collect::<Vec<Row>>(),
+        [Constraint::Length(16), Constraint::Fill(1)],
+    )
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .padding(Padding::horizontal(1))
+            .title(Span::styled(title, Style::default().fg(Color::Cyan))),
+    );
+    f.render_widget(table, area);
+}
+
+fn days_to_string(days: usize) -> String {
+    if days == 1 {
+        String::from("1 day")
+    } else {
+        format!("{days} days")
+    }
+}
+
+/// Duration in hours, minutes and seconds, like `3h 05m 12s`.
+fn duration_to_string(time: u128) -> String {
+    let seconds = time / 1000;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    if hours > 0 {
+        format!("{hours}h {minutes:02}m {seconds:02}s")
+    } else {
+        format!("{minutes}m {seconds:02}s")
+    }
+}
+
+/// Lists the PBs set for a line of the stats, the latest first.
+fn render_pb_history_window(f: &mut Frame, app: &App) {
+    let window = popup_window_from_percentage(60, 80, f.area());
+    f.render_widget(Clear, window);
+
+    let block = Block::default()
+        .title(Span::styled(
+            "PB History",
+            Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        ))
+        .padding(Padding::symmetric(2, 1))
+        .borders(Borders::ALL);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .split(block.inner(window));
+    f.render_widget(block, window);
+
+    let kinds = StatKind::all(app.session.tracked.len()).collect::<Vec<StatKind>>();
+    let tabs = Tabs::new(kinds.iter().map(|&kind| app.stat_name(kind)))
+        .select(
+            kinds
+                .iter()
+                .position(|&kind| kind == app.pb_history_kind)
+                .unwrap_or(0),
+        )
+        .highlight_style(Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD));
+    f.render_widget(tabs, chunks[0]);
+
+    let rows = stats::pb_history(app.get_solves(), app.pb_history_kind)
+        .iter()
+        .rev()
+        .map(|event| {
+            let date = Local
+                .timestamp_opt(event.date as i64, 0)
+                .unwrap()
+                .format("%Y-%m-%d %H:%M")
+                .to_string();
+            let (old, gain) = match event.old {
+                Some(old) => (
+                    old.to_string(),
+                    format!(
+                        "-{}",
+                        millis_to_string_not_running(old.time - event.new.time)
+                    ),
+                ),
+                None => (String::from("-"), String::from("-")),
+            };
+
+            Row::new(vec![
+                format!("{}.", event.solve + 1),
+                date,
+                event.new.to_string(),
+                old,
+                gain,
+            ])
+        })
+        .collect::<Vec<Row>>();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(7),
+            Constraint::Length(16),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(
+        Row::new(vec!["", "Date", "PB", "Previous", "Gain"]).style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ),
+    )
+    .column_spacing(2);
+    f.render_widget(table, chunks[1]);
+
+    let hint = Paragraph::new(Span::styled(
+        "h/l to switch stats",
+        Style::default().fg(Color::DarkGray),
+    ))
+    .alignment(Alignment::Center);
+    f.render_widget(hint, chunks[2]);
+}
+
+/// Lists the solves of the current or best value of a tracked stat, as text
+/// that can be copied to share it.
+fn render_average_detail_window(f: &mut Frame, app: &App) {
+    let window = popup_window_from_percentage(80, 80, f.area());
+    f.render_widget(Clear, window);
+
+    let block = Block::default()
+        .title(Span::styled(
+            "Average Detail",
+            Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        ))
+        .padding(Padding::symmetric(2, 1))
+        .borders(Borders::ALL);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Length(2),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .split(block.inner(window));
+    f.render_widget(block, window);
+
+    let highlight = Style::new().fg(Color::Magenta).add_modifier(Modifier::BOLD);
+    let stats = Tabs::new(
+        app.session
+            .tracked
+            .iter()
+            .map(|tracked| tracked.stat.to_string()),
+    )
+    .select(app.detail_stat)
+    .highlight_style(highlight);
+    let values = Tabs::new(["current", "best"])
+        .select(usize::from(app.detail_best))
+        .highlight_style(highlight);
+    f.render_widget(stats, chunks[0]);
+    f.render_widget(values, chunks[1]);
+
+    let stat = app.session.tracked[app.detail_stat].stat;
+    let line = &app.get_stats().averages[app.detail_stat];
+    let (value, end) = if app.detail_best {
+        (line.best(), line.best_solve())
+    } else {
+        (line.current(), app.get_solves().len().checked_sub(1))
+    };
+
+    let report = value
+        .zip(end)
+        .and_then(|(value, end)| stats::window_report(stat, value, app.get_solves(), end))
+        .unwrap_or_else(|| String::from("Not enough solves yet"));
+
+    f.render_widget(Paragraph::new(report).wrap(Wrap { trim: false }), chunks[2]);
+
+    let hint = Paragraph::new(Span::styled(
+        "h/l to switch stats, j/k to switch between current and best",
+        Style::default().fg(Color::DarkGray),
+    ))
+    .alignment(Alignment::Center);
+    f.render_widget(hint, chunks[3]);
+}
+
+fn render_input_window(f: &mut Frame, input: &Input) {
+    let window = popup_window_from_dimensions(5, 60, f.area());
+    f.render_widget(Clear, window);
+
+    let (title, hint) = match input.prompt {
+        Prompt::Rename => ("Rename Session", "Enter to save, Esc to cancel"),
+        Prompt::Stats => (
+            "Session Stats",
+            "e.g. mo3 ao5* ao12* ao100, * for a solves column",
+        ),
+        Prompt::Thresholds => ("Sub-X Thresholds", "in seconds, e.g. 10 15 20 1:30"),
+        Prompt::Import => (
+            "Import Sessions",
+            "path of a csTimer .json or Twisty Timer .csv export",
+        ),
+        Prompt::Export => (
+            "Export Session",
+            ".json for csTimer, .csv for Twisty Timer, .txt for a report",
+        ),
+    };
+
+    let block = Block::default().borders(Borders::ALL).title(Span::styled(
+        title,
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    ));
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(block.inner(window));
+    f.render_widget(block, window);
+
+    let text = Paragraph::new(Line::from(vec![
+        Span::raw(input.text.clone()),
+        Span::styled("_", Style::default().add_modifier(Modifier::SLOW_BLINK)),
+    ]));
+
+    let hint = Paragraph::new(Span::styled(hint, Style::default().fg(Color::DarkGray)))
+        .alignment(Alignment::Center);
+
+    f.render_widget(text, chunks[0]);
+    f.render_widget(hint, chunks[1]);
+}
+
+fn render_message_window(f: &mut Frame, title: &str, color: Color, text: &str) {
+    let window = popup_window_from_percentage(50, 20, f.area());
+    f.render_widget(Clear, window);
+
+    let block = Block::default().borders(Borders::ALL).title(Span::styled(
+        title,
+        Style::default().fg(color).add_modifier(Modifier::BOLD),
+    ));
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(block.inner(window));
+    f.render_widget(block, window);
+
+    let mut text = Paragraph::new(Text::from(text)).alignment(Alignment::Center);
+    // program crashes if width is 0 and wrap is enabled
+    if chunks[0].width > 0 {
+        text = text.wrap(Wrap { trim: true });
+    }
+
+    let hint = Paragraph::new(Span::styled(
+        "Press any key to dismiss",
+        Style::default().fg(Color::DarkGray),
+    ))
+    .alignment(Alignment::Center);
+
+    f.render_widget(text, chunks[0]);
+    f.render_widget(hint, chunks[1]);
+}
+
 pub fn popup_window_from_dimensions(height: u16, width: u16, r: Rect) -> Rect {
     let hor = [Constraint::Length(width)];
     let ver = [Constraint::Length(height)];
@@ -447,6 +1981,28 @@ fn popup_window(hor_constraints: &[Constraint], ver_constraints: &[Constraint],
         .areas::<1>(popup_layout)[0]
 }
 
+/// Splits text into lines no longer than `width`, breaking only between words.
+fn wrap_words(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    // line breaks in the text are kept, as in megaminx scramble lines
+    for text_line in text.lines() {
+        let start = lines.len();
+
+        for word in text_line.split_whitespace() {
+            match lines[start..].last_mut() {
+                Some(line) if line.len() + 1 + word.len() <= width => {
+                    line.push(' ');
+                    line.push_str(word);
+                }
+                _ => lines.push(word.to_string()),
+            }
+        }
+    }
+
+    lines
+}
+
 fn center_vertically(text: &str, area: Rect) -> Rect {
     let line_count = text.lines().count() as u16;
 